@@ -1,23 +1,112 @@
-use crate::markdown::RenderedDocument;
+use crate::markdown::{RenderedDocument, SourceSpan};
 
 const AGENT_TAG: &str = "@agent";
+const DONE_STAMP_PREFIX: &str = " (done ";
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum AgentTaskState {
     Open,
+    InProgress,
     Done,
 }
 
+impl AgentTaskState {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::InProgress => "in-progress",
+            Self::Done => "done",
+        }
+    }
+}
+
+/// Inbox ordering for unresolved tasks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum AgentSort {
+    #[default]
+    Document,
+    Priority,
+    Due,
+}
+
+impl AgentSort {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::Document => Self::Priority,
+            Self::Priority => Self::Due,
+            Self::Due => Self::Document,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Document => "document",
+            Self::Priority => "priority",
+            Self::Due => "due",
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct AgentTask {
     pub(crate) line: usize,
+    pub(crate) source: Option<SourceSpan>,
     pub(crate) text: String,
     pub(crate) state: AgentTaskState,
+    /// Addressees from `@agent:<name>` tags, lowercased.
+    pub(crate) owners: Vec<String>,
+    /// `!p1` is most urgent.
+    pub(crate) priority: Option<u8>,
+    /// `due:YYYY-MM-DD`
+    pub(crate) due: Option<String>,
+    /// Stable identifier such as `#T-12`, without the `#`.
+    pub(crate) id: Option<String>,
 }
 
 impl AgentTask {
+    /// Unresolved tasks: open or in progress.
     pub(crate) fn is_open(&self) -> bool {
-        matches!(self.state, AgentTaskState::Open)
+        matches!(
+            self.state,
+            AgentTaskState::Open | AgentTaskState::InProgress
+        )
+    }
+
+    /// Task text with the `@agent` tags and metadata tokens removed.
+    pub(crate) fn summary(&self) -> String {
+        self.text
+            .split_whitespace()
+            .filter(|token| {
+                let lower = token.to_ascii_lowercase();
+                !(lower == AGENT_TAG
+                    || lower.starts_with("@agent:")
+                    || parse_priority(token).is_some()
+                    || parse_due(token).is_some()
+                    || parse_id(token).is_some())
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Compact metadata badges, e.g. `P1 #T-12 due:2026-11-01 @claude`.
+    pub(crate) fn badges(&self) -> String {
+        let mut badges = Vec::new();
+        if let Some(priority) = self.priority {
+            badges.push(format!("P{priority}"));
+        }
+        if let Some(id) = &self.id {
+            badges.push(format!("#{id}"));
+        }
+        if let Some(due) = &self.due {
+            badges.push(format!("due:{due}"));
+        }
+        badges.extend(self.owners.iter().map(|owner| format!("@{owner}")));
+        badges.join(" ")
+    }
+
+    /// 0-based markdown source line of the task's checkbox.
+    pub(crate) fn source_line(&self) -> Option<usize> {
+        self.source.as_ref().map(|span| span.start_line)
     }
 }
 
@@ -40,11 +129,67 @@ fn contains_agent_tag(text: &str) -> bool {
     false
 }
 
+fn trim_token(token: &str) -> &str {
+    token.trim_end_matches([',', ';', '.', ')', ':'])
+}
+
+fn parse_owner(token: &str) -> Option<String> {
+    let token = trim_token(token);
+    let prefix = token.get(..AGENT_TAG.len() + 1)?;
+    if !prefix.eq_ignore_ascii_case("@agent:") {
+        return None;
+    }
+    let name = &token[prefix.len()..];
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    valid.then(|| name.to_ascii_lowercase())
+}
+
+fn parse_priority(token: &str) -> Option<u8> {
+    let token = trim_token(token);
+    let digits = token
+        .strip_prefix("!p")
+        .or_else(|| token.strip_prefix("!P"))?;
+    if digits.len() == 1 {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn parse_due(token: &str) -> Option<String> {
+    let date = trim_token(token).strip_prefix("due:")?;
+    let bytes = date.as_bytes();
+    let valid = bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(idx, byte)| idx == 4 || idx == 7 || byte.is_ascii_digit());
+    valid.then(|| date.to_string())
+}
+
+fn parse_id(token: &str) -> Option<String> {
+    let id = trim_token(token).strip_prefix('#')?;
+    let valid = id.chars().next().is_some_and(|ch| ch.is_ascii_alphabetic())
+        && id.chars().any(|ch| ch.is_ascii_digit())
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    valid.then(|| id.to_string())
+}
+
 fn parse_agent_task_line(line: &str) -> Option<(AgentTaskState, &str)> {
     let trimmed = line.trim_start();
     if let Some(body) = trimmed.strip_prefix("- [ ] ") {
         return Some((AgentTaskState::Open, body));
     }
+    if let Some(body) = trimmed.strip_prefix("- [~] ") {
+        return Some((AgentTaskState::InProgress, body));
+    }
     if let Some(body) = trimmed
         .strip_prefix("- [x] ")
         .or_else(|| trimmed.strip_prefix("- [X] "))
@@ -54,6 +199,132 @@ fn parse_agent_task_line(line: &str) -> Option<(AgentTaskState, &str)> {
     None
 }
 
+/// In-place change to a task's markdown source.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum TaskEdit<'a> {
+    /// Flip `[ ]` / `[~]` and `[x]`. Completing appends ` (done <date>)` when a
+    /// stamp is given; reopening strips an existing stamp.
+    Toggle {
+        done_stamp: Option<&'a str>,
+    },
+    Delete,
+}
+
+/// Byte offset of the character inside a list item's `[ ]` / `[x]` box,
+/// allowing for indentation, blockquote markers and ordered bullets.
+fn checkbox_offset(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut idx = 0;
+    loop {
+        while idx < bytes.len() && (bytes[idx] == b' ' || bytes[idx] == b'\t') {
+            idx += 1;
+        }
+        if bytes.get(idx) == Some(&b'>') {
+            idx += 1;
+            continue;
+        }
+        break;
+    }
+
+    match bytes.get(idx)? {
+        b'-' | b'*' | b'+' => idx += 1,
+        b'0'..=b'9' => {
+            while bytes.get(idx).is_some_and(u8::is_ascii_digit) {
+                idx += 1;
+            }
+            if !matches!(bytes.get(idx), Some(b'.' | b')')) {
+                return None;
+            }
+            idx += 1;
+        }
+        _ => return None,
+    }
+
+    if !matches!(bytes.get(idx), Some(b' ' | b'\t')) {
+        return None;
+    }
+    while matches!(bytes.get(idx), Some(b' ' | b'\t')) {
+        idx += 1;
+    }
+
+    let marker = bytes.get(idx..idx + 3)?;
+    if marker[0] == b'[' && marker[2] == b']' && matches!(marker[1], b' ' | b'x' | b'X' | b'~') {
+        Some(idx + 1)
+    } else {
+        None
+    }
+}
+
+/// Splits `line` into its text and its `\n` / `\r\n` ending.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    match line.strip_suffix("\r\n") {
+        Some(body) => (body, "\r\n"),
+        None => match line.strip_suffix('\n') {
+            Some(body) => (body, "\n"),
+            None => (line, ""),
+        },
+    }
+}
+
+/// Appends ` (done <stamp>)` to the text of `line`, or strips an existing
+/// stamp when `stamp` is `None`.
+fn restamp_line(line: &str, stamp: Option<&str>) -> String {
+    let (body, ending) = split_line_ending(line);
+    let trimmed = body.trim_end();
+    match stamp {
+        Some(stamp) => format!("{trimmed}{DONE_STAMP_PREFIX}{stamp}){ending}"),
+        None => match trimmed
+            .rfind(DONE_STAMP_PREFIX)
+            .filter(|_| trimmed.ends_with(')'))
+        {
+            Some(idx) => format!("{}{ending}", &trimmed[..idx]),
+            None => line.to_string(),
+        },
+    }
+}
+
+/// Applies `edit` to the task whose checkbox sits on 0-based `start_line`
+/// and whose text continues through `end_line`. Returns `None` when that
+/// line is not a checklist item, so stale line numbers never clobber
+/// unrelated content. Done stamps go after the task's last line.
+pub(crate) fn edit_task_in_source(
+    source: &str,
+    start_line: usize,
+    end_line: usize,
+    edit: TaskEdit<'_>,
+) -> Option<String> {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let target = *lines.get(start_line)?;
+    let offset = checkbox_offset(target)?;
+    let completing = !matches!(target.as_bytes()[offset], b'x' | b'X');
+    let last_line = end_line.clamp(start_line, lines.len() - 1);
+
+    let mut out = String::with_capacity(source.len() + 20);
+    for (idx, line) in lines.iter().enumerate() {
+        if !(start_line..=last_line).contains(&idx) {
+            out.push_str(line);
+            continue;
+        }
+        let TaskEdit::Toggle { done_stamp } = edit else {
+            continue;
+        };
+        let mut line = line.to_string();
+        if idx == start_line {
+            line.replace_range(offset..offset + 1, if completing { "x" } else { " " });
+        }
+        if completing {
+            if idx == last_line && done_stamp.is_some() {
+                line = restamp_line(&line, done_stamp);
+            }
+        } else if idx == start_line || idx == last_line {
+            // Older stamps sit on the checkbox line; clear them there too.
+            line = restamp_line(&line, None);
+        }
+        out.push_str(&line);
+    }
+    Some(out)
+}
+
 pub(crate) fn extract_agent_tasks(rendered: &RenderedDocument) -> Vec<AgentTask> {
     rendered
         .lines
@@ -61,18 +332,82 @@ pub(crate) fn extract_agent_tasks(rendered: &RenderedDocument) -> Vec<AgentTask>
         .enumerate()
         .filter_map(|(line, rendered_line)| {
             let (state, body) = parse_agent_task_line(&rendered_line.plain)?;
-            if !contains_agent_tag(body) {
+            // Tasks wrapped at the render width continue on the following lines.
+            let mut body = body.to_string();
+            for continuation in rendered.lines[line + 1..]
+                .iter()
+                .take_while(|next| next.wrapped)
+            {
+                body.push(' ');
+                body.push_str(continuation.plain.trim());
+            }
+            if !contains_agent_tag(&body) {
                 return None;
             }
-            Some(AgentTask {
+            let mut task = AgentTask {
                 line,
+                source: rendered_line.source.clone(),
                 text: body.trim().to_string(),
                 state,
-            })
+                owners: Vec::new(),
+                priority: None,
+                due: None,
+                id: None,
+            };
+            for token in body.split_whitespace() {
+                if let Some(owner) = parse_owner(token) {
+                    if !task.owners.contains(&owner) {
+                        task.owners.push(owner);
+                    }
+                } else if let Some(priority) = parse_priority(token) {
+                    task.priority.get_or_insert(priority);
+                } else if let Some(due) = parse_due(token) {
+                    task.due.get_or_insert(due);
+                } else if let Some(id) = parse_id(token) {
+                    task.id.get_or_insert(id);
+                }
+            }
+            Some(task)
         })
         .collect()
 }
 
+/// Indices of unresolved tasks for the Agent Inbox, optionally restricted to
+/// one addressee and ordered by `sort`. Tasks without a priority or due date
+/// sort after those that have one; ties keep document order.
+pub(crate) fn inbox_order(tasks: &[AgentTask], sort: AgentSort, owner: Option<&str>) -> Vec<usize> {
+    let mut order: Vec<usize> = tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, task)| task.is_open())
+        .filter(|(_, task)| owner.is_none_or(|name| task.owners.iter().any(|o| o == name)))
+        .map(|(idx, _)| idx)
+        .collect();
+
+    match sort {
+        AgentSort::Document => {}
+        AgentSort::Priority => order.sort_by_key(|idx| tasks[*idx].priority.unwrap_or(u8::MAX)),
+        AgentSort::Due => order.sort_by(|a, b| match (&tasks[*a].due, &tasks[*b].due) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }),
+    }
+    order
+}
+
+/// Distinct `@agent:<name>` addressees in document order.
+pub(crate) fn agent_owners(tasks: &[AgentTask]) -> Vec<String> {
+    let mut owners: Vec<String> = Vec::new();
+    for owner in tasks.iter().flat_map(|task| task.owners.iter()) {
+        if !owners.contains(owner) {
+            owners.push(owner.clone());
+        }
+    }
+    owners
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,10 +423,16 @@ mod tests {
                         style: Default::default(),
                     }],
                     plain: (*line).to_string(),
+                    source: None,
+                    wrapped: false,
                 })
                 .collect(),
             toc: Vec::new(),
             links: Vec::new(),
+            anchors: Vec::new(),
+            front_matter: None,
+            callouts: Vec::new(),
+            details: Vec::new(),
         }
     }
 
@@ -123,6 +464,123 @@ mod tests {
         assert_eq!(tasks[0].text, "prep @agent should be included");
     }
 
+    #[test]
+    fn extract_agent_tasks_joins_wrapped_lines() {
+        let mut rendered = doc(&[
+            "- [ ] tidy the intro and then",
+            "      ask @agent to review !p2",
+            "plain paragraph",
+        ]);
+        rendered.lines[1].wrapped = true;
+
+        let tasks = extract_agent_tasks(&rendered);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].line, 0);
+        assert_eq!(
+            tasks[0].text,
+            "tidy the intro and then ask @agent to review !p2"
+        );
+        assert_eq!(tasks[0].priority, Some(2));
+    }
+
+    #[test]
+    fn extract_agent_tasks_parses_metadata() {
+        let rendered = doc(&[
+            "- [~] @agent:Claude @agent:reviewer fix #T-12 !p1 due:2026-11-01, now",
+            "- [ ] @agent ship it !p3 #42 due:soon",
+        ]);
+
+        let tasks = extract_agent_tasks(&rendered);
+        assert_eq!(tasks[0].state, AgentTaskState::InProgress);
+        assert!(tasks[0].is_open());
+        assert_eq!(tasks[0].owners, vec!["claude", "reviewer"]);
+        assert_eq!(tasks[0].priority, Some(1));
+        assert_eq!(tasks[0].due.as_deref(), Some("2026-11-01"));
+        assert_eq!(tasks[0].id.as_deref(), Some("T-12"));
+        assert_eq!(tasks[0].summary(), "fix now");
+        assert_eq!(tasks[1].priority, Some(3));
+        assert_eq!(tasks[1].id, None);
+        assert_eq!(tasks[1].due, None);
+    }
+
+    #[test]
+    fn inbox_order_sorts_and_filters() {
+        let rendered = doc(&[
+            "- [ ] @agent:a first !p2",
+            "- [x] @agent:a done !p1",
+            "- [ ] @agent:b second due:2026-01-05",
+            "- [ ] @agent:a third !p1 due:2026-01-01",
+        ]);
+        let tasks = extract_agent_tasks(&rendered);
+
+        assert_eq!(
+            inbox_order(&tasks, AgentSort::Document, None),
+            vec![0, 2, 3]
+        );
+        assert_eq!(
+            inbox_order(&tasks, AgentSort::Priority, None),
+            vec![3, 0, 2]
+        );
+        assert_eq!(inbox_order(&tasks, AgentSort::Due, None), vec![3, 2, 0]);
+        assert_eq!(
+            inbox_order(&tasks, AgentSort::Document, Some("a")),
+            vec![0, 3]
+        );
+        assert_eq!(agent_owners(&tasks), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn edit_task_in_source_toggles_by_line() {
+        let source = "# Plan\n\n- [ ] @agent first\n  1. [x] @agent second (done 2026-01-02)\n";
+        let done = edit_task_in_source(
+            source,
+            2,
+            2,
+            TaskEdit::Toggle {
+                done_stamp: Some("2026-10-17"),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            done,
+            "# Plan\n\n- [x] @agent first (done 2026-10-17)\n  1. [x] @agent second (done 2026-01-02)\n"
+        );
+
+        let reopened =
+            edit_task_in_source(&done, 3, 3, TaskEdit::Toggle { done_stamp: None }).unwrap();
+        assert!(reopened.ends_with("  1. [ ] @agent second\n"));
+        assert!(edit_task_in_source(source, 0, 0, TaskEdit::Delete).is_none());
+    }
+
+    #[test]
+    fn edit_task_in_source_stamps_after_a_wrapped_task() {
+        let source = "- [ ] @agent first line\n  second line\nafter\n";
+        let done = edit_task_in_source(
+            source,
+            0,
+            1,
+            TaskEdit::Toggle {
+                done_stamp: Some("2026-10-17"),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            done,
+            "- [x] @agent first line\n  second line (done 2026-10-17)\nafter\n"
+        );
+
+        let reopened =
+            edit_task_in_source(&done, 0, 1, TaskEdit::Toggle { done_stamp: None }).unwrap();
+        assert_eq!(reopened, source);
+    }
+
+    #[test]
+    fn edit_task_in_source_deletes_wrapped_task() {
+        let source = "- [ ] @agent keep\n> - [ ] @agent drop this\n> and this\nafter\n";
+        let edited = edit_task_in_source(source, 1, 2, TaskEdit::Delete).unwrap();
+        assert_eq!(edited, "- [ ] @agent keep\nafter\n");
+    }
+
     #[test]
     fn extract_agent_tasks_ignores_non_checklist_lines() {
         let rendered = doc(&[
@@ -0,0 +1,297 @@
+use std::env;
+
+use ratatui::prelude::{Color, Modifier, Style};
+
+use crate::markdown::RenderedDocument;
+
+const SGR_RESET: &str = "\x1b[0m";
+
+/// Color capability of the terminal receiving non-interactive output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub(crate) fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if matches!(colorterm.as_str(), "truecolor" | "24bit") {
+            return Self::TrueColor;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        if term.contains("256color") {
+            return Self::Ansi256;
+        }
+        Self::Ansi16
+    }
+}
+
+fn ansi16_index(color: Color) -> Option<u8> {
+    let index = match color {
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        _ => return None,
+    };
+    Some(index)
+}
+
+const ANSI16_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = i32::from(a.0) - i32::from(b.0);
+    let dg = i32::from(a.1) - i32::from(b.1);
+    let db = i32::from(a.2) - i32::from(b.2);
+    (dr * dr + dg * dg + db * db).unsigned_abs()
+}
+
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> u8 {
+    ANSI16_RGB
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| distance(rgb, **candidate))
+        .map_or(7, |(idx, _)| idx as u8)
+}
+
+fn cube_level(value: u8) -> u8 {
+    if value < 48 {
+        0
+    } else if value < 115 {
+        1
+    } else {
+        (value - 35) / 40
+    }
+}
+
+fn cube_value(level: u8) -> u8 {
+    if level == 0 {
+        0
+    } else {
+        55 + level * 40
+    }
+}
+
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = (cube_level(rgb.0), cube_level(rgb.1), cube_level(rgb.2));
+    let cube_rgb = (cube_value(r), cube_value(g), cube_value(b));
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let gray_level = if average > 238 {
+        23
+    } else {
+        (average.saturating_sub(3) / 10).min(23) as u8
+    };
+    let gray_value = 8 + gray_level * 10;
+    let gray_index = 232 + gray_level;
+
+    if distance(rgb, (gray_value, gray_value, gray_value)) < distance(rgb, cube_rgb) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16_RGB[usize::from(index)],
+        16..=231 => {
+            let offset = index - 16;
+            (
+                cube_value(offset / 36),
+                cube_value((offset / 6) % 6),
+                cube_value(offset % 6),
+            )
+        }
+        _ => {
+            let value = 8 + (index - 232) * 10;
+            (value, value, value)
+        }
+    }
+}
+
+fn color_sgr(color: Color, depth: ColorDepth, background: bool) -> Option<String> {
+    let base16 = |index: u8| {
+        let base = match (background, index < 8) {
+            (false, true) => 30,
+            (false, false) => 90 - 8,
+            (true, true) => 40,
+            (true, false) => 100 - 8,
+        };
+        (u16::from(index) + base).to_string()
+    };
+    let extended = if background { 48 } else { 38 };
+
+    match color {
+        Color::Reset => Some(if background { "49" } else { "39" }.to_string()),
+        Color::Rgb(r, g, b) => Some(match depth {
+            ColorDepth::TrueColor => format!("{extended};2;{r};{g};{b}"),
+            ColorDepth::Ansi256 => format!("{extended};5;{}", nearest_ansi256((r, g, b))),
+            ColorDepth::Ansi16 => base16(nearest_ansi16((r, g, b))),
+        }),
+        Color::Indexed(index) => Some(match depth {
+            ColorDepth::TrueColor | ColorDepth::Ansi256 => format!("{extended};5;{index}"),
+            ColorDepth::Ansi16 => base16(nearest_ansi16(indexed_to_rgb(index))),
+        }),
+        named => ansi16_index(named).map(base16),
+    }
+}
+
+/// Builds the SGR escape sequence for a ratatui style, or an empty string for
+/// the default style.
+pub(crate) fn style_sgr(style: Style, depth: ColorDepth) -> String {
+    let mut params: Vec<String> = Vec::new();
+    let modifiers = style.add_modifier.difference(style.sub_modifier);
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifiers.contains(modifier) {
+            params.push(code.to_string());
+        }
+    }
+    if let Some(fg) = style.fg.and_then(|color| color_sgr(color, depth, false)) {
+        params.push(fg);
+    }
+    if let Some(bg) = style.bg.and_then(|color| color_sgr(color, depth, true)) {
+        params.push(bg);
+    }
+
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+/// Renders a document for stdout, translating each segment style into ANSI
+/// SGR escapes. Every line ends with a reset so styles never bleed across
+/// lines when output is paged or truncated. Lines inside collapsed
+/// `<details>` blocks are left out, as in `plain_render`.
+pub(crate) fn ansi_render(doc: &RenderedDocument, depth: ColorDepth) -> String {
+    let mut lines = Vec::new();
+    for line in doc
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !doc.is_hidden(*idx))
+        .map(|(_, line)| line)
+    {
+        let mut out = String::new();
+        let mut active = String::new();
+        for segment in &line.segments {
+            let sgr = style_sgr(segment.style, depth);
+            if sgr != active {
+                if !active.is_empty() {
+                    out.push_str(SGR_RESET);
+                }
+                out.push_str(&sgr);
+                active = sgr;
+            }
+            out.push_str(&segment.text);
+        }
+        if !active.is_empty() {
+            out.push_str(SGR_RESET);
+        }
+        lines.push(out);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_sgr_emits_truecolor_and_modifiers() {
+        let style = Style::default()
+            .fg(Color::Rgb(10, 20, 30))
+            .add_modifier(Modifier::BOLD | Modifier::ITALIC);
+        assert_eq!(
+            style_sgr(style, ColorDepth::TrueColor),
+            "\x1b[1;3;38;2;10;20;30m"
+        );
+        assert_eq!(style_sgr(Style::default(), ColorDepth::TrueColor), "");
+    }
+
+    #[test]
+    fn style_sgr_downsamples_rgb_for_limited_palettes() {
+        let style = Style::default().bg(Color::Rgb(255, 0, 0));
+        assert_eq!(style_sgr(style, ColorDepth::Ansi256), "\x1b[48;5;196m");
+        assert_eq!(style_sgr(style, ColorDepth::Ansi16), "\x1b[101m");
+    }
+
+    #[test]
+    fn style_sgr_maps_named_colors_to_base_palette() {
+        let style = Style::default().fg(Color::LightCyan);
+        assert_eq!(style_sgr(style, ColorDepth::Ansi16), "\x1b[96m");
+        let style = Style::default().fg(Color::Yellow);
+        assert_eq!(style_sgr(style, ColorDepth::TrueColor), "\x1b[33m");
+    }
+
+    #[test]
+    fn ansi_render_skips_collapsed_details_like_plain_render() {
+        use syntect::highlighting::Theme;
+        use syntect::parsing::SyntaxSet;
+
+        use crate::markdown::{plain_render, render_markdown, RenderOptions};
+
+        let source =
+            "Intro.\n\n<details>\n<summary>More</summary>\n\nHidden.\n\n</details>\n\nEnd.\n";
+        let doc = render_markdown(
+            source,
+            &SyntaxSet::load_defaults_newlines(),
+            &Theme::default(),
+            &RenderOptions::default(),
+        );
+        let ansi = ansi_render(&doc, ColorDepth::TrueColor);
+        let mut stripped = String::new();
+        let mut in_escape = false;
+        for ch in ansi.chars() {
+            match ch {
+                '\x1b' => in_escape = true,
+                'm' if in_escape => in_escape = false,
+                _ if !in_escape => stripped.push(ch),
+                _ => {}
+            }
+        }
+        assert!(!stripped.contains("Hidden."));
+        assert_eq!(stripped, plain_render(&doc));
+    }
+}
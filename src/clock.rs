@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// UTC time of day as `HH:MM:SS`.
+pub(crate) fn format_clock_hms(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        % 86_400;
+    let hour = seconds / 3_600;
+    let minute = (seconds % 3_600) / 60;
+    let second = seconds % 60;
+    format!("{hour:02}:{minute:02}:{second:02}")
+}
+
+/// UTC calendar date as `YYYY-MM-DD`.
+pub(crate) fn format_date_ymd(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86_400;
+    // Civil-from-days conversion (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn format_date_ymd_formats_utc_dates() {
+        assert_eq!(format_date_ymd(at(0)), "1970-01-01");
+        assert_eq!(format_date_ymd(at(951_782_400)), "2000-02-29");
+        assert_eq!(format_date_ymd(at(1_792_195_199)), "2026-10-16");
+    }
+
+    #[test]
+    fn format_clock_hms_formats_utc_times() {
+        assert_eq!(format_clock_hms(at(0)), "00:00:00");
+        assert_eq!(format_clock_hms(at(1_792_195_199)), "23:59:59");
+    }
+}
@@ -0,0 +1,269 @@
+use crate::keymap::Action;
+
+/// What choosing a palette entry does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PaletteTarget {
+    /// Index into the TOC.
+    Heading(usize),
+    /// Index into the document's links.
+    Link(usize),
+    /// Index into the Agent Inbox order of open tasks.
+    AgentTask(usize),
+    /// Index into the watch snapshots.
+    Revision(usize),
+    /// Index into the tab bar.
+    Tab(usize),
+    Command(Action),
+}
+
+impl PaletteTarget {
+    pub(crate) fn kind(self) -> &'static str {
+        match self {
+            Self::Heading(_) => "heading",
+            Self::Link(_) => "link",
+            Self::AgentTask(_) => "task",
+            Self::Revision(_) => "revision",
+            Self::Tab(_) => "tab",
+            Self::Command(_) => "command",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PaletteItem {
+    /// Text the query is matched against.
+    pub(crate) label: String,
+    /// Dimmed hint after the label: line number, link target, keys, ...
+    pub(crate) detail: String,
+    pub(crate) target: PaletteTarget,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PaletteMatch {
+    pub(crate) item: usize,
+    /// Char indices of `label` that matched the query.
+    pub(crate) positions: Vec<usize>,
+}
+
+/// The `Ctrl-p` / `:` overlay: a query and the items that fuzzy-match it,
+/// best first.
+#[derive(Clone, Debug)]
+pub(crate) struct CommandPalette {
+    pub(crate) query: String,
+    pub(crate) items: Vec<PaletteItem>,
+    pub(crate) matches: Vec<PaletteMatch>,
+    pub(crate) selected: usize,
+}
+
+impl CommandPalette {
+    pub(crate) fn new(items: Vec<PaletteItem>) -> Self {
+        let mut palette = Self {
+            query: String::new(),
+            items,
+            matches: Vec::new(),
+            selected: 0,
+        };
+        palette.update_matches();
+        palette
+    }
+
+    pub(crate) fn push(&mut self, ch: char) {
+        self.query.push(ch);
+        self.update_matches();
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.query.pop();
+        self.update_matches();
+    }
+
+    pub(crate) fn move_selection(&mut self, up: bool) {
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        } else {
+            self.selected = self
+                .selected
+                .saturating_add(1)
+                .min(self.matches.len().saturating_sub(1));
+        }
+    }
+
+    pub(crate) fn selected_target(&self) -> Option<PaletteTarget> {
+        let found = self.matches.get(self.selected)?;
+        self.items.get(found.item).map(|item| item.target)
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored: Vec<(i64, PaletteMatch)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(item, entry)| {
+                let (score, positions) = fuzzy_match(&self.query, &entry.label)?;
+                Some((score, PaletteMatch { item, positions }))
+            })
+            .collect();
+        // Stable, so equal scores keep the document order.
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, found)| found).collect();
+        self.selected = 0;
+    }
+}
+
+/// Matches `query` as a subsequence of `candidate`, ignoring case unless
+/// the query has uppercase letters. Returns a score (higher is better) and
+/// the matched char indices of the best alignment: matches at word starts
+/// and consecutive runs score higher, gaps and a late start cost a little.
+pub(crate) fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    const GAP_CAP: usize = 5;
+    let needle: Vec<char> = query.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if needle.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let case_sensitive = needle.iter().any(|ch| ch.is_uppercase());
+    let fold = |ch: char| {
+        if case_sensitive {
+            ch
+        } else {
+            ch.to_lowercase().next().unwrap_or(ch)
+        }
+    };
+    let needle: Vec<char> = needle.into_iter().map(fold).collect();
+    let hay: Vec<char> = candidate.chars().collect();
+    let folded: Vec<char> = hay.iter().map(|ch| fold(*ch)).collect();
+    let bonus = |idx: usize| {
+        let word_start = idx == 0
+            || !hay[idx - 1].is_alphanumeric()
+            || (hay[idx - 1].is_lowercase() && hay[idx].is_uppercase());
+        if word_start {
+            9
+        } else {
+            1
+        }
+    };
+    let penalty = |amount: usize| i64::try_from(amount).unwrap_or(i64::MAX);
+
+    // best[i][j]: score of matching needle[..=i] with needle[i] at hay[j].
+    let mut best: Vec<Vec<Option<i64>>> = vec![vec![None; hay.len()]; needle.len()];
+    let mut from = vec![vec![0usize; hay.len()]; needle.len()];
+    for (j, ch) in folded.iter().enumerate() {
+        if *ch == needle[0] {
+            best[0][j] = Some(bonus(j) - penalty(j.min(10)));
+        }
+    }
+    for i in 1..needle.len() {
+        // Best predecessor at least GAP_CAP chars back; those all cost GAP_CAP.
+        let mut far: Option<(i64, usize)> = None;
+        for j in 0..hay.len() {
+            if let Some(k) = j.checked_sub(GAP_CAP) {
+                if let Some(score) = best[i - 1][k] {
+                    if far.is_none_or(|(top, _)| score > top) {
+                        far = Some((score, k));
+                    }
+                }
+            }
+            if folded[j] != needle[i] {
+                continue;
+            }
+            let mut candidate = far.map(|(score, k)| (score - penalty(GAP_CAP), k));
+            let near = j.saturating_sub(GAP_CAP - 1);
+            for (k, score) in best[i - 1][near..j].iter().enumerate() {
+                let k = near + k;
+                let Some(score) = *score else {
+                    continue;
+                };
+                let step = if k + 1 == j { 5 } else { -penalty(j - k) };
+                if candidate.is_none_or(|(top, _)| score + step > top) {
+                    candidate = Some((score + step, k));
+                }
+            }
+            if let Some((score, k)) = candidate {
+                best[i][j] = Some(score + bonus(j));
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = needle.len() - 1;
+    let (score, mut idx) = best[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (score, j)))
+        .max_by_key(|(score, j)| (*score, std::cmp::Reverse(*j)))?;
+    let mut positions = vec![idx; needle.len()];
+    for i in (1..needle.len()).rev() {
+        idx = from[i][idx];
+        positions[i - 1] = idx;
+    }
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, target: PaletteTarget) -> PaletteItem {
+        PaletteItem {
+            label: label.to_string(),
+            detail: String::new(),
+            target,
+        }
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_word_starts_and_runs() {
+        assert_eq!(fuzzy_match("", "anything"), Some((0, Vec::new())));
+        assert_eq!(fuzzy_match("xyz", "Install"), None);
+        let (_, positions) = fuzzy_match("tt", "Toggle timeline").unwrap();
+        assert_eq!(positions, vec![0, 7]);
+        let (_, positions) = fuzzy_match("inst", "Quick install").unwrap();
+        assert_eq!(positions, vec![6, 7, 8, 9]);
+        // Uppercase in the query makes matching case-sensitive.
+        assert!(fuzzy_match("Gs", "getting started").is_none());
+        assert!(fuzzy_match("gs", "Getting Started").is_some());
+
+        let (word, positions) = fuzzy_match("cfg", "Configuration file guide").unwrap();
+        assert_eq!(positions, vec![0, 14, 19]);
+        let scattered = fuzzy_match("cfg", "conflagration").unwrap().0;
+        assert!(word > scattered);
+    }
+
+    #[test]
+    fn palette_ranks_matches_and_keeps_order_for_ties() {
+        let mut palette = CommandPalette::new(vec![
+            item("Installation", PaletteTarget::Heading(0)),
+            item("Usage", PaletteTarget::Heading(1)),
+            item("Toggle TOC", PaletteTarget::Command(Action::ToggleToc)),
+            item("Install script", PaletteTarget::Link(0)),
+        ]);
+        assert_eq!(palette.matches.len(), 4);
+        assert_eq!(palette.selected_target(), Some(PaletteTarget::Heading(0)));
+
+        for ch in "toc".chars() {
+            palette.push(ch);
+        }
+        assert_eq!(
+            palette.selected_target(),
+            Some(PaletteTarget::Command(Action::ToggleToc))
+        );
+
+        palette.pop();
+        palette.pop();
+        palette.pop();
+        for ch in "inst".chars() {
+            palette.push(ch);
+        }
+        let targets: Vec<PaletteTarget> = palette
+            .matches
+            .iter()
+            .map(|found| palette.items[found.item].target)
+            .collect();
+        assert_eq!(
+            targets,
+            vec![PaletteTarget::Heading(0), PaletteTarget::Link(0)]
+        );
+        palette.move_selection(false);
+        palette.move_selection(false);
+        assert_eq!(palette.selected_target(), Some(PaletteTarget::Link(0)));
+    }
+}
@@ -0,0 +1,125 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::keymap::KeyConfig;
+use crate::theme::ThemePreset;
+
+/// Sidebar open when the pager starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Sidebar {
+    #[default]
+    None,
+    Toc,
+    AgentInbox,
+    Callouts,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PanelConfig {
+    pub(crate) sidebar: Sidebar,
+    /// Front matter panel above the document.
+    pub(crate) metadata: bool,
+    /// Revision timeline dock (watch mode and `--git-log`).
+    pub(crate) timeline: bool,
+}
+
+impl Default for PanelConfig {
+    fn default() -> Self {
+        Self {
+            sidebar: Sidebar::None,
+            metadata: true,
+            timeline: false,
+        }
+    }
+}
+
+/// Settings from `config.toml`; every key is optional.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) theme: ThemePreset,
+    /// Syntect theme for code blocks, overriding the preset's.
+    pub(crate) syntax_theme: Option<String>,
+    /// Default for `--history`.
+    pub(crate) history: Option<usize>,
+    pub(crate) panels: PanelConfig,
+    /// Keymap preset and per-action key overrides.
+    pub(crate) keys: KeyConfig,
+}
+
+impl Config {
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        if config.history == Some(0) {
+            return Err(anyhow!("history must be at least 1"));
+        }
+        config.keys.keymap().context("Invalid [keys] table")?;
+        Ok(config)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/catmd/config.toml`, falling back to
+/// `~/.config/catmd/config.toml`.
+pub(crate) fn config_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|value| !value.is_empty()) {
+        Some(config) => PathBuf::from(config),
+        None => {
+            PathBuf::from(env::var_os("HOME").filter(|value| !value.is_empty())?).join(".config")
+        }
+    };
+    Some(dir.join("catmd/config.toml"))
+}
+
+/// Reads the config file; a missing file yields the defaults.
+pub(crate) fn load_config() -> Result<Config> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+    Config::parse(&text).with_context(|| format!("Invalid config file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeymapPreset;
+
+    #[test]
+    fn config_parses_known_keys_and_rejects_typos() {
+        let config = Config::parse(
+            "theme = \"high-contrast\"\nsyntax_theme = \"InspiredGitHub\"\nhistory = 200\n\n[panels]\nsidebar = \"agent-inbox\"\nmetadata = false\n",
+        )
+        .unwrap();
+        assert_eq!(config.theme, ThemePreset::HighContrast);
+        assert_eq!(config.syntax_theme.as_deref(), Some("InspiredGitHub"));
+        assert_eq!(config.history, Some(200));
+        assert_eq!(config.panels.sidebar, Sidebar::AgentInbox);
+        assert!(!config.panels.metadata);
+        assert!(!config.panels.timeline);
+
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::default().panels.metadata);
+        assert!(Config::parse("theme = \"sepia\"").is_err());
+        assert!(Config::parse("histroy = 10").is_err());
+        assert!(Config::parse("history = 0").is_err());
+
+        let config =
+            Config::parse("[keys]\npreset = \"less\"\nbind = { toggle-toc = \"T\" }\n").unwrap();
+        assert_eq!(config.keys.preset, KeymapPreset::Less);
+        assert!(Config::parse("[keys]\npreset = \"nano\"").is_err());
+        assert!(Config::parse("[keys]\nbind = { quit = \"hyper-q\" }").is_err());
+    }
+}
@@ -1,11 +1,18 @@
-use std::collections::BTreeMap;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::markdown::{RenderedDocument, TocEntry};
 
 const BRIGHT_CHANGE_WINDOW: Duration = Duration::from_secs(2);
 const DIM_CHANGE_WINDOW: Duration = Duration::from_secs(15);
-const DIFF_MAX_CELLS: usize = 2_000_000;
+/// Upper bound on the cost of the Myers search before it falls back to a
+/// single coarse hunk. `middle_snake` charges one unit per diagonal `k` it
+/// examines, forward and backward, at each edit distance `d` (sliding along
+/// a diagonal is free): `(D + 1) * (D + 2)` for a middle snake found at
+/// `D ~ E / 2`, where `E` is the edit distance. Summed over the recursion
+/// that stays below `E^2 / 2`, so this allows about 4_500 changed lines.
+pub(crate) const DIFF_MAX_COST: usize = 10_000_000;
 
 fn clamp_line(line: usize, total_lines: usize) -> usize {
     if total_lines == 0 {
@@ -42,17 +49,22 @@ fn trim_common_suffix(old_lines: &[&str], new_lines: &[&str], prefix: usize) ->
 fn single_hunk_result(
     start_line: usize,
     added: usize,
-    removed: usize,
+    removed_lines: &[&str],
     overflow: bool,
 ) -> LineDiffResult {
     LineDiffResult {
         added,
-        removed,
+        removed: removed_lines.len(),
         hunks: vec![DiffHunk {
             start_line,
             end_line: start_line.saturating_add(added),
             added,
-            removed,
+            removed: removed_lines.len(),
+            old_start_line: start_line,
+            removed_lines: removed_lines
+                .iter()
+                .map(|line| (*line).to_string())
+                .collect(),
         }],
         overflow,
     }
@@ -70,6 +82,10 @@ pub(crate) struct DiffHunk {
     pub(crate) end_line: usize,
     pub(crate) added: usize,
     pub(crate) removed: usize,
+    /// First line of the replaced range in the old document.
+    pub(crate) old_start_line: usize,
+    /// Text of the old lines this hunk removes, in order.
+    pub(crate) removed_lines: Vec<String>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -80,6 +96,9 @@ pub(crate) struct SnapshotDiff {
     pub(crate) section_deltas: BTreeMap<usize, SectionDelta>,
     pub(crate) top_section: Option<String>,
     pub(crate) overflow: bool,
+    /// Changed byte ranges within replaced lines, keyed by new line index.
+    /// Lines without an entry are highlighted as a whole.
+    pub(crate) word_changes: BTreeMap<usize, Vec<Range<usize>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -87,8 +106,41 @@ pub(crate) struct WatchSnapshot {
     pub(crate) revision: u64,
     pub(crate) created_at: SystemTime,
     pub(crate) created_instant: Instant,
+    /// Markdown source the snapshot was rendered from.
+    pub(crate) source: String,
     pub(crate) rendered: RenderedDocument,
     pub(crate) diff: SnapshotDiff,
+    /// Display name for snapshots that did not come from this session's
+    /// watch loop (e.g. a git commit); `None` shows `rNNN`.
+    pub(crate) label: Option<String>,
+    /// `rendered` and `diff` predate the last layout change.
+    pub(crate) stale: bool,
+}
+
+impl WatchSnapshot {
+    pub(crate) fn name(&self) -> String {
+        self.label
+            .clone()
+            .unwrap_or_else(|| format!("r{:03}", self.revision))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DiffRowKind {
+    Context,
+    Removed,
+    Added,
+    /// A removed line paired with its replacement.
+    Changed,
+}
+
+/// One row of a two-pane diff: the old line on the left, the new line on the
+/// right, either of which may be absent.
+#[derive(Clone, Debug)]
+pub(crate) struct DiffRow {
+    pub(crate) kind: DiffRowKind,
+    pub(crate) old: Option<String>,
+    pub(crate) new: Option<String>,
 }
 
 #[derive(Debug, Default)]
@@ -123,16 +175,16 @@ pub(crate) fn change_freshness(created_instant: Instant) -> Option<ChangeFreshne
     }
 }
 
-pub(crate) fn format_clock_hms(time: SystemTime) -> String {
-    let seconds = time
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs()
-        % 86_400;
-    let hour = seconds / 3_600;
-    let minute = (seconds % 3_600) / 60;
-    let second = seconds % 60;
-    format!("{hour:02}:{minute:02}:{second:02}")
+/// Monotonic stand-in for a past wall-clock time, so snapshots restored from
+/// disk are not highlighted as fresh changes.
+pub(crate) fn instant_for(created_at: SystemTime) -> Instant {
+    let now = Instant::now();
+    let age = SystemTime::now()
+        .duration_since(created_at)
+        .unwrap_or_default();
+    now.checked_sub(age)
+        .or_else(|| now.checked_sub(DIM_CHANGE_WINDOW.saturating_add(Duration::from_secs(1))))
+        .unwrap_or(now)
 }
 
 pub(crate) fn truncate_label(text: &str, max_chars: usize) -> String {
@@ -196,6 +248,114 @@ fn touched_toc_indices_for_hunk(
     (start_idx..=end_idx).collect()
 }
 
+/// Splits a line into runs of word characters, runs of whitespace, and
+/// single punctuation characters.
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = 0usize;
+    let mut class = None;
+    for (idx, ch) in line.char_indices() {
+        let next_class = if ch.is_alphanumeric() || ch == '_' {
+            Some(0u8)
+        } else if ch.is_whitespace() {
+            Some(1)
+        } else {
+            None
+        };
+        if idx > start && (next_class.is_none() || next_class != class) {
+            tokens.push((start, &line[start..idx]));
+            start = idx;
+        }
+        class = next_class;
+    }
+    if start < line.len() {
+        tokens.push((start, &line[start..]));
+    }
+    tokens
+}
+
+/// Byte ranges of `new` that differ from `old` at word granularity, or `None`
+/// when the two lines share too little to read as an edit of one another.
+pub(crate) fn changed_word_ranges(old: &str, new: &str) -> Option<Vec<Range<usize>>> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    let old_words: Vec<&str> = old_tokens.iter().map(|(_, token)| *token).collect();
+    let new_words: Vec<&str> = new_tokens.iter().map(|(_, token)| *token).collect();
+    let token_diff = compute_line_diff(&old_words, &new_words, DIFF_MAX_COST);
+    if token_diff.overflow {
+        return None;
+    }
+
+    let token_range = |idx: usize| {
+        new_tokens
+            .get(idx)
+            .map(|(start, token)| *start..start.saturating_add(token.len()))
+    };
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut changed_bytes = 0usize;
+    for hunk in &token_diff.hunks {
+        let range = if hunk.end_line > hunk.start_line {
+            let (Some(first), Some(last)) = (
+                token_range(hunk.start_line),
+                token_range(hunk.end_line.saturating_sub(1)),
+            ) else {
+                continue;
+            };
+            changed_bytes = changed_bytes.saturating_add(last.end.saturating_sub(first.start));
+            first.start..last.end
+        } else {
+            // Pure deletion: mark the token the removed text sat in front of.
+            match token_range(hunk.start_line)
+                .or_else(|| token_range(hunk.start_line.saturating_sub(1)))
+            {
+                Some(range) => range,
+                None => continue,
+            }
+        };
+        match ranges.last_mut() {
+            Some(previous) if previous.end >= range.start => {
+                previous.end = previous.end.max(range.end);
+            }
+            _ => ranges.push(range),
+        }
+    }
+
+    let common = new.len().saturating_sub(changed_bytes);
+    let average = old.len().saturating_add(new.len()) / 2;
+    if ranges.is_empty() || common.saturating_mul(2) < average {
+        return None;
+    }
+    Some(ranges)
+}
+
+/// Splits `text`, which begins at byte `start` of its line, into pieces
+/// flagged by whether they fall inside one of `ranges`.
+pub(crate) fn split_by_ranges<'a>(
+    text: &'a str,
+    start: usize,
+    ranges: &[Range<usize>],
+) -> Vec<(&'a str, bool)> {
+    let end = start.saturating_add(text.len());
+    let mut pieces = Vec::new();
+    let mut cursor = start;
+    for range in ranges {
+        if range.end <= cursor || range.start >= end {
+            continue;
+        }
+        let from = range.start.max(cursor);
+        let to = range.end.min(end);
+        if from > cursor {
+            pieces.push((&text[cursor - start..from - start], false));
+        }
+        pieces.push((&text[from - start..to - start], true));
+        cursor = to;
+    }
+    if cursor < end {
+        pieces.push((&text[cursor - start..], false));
+    }
+    pieces
+}
+
 pub(crate) fn build_snapshot_diff(
     previous: &RenderedDocument,
     next: &RenderedDocument,
@@ -206,7 +366,7 @@ pub(crate) fn build_snapshot_diff(
         .map(|line| line.plain.as_str())
         .collect();
     let new_lines: Vec<&str> = next.lines.iter().map(|line| line.plain.as_str()).collect();
-    let line_diff = compute_line_diff(&old_lines, &new_lines, DIFF_MAX_CELLS);
+    let line_diff = compute_line_diff(&old_lines, &new_lines, DIFF_MAX_COST);
 
     let mut section_deltas: BTreeMap<usize, SectionDelta> = BTreeMap::new();
     for hunk in &line_diff.hunks {
@@ -225,6 +385,21 @@ pub(crate) fn build_snapshot_diff(
         .first_key_value()
         .and_then(|(idx, _)| next.toc.get(*idx).map(|entry| entry.title.clone()));
 
+    let mut word_changes = BTreeMap::new();
+    if !line_diff.overflow {
+        for hunk in &line_diff.hunks {
+            for (offset, old_line) in hunk.removed_lines.iter().take(hunk.added).enumerate() {
+                let line = hunk.start_line.saturating_add(offset);
+                if let Some(ranges) = new_lines
+                    .get(line)
+                    .and_then(|new_line| changed_word_ranges(old_line, new_line))
+                {
+                    word_changes.insert(line, ranges);
+                }
+            }
+        }
+    }
+
     SnapshotDiff {
         added: line_diff.added,
         removed: line_diff.removed,
@@ -232,13 +407,158 @@ pub(crate) fn build_snapshot_diff(
         section_deltas,
         top_section,
         overflow: line_diff.overflow,
+        word_changes,
+    }
+}
+
+/// Maps each distinct line to a small id so the diff compares integers.
+fn intern_lines<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> (Vec<u32>, Vec<u32>) {
+    let mut ids: HashMap<&'a str, u32> = HashMap::new();
+    let mut intern = |line: &'a str| {
+        let next = u32::try_from(ids.len()).unwrap_or(u32::MAX);
+        *ids.entry(line).or_insert(next)
+    };
+    let old_ids = old_lines.iter().map(|line| intern(line)).collect();
+    let new_ids = new_lines.iter().map(|line| intern(line)).collect();
+    (old_ids, new_ids)
+}
+
+/// Finds a point on an optimal edit path through the middle of the edit
+/// graph by running the Myers search from both ends at once. Gives up with
+/// `None` as soon as `cost` passes `max_cost`.
+fn middle_snake(
+    old: &[u32],
+    new: &[u32],
+    cost: &mut usize,
+    max_cost: usize,
+) -> Option<(usize, usize)> {
+    let old_len = old.len() as isize;
+    let new_len = new.len() as isize;
+    let max_d = (old_len + new_len + 1) / 2;
+    let offset = max_d + 1;
+    let width = (2 * max_d + 3) as usize;
+    let mut forward = vec![-1isize; width];
+    let mut backward = vec![-1isize; width];
+    forward[(offset + 1) as usize] = 0;
+    backward[(offset + 1) as usize] = 0;
+    let delta = old_len - new_len;
+    let front = delta % 2 != 0;
+    let at = |v: &[isize], k: isize| v.get((offset + k) as usize).copied().unwrap_or(-1);
+
+    for d in 0..=max_d {
+        if *cost > max_cost {
+            return None;
+        }
+        let mut k = -d;
+        while k <= d {
+            *cost = cost.saturating_add(1);
+            let mut x = if k == -d || (k != d && at(&forward, k - 1) < at(&forward, k + 1)) {
+                at(&forward, k + 1)
+            } else {
+                at(&forward, k - 1) + 1
+            };
+            let mut y = x - k;
+            while x < old_len && y < new_len && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[(offset + k) as usize] = x;
+            if front && x <= old_len && y <= new_len {
+                let back_x = at(&backward, delta - k);
+                if back_x >= 0 && x >= old_len - back_x {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k += 2;
+        }
+
+        let mut k = -d;
+        while k <= d {
+            *cost = cost.saturating_add(1);
+            let mut x = if k == -d || (k != d && at(&backward, k - 1) < at(&backward, k + 1)) {
+                at(&backward, k + 1)
+            } else {
+                at(&backward, k - 1) + 1
+            };
+            let mut y = x - k;
+            while x < old_len
+                && y < new_len
+                && old[(old_len - x - 1) as usize] == new[(new_len - y - 1) as usize]
+            {
+                x += 1;
+                y += 1;
+            }
+            backward[(offset + k) as usize] = x;
+            if !front && x <= old_len && y <= new_len {
+                let fwd_x = at(&forward, delta - k);
+                if fwd_x >= 0 && fwd_x >= old_len - x {
+                    let fwd_y = fwd_x - (delta - k);
+                    return Some((fwd_x as usize, fwd_y as usize));
+                }
+            }
+            k += 2;
+        }
+    }
+    None
+}
+
+/// Linear-space Myers diff: marks every line that is not part of a longest
+/// common subsequence. Returns `false` once more than `max_cost` diagonal
+/// steps have been explored.
+fn myers_mark(
+    old: &[u32],
+    new: &[u32],
+    old_changed: &mut [bool],
+    new_changed: &mut [bool],
+    max_cost: usize,
+) -> bool {
+    let mut cost = 0usize;
+    let mut pending = vec![(0usize, old.len(), 0usize, new.len())];
+    while let Some((mut old_lo, mut old_hi, mut new_lo, mut new_hi)) = pending.pop() {
+        while old_lo < old_hi && new_lo < new_hi && old[old_lo] == new[new_lo] {
+            old_lo = old_lo.saturating_add(1);
+            new_lo = new_lo.saturating_add(1);
+        }
+        while old_lo < old_hi
+            && new_lo < new_hi
+            && old[old_hi.saturating_sub(1)] == new[new_hi.saturating_sub(1)]
+        {
+            old_hi = old_hi.saturating_sub(1);
+            new_hi = new_hi.saturating_sub(1);
+        }
+
+        if old_lo == old_hi || new_lo == new_hi {
+            old_changed[old_lo..old_hi].fill(true);
+            new_changed[new_lo..new_hi].fill(true);
+            continue;
+        }
+
+        match middle_snake(
+            &old[old_lo..old_hi],
+            &new[new_lo..new_hi],
+            &mut cost,
+            max_cost,
+        ) {
+            Some((x, y)) if (x, y) != (0, 0) && (x, y) != (old_hi - old_lo, new_hi - new_lo) => {
+                pending.push((old_lo, old_lo + x, new_lo, new_lo + y));
+                pending.push((old_lo + x, old_hi, new_lo + y, new_hi));
+            }
+            _ => {
+                old_changed[old_lo..old_hi].fill(true);
+                new_changed[new_lo..new_hi].fill(true);
+            }
+        }
+        if cost > max_cost {
+            return false;
+        }
     }
+    true
 }
 
 pub(crate) fn compute_line_diff(
     old_lines: &[&str],
     new_lines: &[&str],
-    max_cells: usize,
+    max_cost: usize,
 ) -> LineDiffResult {
     let prefix = common_prefix_len(old_lines, new_lines);
     let (old_end, new_end) = trim_common_suffix(old_lines, new_lines, prefix);
@@ -251,104 +571,77 @@ pub(crate) fn compute_line_diff(
     }
 
     if old_mid.is_empty() {
-        return single_hunk_result(prefix, new_mid.len(), 0, false);
+        return single_hunk_result(prefix, new_mid.len(), &[], false);
     }
 
     if new_mid.is_empty() {
-        return single_hunk_result(prefix, 0, old_mid.len(), false);
-    }
-
-    let rows = old_mid.len().saturating_add(1);
-    let cols = new_mid.len().saturating_add(1);
-    if rows.saturating_mul(cols) > max_cells {
-        return single_hunk_result(prefix, new_mid.len(), old_mid.len(), true);
+        return single_hunk_result(prefix, 0, old_mid, false);
     }
 
-    let mut table = vec![0u32; rows.saturating_mul(cols)];
-    for i in 1..rows {
-        for j in 1..cols {
-            let idx = i.saturating_mul(cols).saturating_add(j);
-            table[idx] = if old_mid[i.saturating_sub(1)] == new_mid[j.saturating_sub(1)] {
-                table[(i.saturating_sub(1))
-                    .saturating_mul(cols)
-                    .saturating_add(j.saturating_sub(1))]
-                .saturating_add(1)
-            } else {
-                table[(i.saturating_sub(1)).saturating_mul(cols).saturating_add(j)]
-                    .max(table[i.saturating_mul(cols).saturating_add(j.saturating_sub(1))])
-            };
-        }
+    let (old_ids, new_ids) = intern_lines(old_mid, new_mid);
+    let mut old_changed = vec![false; old_mid.len()];
+    let mut new_changed = vec![false; new_mid.len()];
+    if !myers_mark(
+        &old_ids,
+        &new_ids,
+        &mut old_changed,
+        &mut new_changed,
+        max_cost,
+    ) {
+        return single_hunk_result(prefix, new_mid.len(), old_mid, true);
     }
 
-    let mut ops_reversed = Vec::with_capacity(old_mid.len().saturating_add(new_mid.len()));
-    let mut i = old_mid.len();
-    let mut j = new_mid.len();
-
-    while i > 0 && j > 0 {
-        if old_mid[i.saturating_sub(1)] == new_mid[j.saturating_sub(1)] {
-            ops_reversed.push(DiffOp::Equal);
-            i = i.saturating_sub(1);
-            j = j.saturating_sub(1);
-            continue;
-        }
-
-        let up = table[(i.saturating_sub(1)).saturating_mul(cols).saturating_add(j)];
-        let left = table[i.saturating_mul(cols).saturating_add(j.saturating_sub(1))];
-        if up >= left {
-            ops_reversed.push(DiffOp::Remove);
-            i = i.saturating_sub(1);
+    let mut ops = Vec::with_capacity(old_mid.len().saturating_add(new_mid.len()));
+    let (mut i, mut j) = (0usize, 0usize);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && old_changed[i] {
+            ops.push(DiffOp::Remove);
+            i = i.saturating_add(1);
+        } else if j < new_mid.len() && new_changed[j] {
+            ops.push(DiffOp::Add);
+            j = j.saturating_add(1);
         } else {
-            ops_reversed.push(DiffOp::Add);
-            j = j.saturating_sub(1);
+            ops.push(DiffOp::Equal);
+            i = i.saturating_add(1);
+            j = j.saturating_add(1);
         }
     }
 
-    while i > 0 {
-        ops_reversed.push(DiffOp::Remove);
-        i = i.saturating_sub(1);
-    }
-    while j > 0 {
-        ops_reversed.push(DiffOp::Add);
-        j = j.saturating_sub(1);
-    }
-
-    ops_reversed.reverse();
-
     let mut hunks = Vec::new();
     let mut current: Option<DiffHunk> = None;
     let mut new_index = prefix;
+    let mut old_index = prefix;
     let mut added = 0usize;
     let mut removed = 0usize;
+    let empty_hunk = |new_index: usize, old_index: usize| DiffHunk {
+        start_line: new_index,
+        end_line: new_index,
+        old_start_line: old_index,
+        ..DiffHunk::default()
+    };
 
-    for op in ops_reversed {
+    for op in ops {
         match op {
             DiffOp::Equal => {
                 new_index = new_index.saturating_add(1);
+                old_index = old_index.saturating_add(1);
                 if let Some(hunk) = current.take() {
                     hunks.push(hunk);
                 }
             }
             DiffOp::Add => {
                 added = added.saturating_add(1);
-                let hunk = current.get_or_insert(DiffHunk {
-                    start_line: new_index,
-                    end_line: new_index,
-                    added: 0,
-                    removed: 0,
-                });
+                let hunk = current.get_or_insert_with(|| empty_hunk(new_index, old_index));
                 hunk.added = hunk.added.saturating_add(1);
                 new_index = new_index.saturating_add(1);
                 hunk.end_line = new_index;
             }
             DiffOp::Remove => {
                 removed = removed.saturating_add(1);
-                let hunk = current.get_or_insert(DiffHunk {
-                    start_line: new_index,
-                    end_line: new_index,
-                    added: 0,
-                    removed: 0,
-                });
+                let hunk = current.get_or_insert_with(|| empty_hunk(new_index, old_index));
                 hunk.removed = hunk.removed.saturating_add(1);
+                hunk.removed_lines.push(old_lines[old_index].to_string());
+                old_index = old_index.saturating_add(1);
             }
         }
     }
@@ -364,3 +657,83 @@ pub(crate) fn compute_line_diff(
         overflow: false,
     }
 }
+
+/// Diffs two line sequences and aligns them into side-by-side rows. Within a
+/// hunk, removed and added lines are paired up in order.
+pub(crate) fn diff_rows(old_lines: &[&str], new_lines: &[&str]) -> (Vec<DiffRow>, LineDiffResult) {
+    let line_diff = compute_line_diff(old_lines, new_lines, DIFF_MAX_COST);
+    let mut rows = Vec::with_capacity(new_lines.len().max(old_lines.len()));
+    let mut old_index = 0usize;
+    let mut new_index = 0usize;
+    let context = |rows: &mut Vec<DiffRow>, old: &str, new: &str| {
+        debug_assert_eq!(old, new);
+        rows.push(DiffRow {
+            kind: DiffRowKind::Context,
+            old: Some(old.to_string()),
+            new: Some(new.to_string()),
+        });
+    };
+
+    for hunk in &line_diff.hunks {
+        while new_index < hunk.start_line && old_index < hunk.old_start_line {
+            context(&mut rows, old_lines[old_index], new_lines[new_index]);
+            old_index = old_index.saturating_add(1);
+            new_index = new_index.saturating_add(1);
+        }
+
+        let paired = hunk.removed_lines.len().max(hunk.added);
+        for offset in 0..paired {
+            let old = hunk.removed_lines.get(offset).cloned();
+            let new = new_lines
+                .get(hunk.start_line.saturating_add(offset))
+                .filter(|_| offset < hunk.added)
+                .map(|line| (*line).to_string());
+            let kind = match (&old, &new) {
+                (Some(_), Some(_)) => DiffRowKind::Changed,
+                (Some(_), None) => DiffRowKind::Removed,
+                _ => DiffRowKind::Added,
+            };
+            rows.push(DiffRow { kind, old, new });
+        }
+        old_index = hunk.old_start_line.saturating_add(hunk.removed_lines.len());
+        new_index = hunk.end_line;
+    }
+
+    while new_index < new_lines.len() && old_index < old_lines.len() {
+        context(&mut rows, old_lines[old_index], new_lines[new_index]);
+        old_index = old_index.saturating_add(1);
+        new_index = new_index.saturating_add(1);
+    }
+
+    (rows, line_diff)
+}
+
+/// Flattens aligned rows into a unified listing: each changed run shows all of
+/// its removed lines before its added lines.
+pub(crate) fn inline_rows(rows: &[DiffRow]) -> Vec<DiffRow> {
+    let mut out = Vec::with_capacity(rows.len());
+    let mut pending_added: Vec<DiffRow> = Vec::new();
+    for row in rows {
+        if row.kind == DiffRowKind::Context {
+            out.append(&mut pending_added);
+            out.push(row.clone());
+            continue;
+        }
+        if let Some(old) = &row.old {
+            out.push(DiffRow {
+                kind: DiffRowKind::Removed,
+                old: Some(old.clone()),
+                new: None,
+            });
+        }
+        if let Some(new) = &row.new {
+            pending_added.push(DiffRow {
+                kind: DiffRowKind::Added,
+                old: None,
+                new: Some(new.clone()),
+            });
+        }
+    }
+    out.append(&mut pending_added);
+    out
+}
@@ -0,0 +1,38 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+
+const FALLBACK_EDITOR: &str = "vi";
+
+/// Editor command from `$VISUAL`, then `$EDITOR`, then `vi`. The value is
+/// shell syntax, so it may carry quoted arguments (`code --wait`).
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| FALLBACK_EDITOR.to_string())
+}
+
+/// Runs the user's editor on `path` positioned at the 1-based `line` and
+/// waits for it to exit.
+pub(crate) fn edit_at_line(path: &Path, line: usize) -> Result<()> {
+    let editor = editor_command();
+    // Like git, let `sh` split the command and pass the position and path
+    // as positional parameters so they are never re-parsed.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(format!("+{line}"))
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{editor}'"))?;
+
+    if !status.success() {
+        return Err(anyhow!("editor '{editor}' exited with status {status}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,421 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use ignore::WalkBuilder;
+
+use crate::links::is_markdown_path;
+
+/// Order of the file tree sidebar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum FileSort {
+    /// Directories and files by path.
+    #[default]
+    Name,
+    /// A flat list, most recently modified first.
+    Recent,
+}
+
+impl FileSort {
+    pub(crate) fn toggle(self) -> Self {
+        match self {
+            Self::Name => Self::Recent,
+            Self::Recent => Self::Name,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Recent => "recently modified",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MarkdownFile {
+    pub(crate) path: PathBuf,
+    /// `path` relative to the tree root.
+    pub(crate) relative: PathBuf,
+    pub(crate) modified: SystemTime,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TreeRow {
+    /// A directory, relative to the tree root.
+    Dir {
+        path: PathBuf,
+        depth: usize,
+        open: bool,
+    },
+    /// Index into `FileTree::files`.
+    File { index: usize, depth: usize },
+}
+
+/// Every `.md` / `.markdown` / `.mdx` file under `root`, sorted by path,
+/// and every directory the walk entered (`root` first). Hidden entries and
+/// anything matched by `.gitignore` (or `.ignore`) files are skipped, inside
+/// a git repository or not.
+pub(crate) fn scan_markdown_files(root: &Path) -> Result<(Vec<MarkdownFile>, Vec<PathBuf>)> {
+    if !root.is_dir() {
+        return Err(anyhow!("Not a directory: {}", root.display()));
+    }
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in WalkBuilder::new(root)
+        .require_git(false)
+        .build()
+        .filter_map(|entry| entry.ok())
+    {
+        let Some(kind) = entry.file_type() else {
+            continue;
+        };
+        if kind.is_dir() {
+            dirs.push(entry.into_path());
+            continue;
+        }
+        if !kind.is_file() || !is_markdown_path(entry.path()) {
+            continue;
+        }
+        let path = entry.into_path();
+        let modified = path
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        files.push(MarkdownFile {
+            relative: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+            path,
+            modified,
+        });
+    }
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok((files, dirs))
+}
+
+/// Compact age of `modified` for the recent list: `now`, `42s`, `5m`, `3h`, `2d`.
+pub(crate) fn format_age(modified: SystemTime, now: SystemTime) -> String {
+    let seconds = now
+        .duration_since(modified)
+        .map(|age| age.as_secs())
+        .unwrap_or(0);
+    match seconds {
+        0..=4 => "now".to_string(),
+        5..=59 => format!("{seconds}s"),
+        60..=3_599 => format!("{}m", seconds / 60),
+        3_600..=86_399 => format!("{}h", seconds / 3_600),
+        _ => format!("{}d", seconds / 86_400),
+    }
+}
+
+/// The markdown files under a directory and the rows the sidebar shows for
+/// them.
+#[derive(Clone, Debug)]
+pub(crate) struct FileTree {
+    pub(crate) root: PathBuf,
+    pub(crate) files: Vec<MarkdownFile>,
+    /// Directories the last scan entered; the pager watches only these, so
+    /// ignored trees such as `target/` or `node_modules/` cost no watches.
+    pub(crate) dirs: Vec<PathBuf>,
+    pub(crate) sort: FileSort,
+    /// Collapsed directories, relative to the root.
+    collapsed: HashSet<PathBuf>,
+    pub(crate) rows: Vec<TreeRow>,
+    pub(crate) selected: usize,
+}
+
+impl FileTree {
+    /// Scans `root` and selects the first file.
+    pub(crate) fn new(root: PathBuf) -> Result<Self> {
+        let (files, dirs) = scan_markdown_files(&root)?;
+        let mut tree = Self {
+            root,
+            files,
+            dirs,
+            sort: FileSort::default(),
+            collapsed: HashSet::new(),
+            rows: Vec::new(),
+            selected: 0,
+        };
+        tree.rebuild_rows();
+        tree.selected = tree
+            .rows
+            .iter()
+            .position(|row| matches!(row, TreeRow::File { .. }))
+            .unwrap_or(0);
+        Ok(tree)
+    }
+
+    /// Re-reads the directory, keeping the selected file or directory.
+    pub(crate) fn rescan(&mut self) -> Result<()> {
+        let selected = self.selected_path();
+        (self.files, self.dirs) = scan_markdown_files(&self.root)?;
+        self.rebuild_rows();
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+        self.selected = self.selected.min(self.rows.len().saturating_sub(1));
+        Ok(())
+    }
+
+    pub(crate) fn toggle_sort(&mut self) {
+        let selected = self.selected_path();
+        self.sort = self.sort.toggle();
+        self.rebuild_rows();
+        self.selected = 0;
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+    }
+
+    pub(crate) fn move_selection(&mut self, up: bool) {
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        } else {
+            self.selected = self
+                .selected
+                .saturating_add(1)
+                .min(self.rows.len().saturating_sub(1));
+        }
+    }
+
+    pub(crate) fn selected_file(&self) -> Option<&MarkdownFile> {
+        match self.rows.get(self.selected)? {
+            TreeRow::File { index, .. } => self.files.get(*index),
+            TreeRow::Dir { .. } => None,
+        }
+    }
+
+    /// Absolute path of the selected row.
+    fn selected_path(&self) -> Option<PathBuf> {
+        match self.rows.get(self.selected)? {
+            TreeRow::File { index, .. } => self.files.get(*index).map(|file| file.path.clone()),
+            TreeRow::Dir { path, .. } => Some(self.root.join(path)),
+        }
+    }
+
+    /// Selects the row for `path`, expanding its parent directories.
+    /// Returns false when the path is not in the tree.
+    pub(crate) fn select_path(&mut self, path: &Path) -> bool {
+        // Opened links are canonical paths; the root is as given.
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => {
+                let (Ok(root), Ok(path)) = (fs::canonicalize(&self.root), fs::canonicalize(path))
+                else {
+                    return false;
+                };
+                match path.strip_prefix(root) {
+                    Ok(relative) => relative.to_path_buf(),
+                    Err(_) => return false,
+                }
+            }
+        };
+        let relative = relative.as_path();
+        let mut expanded = false;
+        for ancestor in relative.ancestors().skip(1) {
+            expanded |= self.collapsed.remove(ancestor);
+        }
+        if expanded {
+            self.rebuild_rows();
+        }
+        let found = self.rows.iter().position(|row| match row {
+            TreeRow::File { index, .. } => self.files[*index].relative == relative,
+            TreeRow::Dir { path, .. } => path == relative,
+        });
+        if let Some(found) = found {
+            self.selected = found;
+        }
+        found.is_some()
+    }
+
+    /// Collapses or expands the selected directory. Returns false when a
+    /// file is selected.
+    pub(crate) fn toggle_selected_dir(&mut self) -> bool {
+        let Some(TreeRow::Dir { path, .. }) = self.rows.get(self.selected).cloned() else {
+            return false;
+        };
+        if !self.collapsed.remove(&path) {
+            self.collapsed.insert(path);
+        }
+        self.rebuild_rows();
+        true
+    }
+
+    fn rebuild_rows(&mut self) {
+        self.rows.clear();
+        if self.sort == FileSort::Recent {
+            let mut order: Vec<usize> = (0..self.files.len()).collect();
+            // Stable, so files modified at the same time stay in path order.
+            order.sort_by_key(|index| std::cmp::Reverse(self.files[*index].modified));
+            self.rows = order
+                .into_iter()
+                .map(|index| TreeRow::File { index, depth: 0 })
+                .collect();
+            return;
+        }
+
+        // Directories already emitted for the previous file, outermost first.
+        let mut open: Vec<PathBuf> = Vec::new();
+        for (index, file) in self.files.iter().enumerate() {
+            let parent = file.relative.parent().unwrap_or(Path::new(""));
+            let mut dirs: Vec<&Path> = parent
+                .ancestors()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .collect();
+            dirs.reverse();
+            let shared = open
+                .iter()
+                .zip(&dirs)
+                .take_while(|(emitted, dir)| emitted.as_path() == **dir)
+                .count();
+            open.truncate(shared);
+            for dir in &dirs[shared..] {
+                if self.is_visible(dir) {
+                    self.rows.push(TreeRow::Dir {
+                        path: dir.to_path_buf(),
+                        depth: open.len(),
+                        open: !self.collapsed.contains(*dir),
+                    });
+                }
+                open.push(dir.to_path_buf());
+            }
+            if self.is_visible(&file.relative) {
+                self.rows.push(TreeRow::File {
+                    index,
+                    depth: dirs.len(),
+                });
+            }
+        }
+    }
+
+    /// Whether no directory above `relative` is collapsed.
+    fn is_visible(&self, relative: &Path) -> bool {
+        relative
+            .ancestors()
+            .skip(1)
+            .all(|dir| !self.collapsed.contains(dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::Duration;
+
+    fn temp_tree(name: &str, files: &[&str]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("catmd-tree-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "# doc\n").unwrap();
+        }
+        dir
+    }
+
+    fn labels(tree: &FileTree) -> Vec<String> {
+        tree.rows
+            .iter()
+            .map(|row| match row {
+                TreeRow::Dir { path, depth, open } => format!(
+                    "{}{}/{}",
+                    "  ".repeat(*depth),
+                    path.file_name().unwrap().to_string_lossy(),
+                    if *open { "" } else { "+" }
+                ),
+                TreeRow::File { index, depth } => format!(
+                    "{}{}",
+                    "  ".repeat(*depth),
+                    tree.files[*index]
+                        .path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scan_respects_gitignore_and_builds_nested_rows() {
+        let root = temp_tree(
+            "scan",
+            &[
+                "README.md",
+                "guide/intro.md",
+                "guide/deep/api.markdown",
+                "guide/notes.txt",
+                "build/out.md",
+                ".hidden/secret.md",
+                "zz.mdx",
+            ],
+        );
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+
+        let mut tree = FileTree::new(root.clone()).unwrap();
+        let mut dirs = tree.dirs.clone();
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![root.clone(), root.join("guide"), root.join("guide/deep")]
+        );
+        assert_eq!(
+            labels(&tree),
+            vec![
+                "README.md",
+                "guide/",
+                "  deep/",
+                "    api.markdown",
+                "  intro.md",
+                "zz.mdx"
+            ]
+        );
+        assert_eq!(
+            tree.selected_file().unwrap().relative,
+            Path::new("README.md")
+        );
+
+        tree.selected = 1;
+        assert!(tree.toggle_selected_dir());
+        assert_eq!(labels(&tree), vec!["README.md", "guide/+", "zz.mdx"]);
+        assert!(tree.select_path(&root.join("guide/deep/api.markdown")));
+        assert_eq!(
+            tree.selected_file().unwrap().relative,
+            Path::new("guide/deep/api.markdown")
+        );
+        assert_eq!(labels(&tree).len(), 6);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn recent_sort_lists_newest_first_and_keeps_selection() {
+        let root = temp_tree("recent", &["a.md", "sub/b.md", "c.md"]);
+        let now = SystemTime::now();
+        for (file, age) in [("a.md", 30), ("sub/b.md", 10), ("c.md", 20)] {
+            fs::File::options()
+                .write(true)
+                .open(root.join(file))
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+
+        let mut tree = FileTree::new(root.clone()).unwrap();
+        assert!(tree.select_path(&root.join("c.md")));
+        tree.toggle_sort();
+        assert_eq!(labels(&tree), vec!["b.md", "c.md", "a.md"]);
+        assert_eq!(format_age(tree.files[2].modified, now), "10s");
+        assert_eq!(format_age(now, now - Duration::from_secs(1)), "now");
+        assert_eq!(format_age(now - Duration::from_secs(7_300), now), "2h");
+        assert_eq!(tree.selected_file().unwrap().relative, Path::new("c.md"));
+
+        fs::write(root.join("new.md"), "# new\n").unwrap();
+        tree.rescan().unwrap();
+        assert_eq!(labels(&tree), vec!["new.md", "b.md", "c.md", "a.md"]);
+        assert_eq!(tree.selected_file().unwrap().relative, Path::new("c.md"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+use serde::Serialize;
+
+/// Delimiter style of a metadata block at the top of a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FrontMatterFormat {
+    /// `---` fenced YAML.
+    Yaml,
+    /// `+++` fenced TOML.
+    Toml,
+}
+
+/// Top-level fields of a document's front matter, in source order. Scalars
+/// are kept as one value; lists keep one value per item. Nested tables are
+/// flattened to their display text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FrontMatter {
+    pub(crate) format: FrontMatterFormat,
+    pub(crate) fields: Vec<(String, Vec<String>)>,
+}
+
+impl FrontMatter {
+    pub(crate) fn parse(format: FrontMatterFormat, text: &str) -> Self {
+        let fields = match format {
+            FrontMatterFormat::Yaml => parse_yaml(text),
+            FrontMatterFormat::Toml => parse_toml(text),
+        };
+        Self { format, fields }
+    }
+
+    /// Values of the first field named `key` (case-insensitive).
+    pub(crate) fn get(&self, key: &str) -> Option<&[String]> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, values)| values.as_slice())
+    }
+
+    fn first(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter()
+            .find_map(|key| self.get(key)?.first())
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    pub(crate) fn title(&self) -> Option<&str> {
+        self.first(&["title"])
+    }
+
+    pub(crate) fn status(&self) -> Option<&str> {
+        self.first(&["status", "state"])
+    }
+
+    pub(crate) fn owners(&self) -> Vec<String> {
+        self.list(&["owner", "owners", "author"])
+    }
+
+    pub(crate) fn tags(&self) -> Vec<String> {
+        self.list(&["tags", "tag", "labels"])
+    }
+
+    /// Items of the first of `keys` present, from a list or a single value.
+    fn list(&self, keys: &[&str]) -> Vec<String> {
+        keys.iter()
+            .find_map(|key| self.get(key))
+            .map(|values| {
+                // `tags: a, b` is as common as a real list.
+                values
+                    .iter()
+                    .flat_map(|value| value.split(','))
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Drops a trailing ` # comment` outside of quotes.
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (idx, ch) in line.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(open), _) if ch == open => quote = None,
+            (None, '#') if idx == 0 || line[..idx].ends_with(char::is_whitespace) => {
+                return &line[..idx];
+            }
+            _ => {}
+        }
+    }
+    line
+}
+
+fn yaml_flow_list(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    Some(
+        inner
+            .split(',')
+            .map(unquote)
+            .filter(|item| !item.is_empty())
+            .collect(),
+    )
+}
+
+/// Reads the flat subset of YAML front matter uses in practice: top-level
+/// `key: value` pairs, `[a, b]` flow lists and `- item` block lists.
+/// Indented mappings under a key are kept as `sub: value` text.
+fn parse_yaml(text: &str) -> Vec<(String, Vec<String>)> {
+    let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+    for raw in text.lines() {
+        let line = strip_yaml_comment(raw).trim_end();
+        if line.trim().is_empty() || line.trim() == "---" || line.trim() == "..." {
+            continue;
+        }
+
+        let indented = line.starts_with(char::is_whitespace);
+        let trimmed = line.trim_start();
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or((trimmed == "-").then_some(""))
+        {
+            if let Some((_, values)) = fields.last_mut() {
+                values.push(unquote(item));
+            }
+            continue;
+        }
+        if indented {
+            if let Some((_, values)) = fields.last_mut() {
+                values.push(trimmed.to_string());
+            }
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let values = match yaml_flow_list(value) {
+            Some(items) => items,
+            None if value.is_empty() => Vec::new(),
+            None => vec![unquote(value)],
+        };
+        fields.push((key.trim().to_string(), values));
+    }
+    fields
+}
+
+fn toml_values(value: &toml::Value) -> Vec<String> {
+    match value {
+        toml::Value::String(text) => vec![text.clone()],
+        toml::Value::Array(items) => items.iter().flat_map(toml_values).collect(),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(key, value)| format!("{key}: {}", toml_values(value).join(", ")))
+            .collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+fn parse_toml(text: &str) -> Vec<(String, Vec<String>)> {
+    let Ok(table) = text.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    // `toml::Table` is sorted; put keys back in source order.
+    let position = |key: &str| {
+        text.lines()
+            .position(|line| {
+                line.trim_start()
+                    .strip_prefix(key)
+                    .is_some_and(|rest| rest.trim_start().starts_with(['=', ']']))
+                    || line.trim() == format!("[{key}]")
+            })
+            .unwrap_or(usize::MAX)
+    };
+    let mut fields: Vec<(usize, String, Vec<String>)> = table
+        .iter()
+        .map(|(key, value)| (position(key), key.clone(), toml_values(value)))
+        .collect();
+    fields.sort_by_key(|(position, _, _)| *position);
+    fields
+        .into_iter()
+        .map(|(_, key, values)| (key, values))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_matter_reads_yaml_fields_and_lists() {
+        let yaml = "title: \"Release plan\"\nstatus: draft # pending review\nowner: ana\ntags: [infra, 'q3']\nreviewers:\n  - bo\n  - cy\n";
+        let meta = FrontMatter::parse(FrontMatterFormat::Yaml, yaml);
+        assert_eq!(meta.title(), Some("Release plan"));
+        assert_eq!(meta.status(), Some("draft"));
+        assert_eq!(meta.owners(), vec!["ana"]);
+        assert_eq!(meta.tags(), vec!["infra", "q3"]);
+        assert_eq!(
+            meta.get("Reviewers"),
+            Some(&["bo".to_string(), "cy".to_string()][..])
+        );
+        let keys: Vec<&str> = meta.fields.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["title", "status", "owner", "tags", "reviewers"]);
+    }
+
+    #[test]
+    fn front_matter_reads_toml_in_source_order() {
+        let toml =
+            "title = \"Spec\"\nstatus = \"review\"\ntags = [\"api\", \"v2\"]\ndraft = true\n";
+        let meta = FrontMatter::parse(FrontMatterFormat::Toml, toml);
+        let keys: Vec<&str> = meta.fields.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["title", "status", "tags", "draft"]);
+        assert_eq!(meta.status(), Some("review"));
+        assert_eq!(meta.tags(), vec!["api", "v2"]);
+        assert!(meta.owners().is_empty());
+        assert_eq!(meta.get("draft"), Some(&["true".to_string()][..]));
+        assert!(FrontMatter::parse(FrontMatterFormat::Toml, "not = [toml")
+            .fields
+            .is_empty());
+    }
+
+    #[test]
+    fn front_matter_reads_every_owner() {
+        let list = FrontMatter::parse(FrontMatterFormat::Yaml, "owners:\n  - ana\n  - bo\n");
+        assert_eq!(list.owners(), vec!["ana", "bo"]);
+        let inline = FrontMatter::parse(FrontMatterFormat::Yaml, "owner: ana, bo\n");
+        assert_eq!(inline.owners(), vec!["ana", "bo"]);
+        let toml = FrontMatter::parse(FrontMatterFormat::Toml, "owners = [\"cy\", \"di\"]\n");
+        assert_eq!(toml.owners(), vec!["cy", "di"]);
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+
+/// A markdown file as recorded in one git commit.
+#[derive(Clone, Debug)]
+pub(crate) struct GitRevision {
+    pub(crate) short_sha: String,
+    pub(crate) committed_at: SystemTime,
+    /// File contents at the commit; empty when the file did not exist yet.
+    pub(crate) source: String,
+}
+
+/// One commit that touched a file, with the file's contents at that commit.
+#[derive(Clone, Debug)]
+pub(crate) struct GitCommit {
+    pub(crate) short_sha: String,
+    pub(crate) author: String,
+    pub(crate) committed_at: SystemTime,
+    pub(crate) subject: String,
+    pub(crate) source: String,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git {}: {}", args.join(" "), stderr.trim()));
+    }
+    String::from_utf8(output.stdout).context("git produced non-UTF-8 output")
+}
+
+/// Directory to run git in and the `./name` spec for `path` relative to it.
+fn split_path(path: &Path) -> Result<(&Path, String)> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", path.display()))?;
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    Ok((dir, format!("./{}", name.to_string_lossy())))
+}
+
+/// Reads `path` as of `rev` with the local `git` binary.
+pub(crate) fn file_at_revision(path: &Path, rev: &str) -> Result<GitRevision> {
+    let (dir, spec) = split_path(path)?;
+    let sha = git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{rev}^{{commit}}"),
+        ],
+    )
+    .map_err(|_| anyhow!("{rev} is not a commit in this repository"))?;
+    let sha = sha.trim();
+
+    let meta = git(dir, &["log", "-1", "--format=%h%x00%ct", sha])?;
+    let (short_sha, timestamp) = meta
+        .trim()
+        .split_once('\0')
+        .ok_or_else(|| anyhow!("Unexpected git log output"))?;
+    let committed_at = UNIX_EPOCH + Duration::from_secs(timestamp.parse().unwrap_or(0));
+
+    let source = git(dir, &["show", &format!("{sha}:{spec}")]).unwrap_or_default();
+    Ok(GitRevision {
+        short_sha: short_sha.to_string(),
+        committed_at,
+        source,
+    })
+}
+
+/// Loads the last `count` commits that touched `path`, oldest first.
+pub(crate) fn file_history(path: &Path, count: usize) -> Result<Vec<GitCommit>> {
+    let (dir, spec) = split_path(path)?;
+    let log = git(
+        dir,
+        &[
+            "log",
+            &format!("-n{count}"),
+            "--format=%h%x00%an%x00%ct%x00%s",
+            "--",
+            &spec,
+        ],
+    )?;
+
+    let mut commits = Vec::new();
+    for line in log.lines().rev() {
+        let mut fields = line.splitn(4, '\0');
+        let (Some(sha), Some(author), Some(timestamp), Some(subject)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // Commits that deleted the file have nothing to show.
+        let Ok(source) = git(dir, &["show", &format!("{sha}:{spec}")]) else {
+            continue;
+        };
+        commits.push(GitCommit {
+            short_sha: sha.to_string(),
+            author: author.to_string(),
+            committed_at: UNIX_EPOCH + Duration::from_secs(timestamp.parse().unwrap_or(0)),
+            subject: subject.to_string(),
+            source,
+        });
+    }
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn git_reads_committed_contents_and_history() {
+        let dir = env::temp_dir().join(format!("catmd-git-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let setup = [
+            vec!["init", "-q"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "user.name", "Test"],
+        ];
+        if setup.iter().any(|args| git(&dir, args).is_err()) {
+            // No usable git binary in this environment.
+            return;
+        }
+        let file = dir.join("notes.md");
+        fs::write(&file, "# Notes\n\ncommitted\n").unwrap();
+        git(&dir, &["add", "notes.md"]).unwrap();
+        git(&dir, &["commit", "-q", "-m", "initial"]).unwrap();
+        fs::write(&file, "# Notes\n\nedited\n").unwrap();
+
+        let base = file_at_revision(&file, "HEAD").unwrap();
+        assert_eq!(base.source, "# Notes\n\ncommitted\n");
+        assert_eq!(base.short_sha.len(), 7);
+        assert!(file_at_revision(&file, "no-such-rev").is_err());
+
+        git(&dir, &["commit", "-q", "-am", "edit notes"]).unwrap();
+        let history = file_history(&file, 5).unwrap();
+        let subjects: Vec<&str> = history.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["initial", "edit notes"]);
+        assert_eq!(history[1].author, "Test");
+        assert_eq!(history[1].source, "# Notes\n\nedited\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
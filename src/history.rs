@@ -0,0 +1,310 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const RETENTION_DAYS: u64 = 5;
+const RETENTION: Duration = Duration::from_secs(RETENTION_DAYS * 86_400);
+const GLOBAL_MAX_BYTES: u64 = 256 * 1024 * 1024;
+const PER_FILE_MAX_BYTES: u64 = 16 * 1024 * 1024;
+const HISTORY_DIR: &str = "watch-history";
+const RECORD_EXT: &str = "jsonl";
+
+/// One persisted watch snapshot, stored as a JSON line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct SnapshotRecord {
+    pub(crate) path: String,
+    pub(crate) timestamp: u64,
+    pub(crate) revision: u64,
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+    pub(crate) hunks: usize,
+    pub(crate) top_section: Option<String>,
+    pub(crate) source: String,
+}
+
+impl SnapshotRecord {
+    pub(crate) fn created_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.timestamp)
+    }
+}
+
+pub(crate) fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RestoredHistory {
+    pub(crate) records: Vec<SnapshotRecord>,
+    pub(crate) corrupt: usize,
+}
+
+/// `$XDG_STATE_HOME/catmd`, falling back to `~/.local/state/catmd`.
+fn state_root() -> Option<PathBuf> {
+    if let Some(state) = env::var_os("XDG_STATE_HOME").filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(state).join("catmd"));
+    }
+    let home = env::var_os("HOME").filter(|value| !value.is_empty())?;
+    Some(PathBuf::from(home).join(".local/state/catmd"))
+}
+
+/// FNV-1a, so history file names stay stable across builds and toolchains.
+fn path_key(path: &Path) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in path.to_string_lossy().as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// On-disk watch history, one JSON-lines file per canonical document path.
+#[derive(Clone, Debug)]
+pub(crate) struct HistoryStore {
+    dir: PathBuf,
+}
+
+impl HistoryStore {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub(crate) fn open_default() -> Option<Self> {
+        state_root().map(|root| Self::new(root.join(HISTORY_DIR)))
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn file_for(&self, canonical: &Path) -> PathBuf {
+        self.dir
+            .join(format!("{}.{RECORD_EXT}", path_key(canonical)))
+    }
+
+    fn read_records(&self, canonical: &Path) -> Result<RestoredHistory> {
+        let file = self.file_for(canonical);
+        let contents = match fs::read_to_string(&file) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(RestoredHistory::default())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {}", file.display()))
+            }
+        };
+
+        let wanted = canonical.to_string_lossy();
+        let mut restored = RestoredHistory::default();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<SnapshotRecord>(line) {
+                Ok(record) if record.path == wanted => restored.records.push(record),
+                Ok(_) => {}
+                Err(_) => restored.corrupt = restored.corrupt.saturating_add(1),
+            }
+        }
+        Ok(restored)
+    }
+
+    fn write_records(&self, canonical: &Path, records: &[SnapshotRecord]) -> Result<()> {
+        let file = self.file_for(canonical);
+        if records.is_empty() {
+            if file.exists() {
+                fs::remove_file(&file)
+                    .with_context(|| format!("Failed to remove {}", file.display()))?;
+            }
+            return Ok(());
+        }
+
+        let mut body = String::new();
+        for record in records {
+            body.push_str(&serde_json::to_string(record)?);
+            body.push('\n');
+        }
+        let tmp = file.with_extension(format!("{RECORD_EXT}.tmp"));
+        fs::write(&tmp, body).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &file).with_context(|| format!("Failed to replace {}", file.display()))
+    }
+
+    /// Loads the snapshots for `canonical` that are still inside the
+    /// retention window, oldest first. Corrupt lines are counted and skipped.
+    pub(crate) fn load(&self, canonical: &Path, now: SystemTime) -> Result<RestoredHistory> {
+        let mut restored = self.read_records(canonical)?;
+        let cutoff = unix_seconds(now).saturating_sub(RETENTION.as_secs());
+        restored.records.retain(|record| record.timestamp >= cutoff);
+        restored
+            .records
+            .sort_by_key(|record| (record.timestamp, record.revision));
+        Ok(restored)
+    }
+
+    /// Appends one snapshot, then enforces retention and size caps.
+    pub(crate) fn append(&self, record: &SnapshotRecord, now: SystemTime) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let canonical = PathBuf::from(&record.path);
+        let file = self.file_for(&canonical);
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file)
+            .and_then(|mut handle| handle.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to append to {}", file.display()))?;
+
+        let size = fs::metadata(&file).map(|meta| meta.len()).unwrap_or(0);
+        if size > PER_FILE_MAX_BYTES {
+            self.prune_file(&canonical, now, PER_FILE_MAX_BYTES)?;
+        }
+        self.prune_global(now, GLOBAL_MAX_BYTES)
+    }
+
+    /// Drops expired records for one document and evicts the oldest until
+    /// the file fits in `max_bytes`.
+    fn prune_file(&self, canonical: &Path, now: SystemTime, max_bytes: u64) -> Result<()> {
+        let mut records = self.load(canonical, now)?.records;
+        let record_size = |record: &SnapshotRecord| {
+            serde_json::to_string(record).map_or(0, |line| line.len() as u64 + 1)
+        };
+        let mut total: u64 = records.iter().map(record_size).sum();
+        while total > max_bytes && !records.is_empty() {
+            total = total.saturating_sub(record_size(&records.remove(0)));
+        }
+        self.write_records(canonical, &records)
+    }
+
+    /// Removes history files untouched for longer than the retention window,
+    /// then the least recently written files until the directory fits in
+    /// `max_bytes`.
+    fn prune_global(&self, now: SystemTime, max_bytes: u64) -> Result<()> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(());
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.path().extension().and_then(|ext| ext.to_str()) == Some(RECORD_EXT)
+            })
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), entry.path()))
+            })
+            .collect();
+        files.sort_by_key(|(modified, _, _)| *modified);
+
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        for (modified, len, path) in files {
+            let expired = now
+                .duration_since(modified)
+                .is_ok_and(|age| age > RETENTION);
+            if !expired && total <= max_bytes {
+                continue;
+            }
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            total = total.saturating_sub(len);
+        }
+        Ok(())
+    }
+
+    /// Deletes every persisted history file. Returns how many were removed.
+    pub(crate) fn clear(&self) -> Result<usize> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(0);
+        };
+        let mut removed = 0usize;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_file() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                removed = removed.saturating_add(1);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> HistoryStore {
+        let dir = env::temp_dir().join(format!("catmd-history-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        HistoryStore::new(dir)
+    }
+
+    fn record(path: &str, timestamp: u64, revision: u64, source: &str) -> SnapshotRecord {
+        SnapshotRecord {
+            path: path.to_string(),
+            timestamp,
+            revision,
+            added: 1,
+            removed: 0,
+            hunks: 1,
+            top_section: None,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn history_store_round_trips_and_skips_corrupt_lines() {
+        let store = temp_store("roundtrip");
+        let now = UNIX_EPOCH + Duration::from_secs(10 * 86_400);
+        let doc = Path::new("/notes/plan.md");
+        let now_secs = unix_seconds(now);
+
+        store
+            .append(&record("/notes/plan.md", now_secs - 60, 1, "a"), now)
+            .unwrap();
+        store
+            .append(&record("/notes/plan.md", now_secs, 2, "b"), now)
+            .unwrap();
+        let file = store.file_for(doc);
+        let mut handle = OpenOptions::new().append(true).open(&file).unwrap();
+        handle.write_all(b"{\"path\": truncated").unwrap();
+
+        let restored = store.load(doc, now).unwrap();
+        let sources: Vec<&str> = restored.records.iter().map(|r| r.source.as_str()).collect();
+        assert_eq!(sources, vec!["a", "b"]);
+        assert_eq!(restored.corrupt, 1);
+
+        assert_eq!(store.clear().unwrap(), 1);
+        assert!(store.load(doc, now).unwrap().records.is_empty());
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn history_store_drops_records_outside_retention() {
+        let store = temp_store("retention");
+        let now = UNIX_EPOCH + Duration::from_secs(30 * 86_400);
+        let now_secs = unix_seconds(now);
+        let doc = Path::new("/notes/old.md");
+
+        store
+            .append(
+                &record("/notes/old.md", now_secs - 6 * 86_400, 1, "old"),
+                now,
+            )
+            .unwrap();
+        store
+            .append(&record("/notes/old.md", now_secs, 2, "new"), now)
+            .unwrap();
+
+        let restored = store.load(doc, now).unwrap();
+        assert_eq!(restored.records.len(), 1);
+        assert_eq!(restored.records[0].source, "new");
+
+        store.prune_file(doc, now, 1).unwrap();
+        assert!(!store.file_for(doc).exists());
+        let _ = fs::remove_dir_all(store.dir());
+    }
+}
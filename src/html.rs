@@ -0,0 +1,275 @@
+use std::borrow::Cow;
+
+/// A piece of the raw HTML that Markdown passes through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum HtmlToken<'a> {
+    Text(&'a str),
+    /// Part of a `<!-- -->` comment, delimiters included. Block comments
+    /// arrive one line per event, so a comment can span several calls.
+    Comment(&'a str),
+    Tag(HtmlTag),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct HtmlTag {
+    /// Lowercase tag name.
+    pub(crate) name: String,
+    pub(crate) closing: bool,
+    pub(crate) attrs: Vec<(String, String)>,
+}
+
+impl HtmlTag {
+    pub(crate) fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Splits `html` into text, comments and tags. `in_comment` carries an
+/// unterminated comment over to the next call. Declarations such as
+/// `<!DOCTYPE>` are dropped, and a `<` that does not start a tag stays text.
+pub(crate) fn tokenize<'a>(html: &'a str, in_comment: &mut bool) -> Vec<HtmlToken<'a>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0usize;
+    while pos < html.len() {
+        let rest = &html[pos..];
+        if *in_comment {
+            let body = if rest.starts_with("<!--") { 4 } else { 0 };
+            match rest[body..].find("-->") {
+                Some(end) => {
+                    let end = body + end + 3;
+                    tokens.push(HtmlToken::Comment(&rest[..end]));
+                    *in_comment = false;
+                    pos += end;
+                }
+                None => {
+                    tokens.push(HtmlToken::Comment(rest));
+                    pos = html.len();
+                }
+            }
+            continue;
+        }
+
+        let Some(lt) = rest.find('<') else {
+            tokens.push(HtmlToken::Text(rest));
+            break;
+        };
+        if lt > 0 {
+            tokens.push(HtmlToken::Text(&rest[..lt]));
+        }
+        let markup = &rest[lt..];
+        if markup.starts_with("<!--") {
+            *in_comment = true;
+            pos += lt;
+            continue;
+        }
+        match parse_tag(markup) {
+            Some((tag, len)) => {
+                if let Some(tag) = tag {
+                    tokens.push(HtmlToken::Tag(tag));
+                }
+                pos += lt + len;
+            }
+            None => {
+                tokens.push(HtmlToken::Text(&markup[..1]));
+                pos += lt + 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Parses the tag at the start of `markup`, returning it (`None` for
+/// declarations) and its length in bytes.
+fn parse_tag(markup: &str) -> Option<(Option<HtmlTag>, usize)> {
+    let mut quote = None;
+    let end = markup.char_indices().skip(1).find_map(|(idx, ch)| {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(open), _) if ch == open => quote = None,
+            (None, '>') => return Some(idx),
+            _ => {}
+        }
+        None
+    })?;
+    let inner = &markup[1..end];
+    if inner.starts_with(['!', '?']) {
+        return Some((None, end + 1));
+    }
+
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+    let name_len = inner
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '-'))
+        .unwrap_or(inner.len());
+    if name_len == 0 || !inner.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        return None;
+    }
+    let tag = HtmlTag {
+        name: inner[..name_len].to_ascii_lowercase(),
+        closing,
+        attrs: parse_attrs(inner[name_len..].trim_end_matches('/')),
+    };
+    Some((Some(tag), end + 1))
+}
+
+fn parse_attrs(mut text: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    loop {
+        text = text.trim_start();
+        let name_len = text
+            .find(|ch: char| ch.is_whitespace() || ch == '=' || ch == '/')
+            .unwrap_or(text.len());
+        if name_len == 0 {
+            let Some(ch) = text.chars().next() else {
+                return attrs;
+            };
+            text = &text[ch.len_utf8()..];
+            continue;
+        }
+        let name = text[..name_len].to_ascii_lowercase();
+        text = text[name_len..].trim_start();
+
+        let Some(value_text) = text.strip_prefix('=') else {
+            attrs.push((name, String::new()));
+            continue;
+        };
+        let value_text = value_text.trim_start();
+        let (value, rest) = match value_text.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let body = &value_text[1..];
+                match body.find(quote) {
+                    Some(end) => (&body[..end], &body[end + 1..]),
+                    None => (body, ""),
+                }
+            }
+            _ => {
+                let end = value_text
+                    .find(char::is_whitespace)
+                    .unwrap_or(value_text.len());
+                (&value_text[..end], &value_text[end..])
+            }
+        };
+        attrs.push((name, decode_entities(value).into_owned()));
+        text = rest;
+    }
+}
+
+/// Decodes the character references common in hand-written HTML: the
+/// named `&amp; &lt; &gt; &quot; &apos; &nbsp;` and numeric `&#NN;` /
+/// `&#xHH;`. Anything else is left as written.
+pub(crate) fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let candidate = &rest[amp..];
+        let decoded = candidate
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity_char(&candidate[1..end])?, end + 1)));
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &candidate[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn entity_char(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, closing: bool) -> HtmlToken<'static> {
+        HtmlToken::Tag(HtmlTag {
+            name: name.to_string(),
+            closing,
+            attrs: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn tokenize_splits_tags_text_and_comments() {
+        let mut in_comment = false;
+        let tokens = tokenize(
+            "<KBD>Ctrl</kbd> a < b<br/><!-- x --><!DOCTYPE html>",
+            &mut in_comment,
+        );
+        assert_eq!(
+            tokens,
+            vec![
+                tag("kbd", false),
+                HtmlToken::Text("Ctrl"),
+                tag("kbd", true),
+                HtmlToken::Text(" a "),
+                HtmlToken::Text("<"),
+                HtmlToken::Text(" b"),
+                tag("br", false),
+                HtmlToken::Comment("<!-- x -->"),
+            ]
+        );
+        assert!(!in_comment);
+
+        assert_eq!(
+            tokenize("<!-- open\n", &mut in_comment),
+            vec![HtmlToken::Comment("<!-- open\n")]
+        );
+        assert!(in_comment);
+        assert_eq!(
+            tokenize("still --> after", &mut in_comment),
+            vec![HtmlToken::Comment("still -->"), HtmlToken::Text(" after")]
+        );
+        assert!(!in_comment);
+    }
+
+    #[test]
+    fn tags_keep_attributes_and_entities_decode() {
+        let mut in_comment = false;
+        let tokens = tokenize(
+            "<img src=\"a.png\" alt='R&amp;D > plan' width=40 hidden>",
+            &mut in_comment,
+        );
+        let [HtmlToken::Tag(img)] = tokens.as_slice() else {
+            panic!("expected one tag, got {tokens:?}");
+        };
+        assert_eq!(img.name, "img");
+        assert_eq!(img.attr("SRC"), Some("a.png"));
+        assert_eq!(img.attr("alt"), Some("R&D > plan"));
+        assert_eq!(img.attr("width"), Some("40"));
+        assert_eq!(img.attr("hidden"), Some(""));
+        assert_eq!(decode_entities("&lt;&#65;&#x42;&bogus; &"), "<AB&bogus; &");
+    }
+}
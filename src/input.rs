@@ -1,14 +1,19 @@
+use std::env;
 use std::fs;
 use std::io::{self, IsTerminal, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::theme::ThemePreset;
 
 const HISTORY_PARSE_ERR: &str = "--history must be a positive integer";
 const HISTORY_MIN_ERR: &str = "--history must be at least 1";
 const NO_INPUT_ERR: &str = "No input provided. Pass a markdown file or pipe markdown into stdin.";
 const READ_STDIN_ERR: &str = "Failed to read markdown from stdin";
+const STDIN_WITH_FILES_ERR: &str = "'-' (stdin) cannot be combined with other files";
+const DIRECTORY_WITH_FILES_ERR: &str = "A directory cannot be combined with other inputs";
 
 fn parse_history(value: &str) -> std::result::Result<usize, String> {
     let parsed: usize = value.parse().map_err(|_| HISTORY_PARSE_ERR.to_string())?;
@@ -18,6 +23,76 @@ fn parse_history(value: &str) -> std::result::Result<usize, String> {
     Ok(parsed)
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ColorWhen {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Ndjson,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct TasksArgs {
+    /// Markdown files or directories to scan (directories are searched recursively).
+    #[arg(required = true)]
+    pub(crate) paths: Vec<PathBuf>,
+
+    /// Only list unresolved tasks (open or in progress).
+    #[arg(long, conflicts_with = "done")]
+    pub(crate) open: bool,
+
+    /// Only list completed tasks.
+    #[arg(long)]
+    pub(crate) done: bool,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub(crate) format: OutputFormat,
+
+    /// Exit with status 0 even when unresolved tasks remain.
+    #[arg(long)]
+    pub(crate) exit_zero: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MetaArgs {
+    /// Markdown files or directories to scan (directories are searched recursively).
+    #[arg(required = true)]
+    pub(crate) paths: Vec<PathBuf>,
+
+    /// Only list documents whose front matter status matches (case-insensitive).
+    #[arg(long)]
+    pub(crate) status: Option<String>,
+
+    /// Only list documents with this owner among their owners.
+    #[arg(long)]
+    pub(crate) owner: Option<String>,
+
+    /// Only list documents carrying this tag.
+    #[arg(long)]
+    pub(crate) tag: Option<String>,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub(crate) format: OutputFormat,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// List @agent tasks without opening the pager.
+    Tasks(TasksArgs),
+    /// Print front matter fields (title, status, owner, tags) of markdown files.
+    Meta(MetaArgs),
+}
+
 #[derive(Debug, Parser)]
 #[command(
     name = "catmd",
@@ -25,8 +100,13 @@ fn parse_history(value: &str) -> std::result::Result<usize, String> {
     about = "Render markdown for terminal workflows"
 )]
 pub(crate) struct Cli {
-    /// Markdown file path. Use '-' to read from stdin.
-    pub(crate) input: Option<String>,
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// Markdown file paths, opened as tabs in the pager, or one directory to
+    /// browse. Use '-' to read from stdin.
+    #[arg(value_name = "INPUT")]
+    pub(crate) inputs: Vec<String>,
 
     /// Force interactive pager mode.
     #[arg(short, long)]
@@ -40,9 +120,45 @@ pub(crate) struct Cli {
     #[arg(long)]
     pub(crate) watch: bool,
 
-    /// Number of in-memory snapshots to keep while watching.
-    #[arg(long, default_value_t = 50, value_parser = parse_history)]
-    pub(crate) history: usize,
+    /// Number of in-memory snapshots to keep while watching [default: 50, or `history` from the config file].
+    #[arg(long, value_parser = parse_history)]
+    pub(crate) history: Option<usize>,
+
+    /// Git revision to compare against (defaults to HEAD inside a git repository).
+    #[arg(long, value_name = "REV")]
+    pub(crate) since: Option<String>,
+
+    /// Do not seed the timeline with a git baseline.
+    #[arg(long, conflicts_with = "since")]
+    pub(crate) no_since: bool,
+
+    /// Load the last N commits that touched the file into the revision timeline.
+    #[arg(long, value_name = "N")]
+    pub(crate) git_log: Option<usize>,
+
+    /// Keep watch history in memory only (no reads or writes under the XDG state directory).
+    #[arg(long)]
+    pub(crate) no_persist_history: bool,
+
+    /// Delete all persisted watch history, then exit unless a file is given.
+    #[arg(long)]
+    pub(crate) clear_history: bool,
+
+    /// Append "(done YYYY-MM-DD)", the UTC date, when completing an agent task from the pager.
+    #[arg(long)]
+    pub(crate) done_stamp: bool,
+
+    /// Color theme preset, overriding `theme` from the config file.
+    #[arg(long, value_enum, value_name = "PRESET")]
+    pub(crate) theme: Option<ThemePreset>,
+
+    /// List theme presets and syntax highlighting themes, then exit.
+    #[arg(long)]
+    pub(crate) list_themes: bool,
+
+    /// When to emit ANSI colors in non-interactive output.
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorWhen::Auto)]
+    pub(crate) color: ColorWhen,
 }
 
 #[derive(Clone, Debug)]
@@ -54,15 +170,26 @@ pub(crate) struct LoadResult {
 #[derive(Clone, Debug)]
 pub(crate) enum InputSource {
     File(PathBuf),
+    /// Markdown files under a directory, browsed with the file tree.
+    Directory(PathBuf),
     Stdin,
 }
 
-pub(crate) fn detect_input(cli: &Cli) -> Result<InputSource> {
-    match cli.input.as_deref() {
-        Some("-") => Ok(InputSource::Stdin),
-        Some(path) => Ok(InputSource::File(PathBuf::from(path))),
-        None if io::stdin().is_terminal() => Err(anyhow!(NO_INPUT_ERR)),
-        None => Ok(InputSource::Stdin),
+/// The inputs to open, in order; never empty.
+pub(crate) fn detect_inputs(cli: &Cli) -> Result<Vec<InputSource>> {
+    match cli.inputs.as_slice() {
+        [] if io::stdin().is_terminal() => Err(anyhow!(NO_INPUT_ERR)),
+        [] => Ok(vec![InputSource::Stdin]),
+        [only] if only == "-" => Ok(vec![InputSource::Stdin]),
+        [only] if Path::new(only).is_dir() => Ok(vec![InputSource::Directory(PathBuf::from(only))]),
+        inputs if inputs.iter().any(|input| input == "-") => Err(anyhow!(STDIN_WITH_FILES_ERR)),
+        inputs if inputs.iter().any(|input| Path::new(input).is_dir()) => {
+            Err(anyhow!(DIRECTORY_WITH_FILES_ERR))
+        }
+        inputs => Ok(inputs
+            .iter()
+            .map(|input| InputSource::File(PathBuf::from(input)))
+            .collect()),
     }
 }
 
@@ -76,6 +203,7 @@ pub(crate) fn read_input(source: &InputSource) -> Result<LoadResult> {
                 source,
             })
         }
+        InputSource::Directory(path) => Err(anyhow!("{} is a directory", path.display())),
         InputSource::Stdin => {
             let mut buf = String::new();
             io::stdin()
@@ -93,6 +221,20 @@ fn is_tty_stdout() -> bool {
     io::stdout().is_terminal()
 }
 
+/// `--color=always` wins over `NO_COLOR`; `auto` colors only a TTY stdout
+/// when `NO_COLOR` is unset and the terminal is not `dumb`.
+pub(crate) fn color_output_enabled(when: ColorWhen) -> bool {
+    match when {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        ColorWhen::Auto => {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            let dumb = env::var("TERM").is_ok_and(|term| term == "dumb");
+            !no_color && !dumb && is_tty_stdout()
+        }
+    }
+}
+
 pub(crate) fn default_interactive(input: &InputSource) -> bool {
-    matches!(input, InputSource::File(_)) && is_tty_stdout()
+    matches!(input, InputSource::File(_) | InputSource::Directory(_)) && is_tty_stdout()
 }
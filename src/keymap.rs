@@ -0,0 +1,1066 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Everything a key can do in the pager. Config files name actions in
+/// kebab-case (`scroll-down`, `toggle-toc`, ...).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum Action {
+    Quit,
+    Help,
+    ScrollDown,
+    ScrollUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    Search,
+    NextMatch,
+    PrevMatch,
+    QuickCapture,
+    ToggleTask,
+    DeleteTask,
+    ToggleToc,
+    ToggleAgentInbox,
+    ToggleMetadata,
+    ToggleCallouts,
+    ToggleDetails,
+    ToggleAllDetails,
+    ToggleHtmlComments,
+    PrevHeading,
+    NextHeading,
+    PrevAgentTask,
+    NextAgentTask,
+    Select,
+    NextLink,
+    PrevLink,
+    OpenExternal,
+    Edit,
+    Back,
+    CycleAgentSort,
+    CycleAgentFilter,
+    ToggleFileTree,
+    CycleFileSort,
+    ToggleTimeline,
+    OlderRevision,
+    NewerRevision,
+    LiveRevision,
+    PrevHunk,
+    NextHunk,
+    ToggleDiff,
+    DiffLayout,
+    CommandPalette,
+    Reload,
+    ExportText,
+    NextTab,
+    PrevTab,
+    CloseTab,
+    OpenLinkInTab,
+}
+
+impl Action {
+    pub(crate) const ALL: [Self; 52] = [
+        Self::Quit,
+        Self::Help,
+        Self::ScrollDown,
+        Self::ScrollUp,
+        Self::HalfPageDown,
+        Self::HalfPageUp,
+        Self::PageDown,
+        Self::PageUp,
+        Self::Top,
+        Self::Bottom,
+        Self::Search,
+        Self::NextMatch,
+        Self::PrevMatch,
+        Self::QuickCapture,
+        Self::ToggleTask,
+        Self::DeleteTask,
+        Self::ToggleToc,
+        Self::ToggleAgentInbox,
+        Self::ToggleMetadata,
+        Self::ToggleCallouts,
+        Self::ToggleDetails,
+        Self::ToggleAllDetails,
+        Self::ToggleHtmlComments,
+        Self::PrevHeading,
+        Self::NextHeading,
+        Self::PrevAgentTask,
+        Self::NextAgentTask,
+        Self::Select,
+        Self::NextLink,
+        Self::PrevLink,
+        Self::OpenExternal,
+        Self::Edit,
+        Self::Back,
+        Self::CycleAgentSort,
+        Self::CycleAgentFilter,
+        Self::ToggleFileTree,
+        Self::CycleFileSort,
+        Self::ToggleTimeline,
+        Self::OlderRevision,
+        Self::NewerRevision,
+        Self::LiveRevision,
+        Self::PrevHunk,
+        Self::NextHunk,
+        Self::ToggleDiff,
+        Self::DiffLayout,
+        Self::CommandPalette,
+        Self::Reload,
+        Self::ExportText,
+        Self::NextTab,
+        Self::PrevTab,
+        Self::CloseTab,
+        Self::OpenLinkInTab,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Help => "help",
+            Self::ScrollDown => "scroll-down",
+            Self::ScrollUp => "scroll-up",
+            Self::HalfPageDown => "half-page-down",
+            Self::HalfPageUp => "half-page-up",
+            Self::PageDown => "page-down",
+            Self::PageUp => "page-up",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Search => "search",
+            Self::NextMatch => "next-match",
+            Self::PrevMatch => "prev-match",
+            Self::QuickCapture => "quick-capture",
+            Self::ToggleTask => "toggle-task",
+            Self::DeleteTask => "delete-task",
+            Self::ToggleToc => "toggle-toc",
+            Self::ToggleAgentInbox => "toggle-agent-inbox",
+            Self::ToggleMetadata => "toggle-metadata",
+            Self::ToggleCallouts => "toggle-callouts",
+            Self::ToggleDetails => "toggle-details",
+            Self::ToggleAllDetails => "toggle-all-details",
+            Self::ToggleHtmlComments => "toggle-html-comments",
+            Self::PrevHeading => "prev-heading",
+            Self::NextHeading => "next-heading",
+            Self::PrevAgentTask => "prev-agent-task",
+            Self::NextAgentTask => "next-agent-task",
+            Self::Select => "select",
+            Self::NextLink => "next-link",
+            Self::PrevLink => "prev-link",
+            Self::OpenExternal => "open-external",
+            Self::Edit => "edit",
+            Self::Back => "back",
+            Self::CycleAgentSort => "cycle-agent-sort",
+            Self::CycleAgentFilter => "cycle-agent-filter",
+            Self::ToggleFileTree => "toggle-file-tree",
+            Self::CycleFileSort => "cycle-file-sort",
+            Self::ToggleTimeline => "toggle-timeline",
+            Self::OlderRevision => "older-revision",
+            Self::NewerRevision => "newer-revision",
+            Self::LiveRevision => "live-revision",
+            Self::PrevHunk => "prev-hunk",
+            Self::NextHunk => "next-hunk",
+            Self::ToggleDiff => "toggle-diff",
+            Self::DiffLayout => "diff-layout",
+            Self::CommandPalette => "command-palette",
+            Self::Reload => "reload",
+            Self::ExportText => "export-text",
+            Self::NextTab => "next-tab",
+            Self::PrevTab => "prev-tab",
+            Self::CloseTab => "close-tab",
+            Self::OpenLinkInTab => "open-link-in-tab",
+        }
+    }
+
+    /// Command palette entry for actions worth running by name; motions and
+    /// context-bound keys are left out.
+    pub(crate) fn title(self) -> Option<&'static str> {
+        let title = match self {
+            Self::Quit => "Quit",
+            Self::Help => "Show keyboard shortcuts",
+            Self::QuickCapture => "Add new @agent task",
+            Self::ToggleTask => "Toggle @agent task done",
+            Self::ToggleToc => "Toggle TOC",
+            Self::ToggleAgentInbox => "Toggle Agent Inbox",
+            Self::ToggleMetadata => "Toggle front matter metadata panel",
+            Self::ToggleCallouts => "Toggle callouts panel",
+            Self::ToggleDetails => "Expand/collapse <details> on screen",
+            Self::ToggleAllDetails => "Expand/collapse all <details>",
+            Self::ToggleHtmlComments => "Show/hide HTML comments",
+            Self::Search => "Search",
+            Self::OpenExternal => "Open selected link externally",
+            Self::Edit => "Edit in $VISUAL / $EDITOR",
+            Self::Back => "Go back",
+            Self::CycleAgentSort => "Agent Inbox: cycle sort",
+            Self::CycleAgentFilter => "Agent Inbox: cycle owner filter",
+            Self::ToggleFileTree => "Toggle file tree",
+            Self::CycleFileSort => "File tree: sort by name / recently modified",
+            Self::ToggleTimeline => "Toggle timeline",
+            Self::LiveRevision => "Jump to live revision",
+            Self::ToggleDiff => "Toggle diff view",
+            Self::Reload => "Reload file",
+            Self::ExportText => "Export as plain text",
+            Self::NextTab => "Next tab",
+            Self::PrevTab => "Previous tab",
+            Self::CloseTab => "Close tab",
+            Self::OpenLinkInTab => "Open selected link in a new tab",
+            _ => return None,
+        };
+        Some(title)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// A key press with its modifiers. For characters and Tab, Shift is folded
+/// into the key itself (`G`, `BackTab`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    const fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    const fn char(ch: char) -> Self {
+        Self::plain(KeyCode::Char(ch))
+    }
+
+    const fn ctrl(ch: char) -> Self {
+        Self::new(KeyCode::Char(ch), KeyModifiers::CONTROL)
+    }
+
+    const fn alt(ch: char) -> Self {
+        Self::new(KeyCode::Char(ch), KeyModifiers::ALT)
+    }
+
+    pub(crate) fn from_event(event: &KeyEvent) -> Self {
+        let mut modifiers = event
+            .modifiers
+            .intersection(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match event.code {
+            // Some terminals report Shift-a as a lowercase `a` plus Shift.
+            KeyCode::Char(ch) if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(ch.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self::new(code, modifiers)
+    }
+
+    /// Parses `j`, `G`, `ctrl-d` / `C-d`, `alt-v` / `M-v`, `shift-tab`,
+    /// `shift-enter` and named keys such as `space`, `pgdn` or `backspace`.
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let mut rest = text.trim();
+        let mut modifiers = KeyModifiers::NONE;
+        let mut shift = false;
+        while rest.chars().count() > 1 {
+            let Some((prefix, tail)) = rest.split_once('-') else {
+                break;
+            };
+            match prefix.to_ascii_lowercase().as_str() {
+                "ctrl" | "c" => modifiers |= KeyModifiers::CONTROL,
+                "alt" | "m" | "meta" => modifiers |= KeyModifiers::ALT,
+                "shift" | "s" => shift = true,
+                _ => break,
+            }
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) if shift => KeyCode::Char(ch.to_ascii_uppercase()),
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "tab" if shift => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" | "escape" => KeyCode::Esc,
+                "backspace" | "bs" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pgup" | "pageup" => KeyCode::PageUp,
+                "pgdn" | "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                _ => return Err(anyhow!("Unknown key '{text}'")),
+            },
+        };
+        if shift && !matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Parses a space-separated key sequence such as `g t` or `ctrl-x k`.
+    pub(crate) fn parse_sequence(text: &str) -> Result<Vec<Self>> {
+        let keys = text
+            .split_whitespace()
+            .map(Self::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            return Err(anyhow!("Empty key '{text}'"));
+        }
+        Ok(keys)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::BackTab => f.write_str("Shift-Tab"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Delete => f.write_str("Del"),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// Built-in keymaps. They differ in motion keys; everything else uses the
+/// same letters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum KeymapPreset {
+    #[default]
+    Vim,
+    Less,
+    Emacs,
+}
+
+/// One or several keys for an action in the config file.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn keys(&self) -> &[String] {
+        match self {
+            Self::One(key) => std::slice::from_ref(key),
+            Self::Many(keys) => keys,
+        }
+    }
+}
+
+/// `[keys]` in the config file: a preset plus per-action replacements,
+/// e.g. `bind = { toggle-toc = "T", page-down = ["space", "f"] }`. Keys
+/// separated by spaces form a sequence (`next-tab = "g t"`).
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct KeyConfig {
+    pub(crate) preset: KeymapPreset,
+    pub(crate) bind: BTreeMap<String, KeyList>,
+}
+
+impl KeyConfig {
+    pub(crate) fn keymap(&self) -> Result<Keymap> {
+        let mut keymap = Keymap::preset(self.preset);
+        for (name, keys) in &self.bind {
+            let action =
+                Action::from_name(name).ok_or_else(|| anyhow!("Unknown action '{name}'"))?;
+            let sequences = keys
+                .keys()
+                .iter()
+                .map(|key| Key::parse_sequence(key))
+                .collect::<Result<Vec<_>>>()?;
+            keymap.bind(action, sequences);
+        }
+        Ok(keymap)
+    }
+}
+
+/// Key sequences in priority order. A sequence may map to several actions;
+/// the pager runs the first one that applies (`f` cycles the Agent Inbox
+/// filter while it is open and pages down otherwise in the less preset).
+#[derive(Clone, Debug)]
+pub(crate) struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+/// `gt` for plain characters, `Ctrl-x k` otherwise.
+pub(crate) fn sequence_label(keys: &[Key]) -> String {
+    let labels: Vec<String> = keys.iter().map(ToString::to_string).collect();
+    if labels.len() > 1 && labels.iter().all(|label| label.chars().count() == 1) {
+        labels.concat()
+    } else {
+        labels.join(" ")
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(KeymapPreset::Vim)
+    }
+}
+
+impl Keymap {
+    pub(crate) fn preset(preset: KeymapPreset) -> Self {
+        use Action::*;
+
+        let motion: Vec<(Action, Vec<Key>)> = match preset {
+            KeymapPreset::Vim => vec![
+                (ScrollDown, vec![Key::char('j'), Key::plain(KeyCode::Down)]),
+                (ScrollUp, vec![Key::char('k'), Key::plain(KeyCode::Up)]),
+                (HalfPageDown, vec![Key::ctrl('d')]),
+                (HalfPageUp, vec![Key::ctrl('u')]),
+                (
+                    PageDown,
+                    vec![Key::ctrl('f'), Key::plain(KeyCode::PageDown)],
+                ),
+                (PageUp, vec![Key::ctrl('b'), Key::plain(KeyCode::PageUp)]),
+                (Top, vec![Key::char('g'), Key::plain(KeyCode::Home)]),
+                (Bottom, vec![Key::char('G'), Key::plain(KeyCode::End)]),
+                (Search, vec![Key::char('/')]),
+                (NextMatch, vec![Key::char('n')]),
+                (PrevMatch, vec![Key::char('N')]),
+                (CommandPalette, vec![Key::ctrl('p'), Key::char(':')]),
+            ],
+            KeymapPreset::Less => vec![
+                (
+                    ScrollDown,
+                    vec![Key::char('j'), Key::plain(KeyCode::Down), Key::ctrl('n')],
+                ),
+                (
+                    ScrollUp,
+                    vec![
+                        Key::char('k'),
+                        Key::plain(KeyCode::Up),
+                        Key::char('y'),
+                        Key::ctrl('p'),
+                    ],
+                ),
+                (HalfPageDown, vec![Key::char('d'), Key::ctrl('d')]),
+                (HalfPageUp, vec![Key::char('u'), Key::ctrl('u')]),
+                (
+                    PageDown,
+                    vec![
+                        Key::char(' '),
+                        Key::char('f'),
+                        Key::plain(KeyCode::PageDown),
+                        Key::ctrl('f'),
+                    ],
+                ),
+                (
+                    PageUp,
+                    vec![Key::char('b'), Key::plain(KeyCode::PageUp), Key::ctrl('b')],
+                ),
+                (
+                    Top,
+                    vec![Key::char('g'), Key::char('<'), Key::plain(KeyCode::Home)],
+                ),
+                (
+                    Bottom,
+                    vec![Key::char('G'), Key::char('>'), Key::plain(KeyCode::End)],
+                ),
+                (Search, vec![Key::char('/')]),
+                (NextMatch, vec![Key::char('n')]),
+                (PrevMatch, vec![Key::char('N')]),
+                (CommandPalette, vec![Key::char(':'), Key::alt('x')]),
+            ],
+            KeymapPreset::Emacs => vec![
+                (ScrollDown, vec![Key::ctrl('n'), Key::plain(KeyCode::Down)]),
+                (ScrollUp, vec![Key::ctrl('p'), Key::plain(KeyCode::Up)]),
+                (HalfPageDown, vec![Key::ctrl('d')]),
+                (HalfPageUp, vec![Key::ctrl('u')]),
+                (
+                    PageDown,
+                    vec![
+                        Key::ctrl('v'),
+                        Key::char(' '),
+                        Key::plain(KeyCode::PageDown),
+                    ],
+                ),
+                (PageUp, vec![Key::alt('v'), Key::plain(KeyCode::PageUp)]),
+                (Top, vec![Key::alt('<'), Key::plain(KeyCode::Home)]),
+                (Bottom, vec![Key::alt('>'), Key::plain(KeyCode::End)]),
+                (Search, vec![Key::ctrl('s'), Key::char('/')]),
+                (NextMatch, vec![Key::char('n'), Key::alt('s')]),
+                (PrevMatch, vec![Key::char('N'), Key::ctrl('r')]),
+                (CommandPalette, vec![Key::alt('x'), Key::char(':')]),
+            ],
+        };
+
+        let shared: Vec<(Action, Vec<Key>)> = vec![
+            (Quit, vec![Key::char('q')]),
+            (Help, vec![Key::char('?')]),
+            (QuickCapture, vec![Key::char('A'), Key::ctrl('a')]),
+            (ToggleTask, vec![Key::char('x')]),
+            (DeleteTask, vec![Key::char('X')]),
+            (ToggleToc, vec![Key::char('t')]),
+            (ToggleFileTree, vec![Key::char('F')]),
+            (ToggleAgentInbox, vec![Key::char('a')]),
+            (ToggleMetadata, vec![Key::char('M')]),
+            (ToggleCallouts, vec![Key::char('c')]),
+            (ToggleDetails, vec![Key::char('z')]),
+            (ToggleAllDetails, vec![Key::char('Z')]),
+            (ToggleHtmlComments, vec![Key::char('H')]),
+            (PrevHeading, vec![Key::char('[')]),
+            (NextHeading, vec![Key::char(']')]),
+            (PrevAgentTask, vec![Key::char('{')]),
+            (NextAgentTask, vec![Key::char('}')]),
+            (Select, vec![Key::plain(KeyCode::Enter)]),
+            (NextLink, vec![Key::plain(KeyCode::Tab)]),
+            (PrevLink, vec![Key::plain(KeyCode::BackTab)]),
+            (OpenExternal, vec![Key::char('o')]),
+            (Edit, vec![Key::char('e')]),
+            (Back, vec![Key::plain(KeyCode::Backspace)]),
+            (CycleAgentSort, vec![Key::char('s')]),
+            (CycleFileSort, vec![Key::char('s')]),
+            (CycleAgentFilter, vec![Key::char('f')]),
+            (ToggleTimeline, vec![Key::char('v')]),
+            (
+                OlderRevision,
+                vec![Key::char('h'), Key::plain(KeyCode::Left)],
+            ),
+            (
+                NewerRevision,
+                vec![Key::char('l'), Key::plain(KeyCode::Right)],
+            ),
+            (LiveRevision, vec![Key::char('L')]),
+            (PrevHunk, vec![Key::char('(')]),
+            (NextHunk, vec![Key::char(')')]),
+            (ToggleDiff, vec![Key::char('D')]),
+            (DiffLayout, vec![Key::char('m')]),
+            (Reload, vec![Key::char('R')]),
+            (ExportText, vec![Key::char('E')]),
+        ];
+
+        let enter = |modifiers| Key::new(KeyCode::Enter, modifiers);
+        let ctrl = |code| Key::new(code, KeyModifiers::CONTROL);
+        let tabs: Vec<(Action, Vec<Vec<Key>>)> = match preset {
+            // `g` alone still goes to the top once the sequence times out.
+            KeymapPreset::Vim => vec![
+                (
+                    NextTab,
+                    vec![
+                        vec![Key::char('g'), Key::char('t')],
+                        vec![Key::char('J')],
+                        vec![ctrl(KeyCode::PageDown)],
+                    ],
+                ),
+                (
+                    PrevTab,
+                    vec![
+                        vec![Key::char('g'), Key::char('T')],
+                        vec![Key::char('K')],
+                        vec![ctrl(KeyCode::PageUp)],
+                    ],
+                ),
+                (CloseTab, vec![vec![Key::ctrl('w')]]),
+            ],
+            // Single keys, so `g` (top) never waits for a second one.
+            KeymapPreset::Less => vec![
+                (
+                    NextTab,
+                    vec![vec![Key::char('J')], vec![ctrl(KeyCode::PageDown)]],
+                ),
+                (
+                    PrevTab,
+                    vec![vec![Key::char('K')], vec![ctrl(KeyCode::PageUp)]],
+                ),
+                (CloseTab, vec![vec![Key::ctrl('w')]]),
+            ],
+            KeymapPreset::Emacs => vec![
+                (
+                    NextTab,
+                    vec![
+                        vec![Key::ctrl('x'), Key::plain(KeyCode::Right)],
+                        vec![ctrl(KeyCode::PageDown)],
+                    ],
+                ),
+                (
+                    PrevTab,
+                    vec![
+                        vec![Key::ctrl('x'), Key::plain(KeyCode::Left)],
+                        vec![ctrl(KeyCode::PageUp)],
+                    ],
+                ),
+                (CloseTab, vec![vec![Key::ctrl('x'), Key::char('k')]]),
+            ],
+        };
+
+        // Context-dependent actions come first so they win while they apply.
+        let mut groups: Vec<(Action, Vec<Vec<Key>>)> = shared
+            .into_iter()
+            .chain(motion)
+            .map(|(action, keys)| (action, keys.into_iter().map(|key| vec![key]).collect()))
+            .collect();
+        groups.extend(tabs);
+        groups.push((
+            OpenLinkInTab,
+            vec![
+                vec![enter(KeyModifiers::SHIFT)],
+                vec![enter(KeyModifiers::ALT)],
+            ],
+        ));
+        groups.sort_by_key(|(action, _)| {
+            !matches!(action, CycleAgentSort | CycleAgentFilter | CycleFileSort)
+        });
+        let bindings = groups
+            .into_iter()
+            .flat_map(|(action, keys)| keys.into_iter().map(move |keys| (keys, action)))
+            .collect();
+        Self { bindings }
+    }
+
+    /// Replaces the key sequences of `action`.
+    fn bind(&mut self, action: Action, sequences: Vec<Vec<Key>>) {
+        let position = self
+            .bindings
+            .iter()
+            .position(|(_, bound)| *bound == action)
+            .unwrap_or(self.bindings.len());
+        self.bindings.retain(|(_, bound)| *bound != action);
+        let position = position.min(self.bindings.len());
+        self.bindings.splice(
+            position..position,
+            sequences.into_iter().map(|keys| (keys, action)),
+        );
+    }
+
+    /// Actions bound to exactly `keys`, in priority order.
+    pub(crate) fn actions<'a>(&'a self, keys: &'a [Key]) -> impl Iterator<Item = Action> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |(bound, _)| bound.as_slice() == keys)
+            .map(|(_, action)| *action)
+    }
+
+    /// The key pressed after `pending`. Shift on keys such as arrows or
+    /// `PgDn` is dropped unless a binding asks for it, so `Shift-Down` still
+    /// scrolls while `Shift-Enter` can open a tab.
+    pub(crate) fn key_for_event(&self, pending: &[Key], event: &KeyEvent) -> Key {
+        let key = Key::from_event(event);
+        if !key.modifiers.contains(KeyModifiers::SHIFT) {
+            return key;
+        }
+        let bound = self.bindings.iter().any(|(bound, _)| {
+            bound.len() > pending.len() && bound.starts_with(pending) && bound[pending.len()] == key
+        });
+        if bound {
+            key
+        } else {
+            Key::new(key.code, key.modifiers.difference(KeyModifiers::SHIFT))
+        }
+    }
+
+    /// Whether `keys` starts a longer sequence, so the pager should wait for
+    /// the next key.
+    pub(crate) fn is_prefix(&self, keys: &[Key]) -> bool {
+        self.bindings
+            .iter()
+            .any(|(bound, _)| bound.len() > keys.len() && bound.starts_with(keys))
+    }
+
+    pub(crate) fn keys(&self, action: Action) -> Vec<&[Key]> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| keys.as_slice())
+            .collect()
+    }
+
+    /// The first two bindings of `action` for display, e.g. `j, Down` or
+    /// `gt`.
+    pub(crate) fn label(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys(action)
+            .into_iter()
+            .take(2)
+            .map(sequence_label)
+            .collect();
+        if keys.is_empty() {
+            "(unbound)".to_string()
+        } else {
+            keys.join(", ")
+        }
+    }
+
+    /// Rows of the `?` overlay: section title, then (keys, description).
+    pub(crate) fn help(&self) -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+        HELP.iter()
+            .map(|(section, rows)| {
+                let rows = rows
+                    .iter()
+                    .map(|(actions, text)| {
+                        let keys: Vec<String> =
+                            actions.iter().map(|action| self.label(*action)).collect();
+                        (keys.join(" / "), *text)
+                    })
+                    .collect();
+                (*section, rows)
+            })
+            .collect()
+    }
+}
+
+type HelpRow = (&'static [Action], &'static str);
+
+const HELP: &[(&str, &[HelpRow])] = &[
+    (
+        "General",
+        &[
+            (&[Action::Quit], "Quit"),
+            (&[Action::Help], "Toggle this shortcuts view"),
+            (
+                &[Action::QuickCapture],
+                "Add new @agent task (append to file)",
+            ),
+            (
+                &[Action::ToggleTask],
+                "Toggle selected/visible @agent task done",
+            ),
+            (
+                &[Action::DeleteTask],
+                "Delete selected/visible @agent task (press twice)",
+            ),
+            (&[Action::ScrollDown, Action::ScrollUp], "Scroll down / up"),
+            (
+                &[Action::HalfPageDown, Action::HalfPageUp],
+                "Half-page down / up",
+            ),
+            (&[Action::PageDown, Action::PageUp], "Page down / up"),
+            (&[Action::Top, Action::Bottom], "Top / bottom"),
+            (
+                &[Action::Search],
+                "Search (Ctrl-r regex, Ctrl-w whole word, Up/Down history)",
+            ),
+            (
+                &[Action::NextMatch, Action::PrevMatch],
+                "Next / previous match",
+            ),
+            (
+                &[Action::CommandPalette],
+                "Command palette: headings, links, tasks, revisions, commands",
+            ),
+            (
+                &[Action::Reload],
+                "Reload the file, or rescan the focused file tree",
+            ),
+            (
+                &[Action::ExportText],
+                "Export the rendered document as plain text",
+            ),
+        ],
+    ),
+    (
+        "Navigation",
+        &[
+            (&[Action::ToggleToc], "Toggle TOC"),
+            (
+                &[Action::ToggleFileTree],
+                "Show / focus / hide the file tree (directory mode)",
+            ),
+            (&[Action::ToggleAgentInbox], "Toggle Agent Inbox"),
+            (
+                &[Action::ToggleMetadata],
+                "Toggle front matter metadata panel",
+            ),
+            (
+                &[Action::ToggleCallouts],
+                "Toggle callouts panel ([!NOTE], [!IMPORTANT], ...)",
+            ),
+            (
+                &[Action::ToggleDetails, Action::ToggleAllDetails],
+                "Expand/collapse the <details> on screen / all of them",
+            ),
+            (&[Action::ToggleHtmlComments], "Show/hide HTML comments"),
+            (
+                &[Action::PrevHeading, Action::NextHeading],
+                "Previous / next heading",
+            ),
+            (
+                &[Action::PrevAgentTask, Action::NextAgentTask],
+                "Previous / next unresolved @agent task",
+            ),
+            (&[Action::Select], "Follow selected item/link"),
+            (
+                &[Action::NextLink, Action::PrevLink],
+                "Next / previous link",
+            ),
+            (&[Action::OpenExternal], "Open selected link externally"),
+            (&[Action::Edit], "Edit at current line in $VISUAL / $EDITOR"),
+            (&[Action::Back], "Go back in local markdown history"),
+        ],
+    ),
+    (
+        "Watch Mode",
+        &[
+            (&[Action::ToggleTimeline], "Toggle timeline"),
+            (
+                &[Action::OlderRevision, Action::NewerRevision],
+                "Older / newer revision",
+            ),
+            (&[Action::LiveRevision], "Jump to live revision"),
+            (
+                &[Action::PrevHunk, Action::NextHunk],
+                "Previous / next changed hunk",
+            ),
+            (
+                &[Action::ToggleDiff],
+                "Toggle diff view against the previous revision",
+            ),
+        ],
+    ),
+    (
+        "Tabs",
+        &[
+            (&[Action::NextTab, Action::PrevTab], "Next / previous tab"),
+            (&[Action::CloseTab], "Close tab"),
+            (&[Action::OpenLinkInTab], "Open selected link in a new tab"),
+        ],
+    ),
+    (
+        "Diff View",
+        &[
+            (
+                &[Action::OlderRevision, Action::NewerRevision],
+                "Older / newer target revision",
+            ),
+            (
+                &[Action::PrevHeading, Action::NextHeading],
+                "Older / newer base revision",
+            ),
+            (&[Action::DiffLayout], "Inline / side-by-side"),
+            (
+                &[Action::PrevHunk, Action::NextHunk],
+                "Previous / next hunk",
+            ),
+            (&[Action::ToggleDiff, Action::Quit], "Close (also Esc)"),
+        ],
+    ),
+    (
+        "Panels",
+        &[
+            (
+                &[Action::ScrollDown, Action::ScrollUp],
+                "Move selection in file tree, TOC, Agent Inbox or callouts",
+            ),
+            (
+                &[Action::CycleAgentSort],
+                "Agent Inbox: cycle sort (document/priority/due)",
+            ),
+            (
+                &[Action::CycleAgentFilter],
+                "Agent Inbox: cycle @agent:<name> filter",
+            ),
+            (
+                &[Action::CycleFileSort],
+                "File tree: sort by name / recently modified",
+            ),
+            (
+                &[Action::Select],
+                "Jump to selected TOC heading, agent task or callout",
+            ),
+            (
+                &[Action::Select],
+                "File tree: fold the selected directory, or read the file",
+            ),
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        Key::from_event(&KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn keys_parse_and_normalize_shift() {
+        assert_eq!(Key::parse("ctrl-d").unwrap(), Key::ctrl('d'));
+        assert_eq!(Key::parse("C-d").unwrap(), Key::ctrl('d'));
+        assert_eq!(Key::parse("M-v").unwrap(), Key::alt('v'));
+        assert_eq!(Key::parse("shift-a").unwrap(), Key::char('A'));
+        assert_eq!(
+            Key::parse("shift-tab").unwrap(),
+            Key::plain(KeyCode::BackTab)
+        );
+        assert_eq!(Key::parse("Space").unwrap(), Key::char(' '));
+        assert_eq!(Key::parse("-").unwrap(), Key::char('-'));
+        assert!(Key::parse("hyper-x").is_err());
+
+        assert_eq!(
+            press(KeyCode::Char('a'), KeyModifiers::SHIFT),
+            Key::char('A')
+        );
+        assert_eq!(
+            press(KeyCode::Char('G'), KeyModifiers::SHIFT),
+            Key::char('G')
+        );
+        assert_eq!(Key::ctrl('d').to_string(), "Ctrl-d");
+        assert_eq!(Key::char(' ').to_string(), "Space");
+    }
+
+    #[test]
+    fn presets_and_overrides_resolve_actions() {
+        let less = Keymap::preset(KeymapPreset::Less);
+        let space: Vec<Action> = less.actions(&[Key::char(' ')]).collect();
+        assert_eq!(space, vec![Action::PageDown]);
+        let f: Vec<Action> = less.actions(&[Key::char('f')]).collect();
+        assert_eq!(f, vec![Action::CycleAgentFilter, Action::PageDown]);
+        assert_eq!(
+            less.actions(&[Key::ctrl('p')]).next(),
+            Some(Action::ScrollUp)
+        );
+        assert_eq!(less.label(Action::CommandPalette), ":, Alt-x");
+        assert_eq!(Keymap::default().actions(&[Key::char(' ')]).next(), None);
+        let emacs = Keymap::preset(KeymapPreset::Emacs);
+        assert_eq!(
+            emacs.actions(&[Key::ctrl('n')]).next(),
+            Some(Action::ScrollDown)
+        );
+        assert_eq!(emacs.actions(&[Key::char('j')]).next(), None);
+
+        let config: KeyConfig = toml::from_str(
+            "preset = \"vim\"\nbind = { toggle-toc = \"T\", quit = [\"q\", \"ctrl-c\"] }",
+        )
+        .unwrap();
+        let keymap = config.keymap().unwrap();
+        assert_eq!(keymap.actions(&[Key::char('t')]).next(), None);
+        assert_eq!(
+            keymap.actions(&[Key::char('T')]).next(),
+            Some(Action::ToggleToc)
+        );
+        assert_eq!(keymap.label(Action::Quit), "q, Ctrl-c");
+
+        let typo: KeyConfig = toml::from_str("bind = { toggle-tco = \"T\" }").unwrap();
+        assert!(typo.keymap().is_err());
+    }
+
+    #[test]
+    fn sequences_wait_for_their_second_key() {
+        let vim = Keymap::default();
+        assert!(vim.is_prefix(&[Key::char('g')]));
+        assert_eq!(vim.actions(&[Key::char('g')]).next(), Some(Action::Top));
+        assert_eq!(
+            vim.actions(&[Key::char('g'), Key::char('t')]).next(),
+            Some(Action::NextTab)
+        );
+        assert_eq!(
+            vim.actions(&[Key::char('g'), Key::char('T')]).next(),
+            Some(Action::PrevTab)
+        );
+        assert_eq!(vim.actions(&[Key::char('K')]).next(), Some(Action::PrevTab));
+        assert_eq!(vim.label(Action::NextTab), "gt, J");
+        assert!(!vim.is_prefix(&[Key::char(':')]));
+
+        // less keeps `g` instant.
+        let less = Keymap::preset(KeymapPreset::Less);
+        assert!(!less.is_prefix(&[Key::char('g')]));
+        assert_eq!(less.label(Action::NextTab), "J, Ctrl-PgDn");
+
+        let emacs = Keymap::preset(KeymapPreset::Emacs);
+        assert!(emacs.is_prefix(&[Key::ctrl('x')]));
+        assert_eq!(
+            emacs
+                .actions(&[Key::ctrl('x'), Key::plain(KeyCode::Left)])
+                .next(),
+            Some(Action::PrevTab)
+        );
+        assert!(!emacs.is_prefix(&[Key::ctrl('x'), Key::char('k')]));
+        assert_eq!(emacs.label(Action::CloseTab), "Ctrl-x k");
+
+        let mut custom = Keymap::default();
+        custom.bind(Action::NextTab, vec![Key::parse_sequence("g t").unwrap()]);
+        assert!(custom.is_prefix(&[Key::char('g')]));
+        assert_eq!(custom.actions(&[Key::char('g')]).next(), Some(Action::Top));
+        assert_eq!(custom.label(Action::NextTab), "gt");
+        assert_eq!(
+            Key::parse_sequence("ctrl-x  Right").unwrap(),
+            vec![Key::ctrl('x'), Key::plain(KeyCode::Right)]
+        );
+
+        // Shift survives on keys it cannot be folded into.
+        let shift_enter = press(KeyCode::Enter, KeyModifiers::SHIFT);
+        assert_eq!(shift_enter, Key::parse("shift-enter").unwrap());
+        assert_eq!(shift_enter.to_string(), "Shift-Enter");
+        assert_eq!(
+            vim.actions(&[shift_enter]).next(),
+            Some(Action::OpenLinkInTab)
+        );
+        assert_eq!(
+            vim.actions(&[press(KeyCode::Enter, KeyModifiers::NONE)])
+                .next(),
+            Some(Action::Select)
+        );
+        assert_eq!(
+            vim.key_for_event(&[], &KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT)),
+            shift_enter
+        );
+
+        // Unbound shifted keys fall back to the plain binding.
+        let shift_down = KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT);
+        let key = vim.key_for_event(&[], &shift_down);
+        assert_eq!(key, Key::plain(KeyCode::Down));
+        assert_eq!(vim.actions(&[key]).next(), Some(Action::ScrollDown));
+        let shift_pgdn = KeyEvent::new(KeyCode::PageDown, KeyModifiers::SHIFT);
+        assert_eq!(
+            vim.actions(&[vim.key_for_event(&[], &shift_pgdn)]).next(),
+            Some(Action::PageDown)
+        );
+    }
+
+    #[test]
+    fn help_lists_every_action() {
+        let keymap = Keymap::default();
+        let help = keymap.help();
+        for action in Action::ALL {
+            assert!(
+                HELP.iter()
+                    .any(|(_, rows)| rows.iter().any(|(actions, _)| actions.contains(&action))),
+                "{} is missing from the help overlay",
+                action.name()
+            );
+        }
+        let general = &help[0].1;
+        assert!(general.contains(&("j, Down / k, Up".to_string(), "Scroll down / up")));
+    }
+}
@@ -16,7 +16,7 @@ fn resolve_path(path: &Path, current_doc: Option<&Path>) -> PathBuf {
     path.to_path_buf()
 }
 
-fn is_markdown_path(path: &Path) -> bool {
+pub(crate) fn is_markdown_path(path: &Path) -> bool {
     let ext = path
         .extension()
         .and_then(|s| s.to_str())
@@ -43,9 +43,39 @@ pub(crate) fn system_open<S: AsRef<OsStr>>(arg: S) -> Result<()> {
     Ok(())
 }
 
+/// Decodes `%XX` escapes in a URL fragment so `#caf%C3%A9` matches `café`.
+fn percent_decode(fragment: &str) -> String {
+    let bytes = fragment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            if let Some(value) = fragment
+                .get(idx + 1..idx + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(value);
+                idx += 3;
+                continue;
+            }
+        }
+        out.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn split_fragment(target: &str) -> (&str, Option<String>) {
+    match target.split_once('#') {
+        Some((path, fragment)) if !fragment.is_empty() => (path, Some(percent_decode(fragment))),
+        Some((path, _)) => (path, None),
+        None => (target, None),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum LinkAction {
-    InternalMarkdown(PathBuf),
+    InternalMarkdown(PathBuf, Option<String>),
     ExternalUrl(String),
     ExternalPath(PathBuf),
     Anchor(String),
@@ -57,19 +87,15 @@ pub(crate) fn classify_link(target: &str, current_doc: Option<&Path>) -> LinkAct
         return LinkAction::ExternalUrl(target.to_string());
     }
 
-    if target.starts_with('#') {
-        return LinkAction::Anchor(target.to_string());
-    }
-
-    let path_part = target.split_once('#').map_or(target, |(path, _)| path);
+    let (path_part, fragment) = split_fragment(target);
 
     if path_part.is_empty() {
-        return LinkAction::Anchor(target.to_string());
+        return LinkAction::Anchor(fragment.unwrap_or_default());
     }
 
     let resolved = resolve_path(Path::new(path_part), current_doc);
     if is_markdown_path(&resolved) {
-        return LinkAction::InternalMarkdown(resolved);
+        return LinkAction::InternalMarkdown(resolved, fragment);
     }
 
     if resolved.exists() {
@@ -78,3 +104,28 @@ pub(crate) fn classify_link(target: &str, current_doc: Option<&Path>) -> LinkAct
 
     LinkAction::Unknown(target.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_link_keeps_fragment_for_markdown_files() {
+        let current = Path::new("/docs/index.md");
+        match classify_link("guide.md#install-steps", Some(current)) {
+            LinkAction::InternalMarkdown(path, fragment) => {
+                assert_eq!(path, PathBuf::from("/docs/guide.md"));
+                assert_eq!(fragment.as_deref(), Some("install-steps"));
+            }
+            other => panic!("unexpected action: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn classify_link_decodes_same_document_anchors() {
+        match classify_link("#caf%C3%A9-notes", None) {
+            LinkAction::Anchor(fragment) => assert_eq!(fragment, "café-notes"),
+            other => panic!("unexpected action: {other:?}"),
+        }
+    }
+}
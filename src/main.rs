@@ -1,21 +1,27 @@
 use std::collections::VecDeque;
 use std::fs;
-use std::io;
+use std::io::{self, IsTerminal};
+use std::mem;
+use std::ops::Range;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::mpsc::{self, Receiver};
 use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use crossterm::event::{self, Event as CEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, Event as CEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
 use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use crossterm::{execute, ExecutableCommand};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Layout};
-use ratatui::prelude::{Color, Modifier, Rect, Style};
+use ratatui::prelude::{Modifier, Rect, Style};
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::block::Padding;
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap};
@@ -24,30 +30,77 @@ use syntect::highlighting::{Theme, ThemeSet};
 use syntect::parsing::SyntaxSet;
 
 mod agent;
+mod ansi;
+mod clock;
+mod command_palette;
+mod config;
 mod diff;
+mod editor;
+mod file_tree;
+mod frontmatter;
+mod git;
+mod history;
+mod html;
 mod input;
+mod keymap;
 mod links;
 mod markdown;
-
-use agent::{extract_agent_tasks, AgentTask, AgentTaskState};
+mod meta;
+mod search;
+mod tasks;
+mod theme;
+
+use agent::{
+    agent_owners, edit_task_in_source, extract_agent_tasks, inbox_order, AgentSort, AgentTask,
+    AgentTaskState, TaskEdit,
+};
+use ansi::{ansi_render, ColorDepth};
+use clock::{format_clock_hms, format_date_ymd};
+use command_palette::{CommandPalette, PaletteItem, PaletteTarget};
+use config::{load_config, PanelConfig, Sidebar};
 use diff::{
-    build_snapshot_diff, change_freshness, format_clock_hms, hunk_anchor_line, truncate_label,
-    ChangeFreshness, SnapshotDiff, WatchSnapshot,
+    build_snapshot_diff, change_freshness, diff_rows, hunk_anchor_line, inline_rows, instant_for,
+    split_by_ranges, truncate_label, ChangeFreshness, DiffRow, DiffRowKind, SnapshotDiff,
+    WatchSnapshot,
+};
+use editor::edit_at_line;
+use file_tree::{format_age, FileSort, FileTree, TreeRow};
+use git::{file_at_revision, file_history};
+use history::{HistoryStore, SnapshotRecord};
+use input::{
+    color_output_enabled, default_interactive, detect_inputs, read_input, Cli, Command,
+    InputSource, LoadResult,
 };
-use input::{default_interactive, detect_input, read_input, Cli, InputSource, LoadResult};
-use links::{classify_link, system_open, LinkAction};
-use markdown::{plain_render, render_markdown, RenderedDocument};
+use keymap::{sequence_label, Action, Key, Keymap};
+use links::{classify_link, is_markdown_path, system_open, LinkAction};
+use markdown::{plain_render, render_markdown, CalloutKind, RenderOptions, RenderedDocument};
+use meta::run_meta;
+use search::{SearchFlags, SearchHistory, SearchMatch, SearchPattern};
+use tasks::run_tasks;
 
 #[cfg(test)]
-use diff::compute_line_diff;
+use diff::{changed_word_ranges, compute_line_diff, LineDiffResult, DIFF_MAX_COST};
 #[cfg(test)]
-use markdown::{RenderedLine, TocEntry};
+use markdown::{heading_slug, RenderedLine, TocEntry};
+use theme::{Palette, ThemePreset};
 
 const NO_TOC_HEADINGS_STATUS: &str = "No headings in TOC";
+const NO_CALLOUTS_STATUS: &str = "No callouts in this document";
+const NO_DETAILS_STATUS: &str = "No <details> block on screen";
+/// Watch snapshots kept when neither `--history` nor the config sets a limit.
+const DEFAULT_HISTORY: usize = 50;
 const TIMELINE_DEFAULT_HEIGHT: u16 = 6;
+/// Columns of the content pane that do not hold document text: the side
+/// padding and the change/agent marker gutter.
+const CONTENT_GUTTER_WIDTH: u16 = 4;
+const MIN_RENDER_WIDTH: usize = 20;
 const TIMELINE_MIN_HEIGHT: u16 = 3;
 const NO_AGENT_TASKS_STATUS: &str = "No agent tasks found";
 const NO_OPEN_AGENT_TASKS_STATUS: &str = "All agent tasks complete";
+/// How long the pager waits for the rest of a key sequence such as `Ctrl-x k`.
+const KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long the file tree selection must rest before the file is loaded.
+const FILE_PREVIEW_DELAY: Duration = Duration::from_millis(200);
 
 fn inset_rect(area: Rect, horizontal: u16, vertical: u16) -> Rect {
     let x = area.x.saturating_add(horizontal);
@@ -78,34 +131,64 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
 }
 
 fn usize_to_u16_saturating(value: usize) -> u16 {
-    match u16::try_from(value) {
-        Ok(v) => v,
-        Err(_) => u16::MAX,
-    }
+    u16::try_from(value).unwrap_or(u16::MAX)
 }
 
-fn resolve_theme(theme_set: &ThemeSet) -> Theme {
-    if let Some(theme) = theme_set.themes.get("base16-ocean.dark") {
-        return theme.clone();
-    }
+fn resolve_theme(theme_set: &ThemeSet, name: &str) -> Result<Theme> {
     theme_set
         .themes
-        .values()
-        .next()
+        .get(name)
         .cloned()
-        .unwrap_or_default()
+        .ok_or_else(|| anyhow!("Unknown syntax theme '{name}' (see catmd --list-themes)"))
+}
+
+/// Prints `--list-themes`, marking the themes in effect with `*`.
+fn print_themes(theme_set: &ThemeSet, preset: ThemePreset, syntax_theme: &str) {
+    println!("Theme presets (--theme, or `theme` in config.toml):");
+    for candidate in ThemePreset::ALL {
+        let marker = if candidate == preset { '*' } else { ' ' };
+        println!(
+            "{marker} {:<15} {}",
+            candidate.name(),
+            candidate.description()
+        );
+    }
+    println!();
+    println!("Syntax themes (`syntax_theme` in config.toml):");
+    for name in theme_set.themes.keys() {
+        let marker = if name == syntax_theme { '*' } else { ' ' };
+        println!("{marker} {name}");
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 struct LoadedDocument {
     path: Option<PathBuf>,
+    /// Markdown source as last read from disk, used to detect external edits
+    /// before writing task changes back.
+    source: String,
     rendered: RenderedDocument,
 }
 
+impl LoadedDocument {
+    /// File name shown in the tab bar.
+    fn title(&self) -> String {
+        self.path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map_or_else(
+                || "<stdin>".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+    }
+}
+
 #[derive(Debug)]
 struct HistoryEntry {
-    path: PathBuf,
+    /// `None` while reading standard input.
+    path: Option<PathBuf>,
     scroll: u16,
+    selected_link: Option<usize>,
 }
 
 struct FileWatcher {
@@ -113,43 +196,185 @@ struct FileWatcher {
     rx: Receiver<notify::Result<Event>>,
 }
 
-struct App {
-    cli: Cli,
-    syntax_set: SyntaxSet,
-    theme: Theme,
+/// Everything `App` keeps per open document. The shown tab is `App::tab`;
+/// the others wait in `App::tabs` and are swapped in as a unit.
+struct Tab {
     doc: LoadedDocument,
     snapshots: VecDeque<WatchSnapshot>,
     active_snapshot: usize,
     next_revision: u64,
-    history_capacity: usize,
+    /// Layout width, HTML comment visibility and expanded `<details>` blocks
+    /// the document was last rendered with.
+    render_options: RenderOptions,
+    scroll: u16,
+    toc_selected: usize,
+    /// The TOC selection was moved by hand rather than following the scroll.
+    toc_focused: bool,
+    agent_selected: usize,
+    callout_selected: usize,
+    diff_view: Option<DiffView>,
+    selected_link: Option<usize>,
+    backstack: Vec<HistoryEntry>,
+    search_query: String,
+    search_pattern: Option<SearchPattern>,
+    /// Why the current regex query does not compile.
+    search_error: Option<String>,
+    search_matches: Vec<SearchMatch>,
+    current_match: usize,
+    agent_tasks: Vec<AgentTask>,
+    /// Unresolved tasks in inbox order (see `agent_sort` / `agent_owner_filter`).
+    open_agent_tasks: Vec<usize>,
+    watcher: Option<FileWatcher>,
+    /// The watcher fired; the shown tab reloads on the next loop pass.
+    watch_requested: bool,
+    /// A revision was recorded while the tab was in the background.
+    unseen_revision: bool,
+}
+
+impl Tab {
+    fn new(doc: LoadedDocument, render_options: RenderOptions) -> Self {
+        Self {
+            doc,
+            snapshots: VecDeque::new(),
+            active_snapshot: 0,
+            next_revision: 1,
+            render_options,
+            scroll: 0,
+            toc_selected: 0,
+            toc_focused: false,
+            agent_selected: 0,
+            callout_selected: 0,
+            diff_view: None,
+            selected_link: None,
+            backstack: Vec::new(),
+            search_query: String::new(),
+            search_pattern: None,
+            search_error: None,
+            search_matches: Vec::new(),
+            current_match: 0,
+            agent_tasks: Vec::new(),
+            open_agent_tasks: Vec::new(),
+            watcher: None,
+            watch_requested: false,
+            unseen_revision: false,
+        }
+    }
+}
+
+/// Display columns left for document text in a content pane `width` wide.
+fn content_text_width(width: u16) -> usize {
+    usize::from(width.saturating_sub(CONTENT_GUTTER_WIDTH)).max(MIN_RENDER_WIDTH)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum DiffLayout {
+    #[default]
+    Inline,
+    SideBySide,
+}
+
+impl DiffLayout {
+    fn toggle(self) -> Self {
+        match self {
+            Self::Inline => Self::SideBySide,
+            Self::SideBySide => Self::Inline,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Inline => "inline",
+            Self::SideBySide => "side-by-side",
+        }
+    }
+}
 
+/// Diff between two watch revisions, recomputed whenever either side changes.
+#[derive(Debug)]
+struct DiffView {
+    /// Compared snapshots, by `WatchSnapshot::name`.
+    base: String,
+    target: String,
+    layout: DiffLayout,
+    rows: Vec<DiffRow>,
+    inline: Vec<DiffRow>,
+    added: usize,
+    removed: usize,
     scroll: u16,
+}
+
+impl DiffView {
+    fn visible_rows(&self) -> &[DiffRow] {
+        match self.layout {
+            DiffLayout::Inline => &self.inline,
+            DiffLayout::SideBySide => &self.rows,
+        }
+    }
+
+    fn hunk_starts(&self) -> Vec<usize> {
+        let rows = self.visible_rows();
+        (0..rows.len())
+            .filter(|idx| {
+                rows[*idx].kind != DiffRowKind::Context
+                    && (*idx == 0 || rows[idx.saturating_sub(1)].kind == DiffRowKind::Context)
+            })
+            .collect()
+    }
+}
+
+struct App {
+    cli: Cli,
+    syntax_set: SyntaxSet,
+    theme: Theme,
+    /// The shown tab.
+    tab: Tab,
+    history_capacity: usize,
+    /// On-disk watch history; `None` unless watching a file with persistence on.
+    history_store: Option<HistoryStore>,
+    keymap: Keymap,
+
     viewport_height: u16,
     toc_open: bool,
-    toc_selected: usize,
     agent_inbox_open: bool,
-    agent_selected: usize,
+    callouts_open: bool,
+    /// Markdown files of a directory argument, shown in their own sidebar.
+    file_tree: Option<FileTree>,
+    file_tree_open: bool,
+    /// Moves and `Enter` go to the file tree rather than the document.
+    file_tree_focused: bool,
+    /// Rescans the file tree when markdown files under it change.
+    file_tree_watcher: Option<FileWatcher>,
+    /// When the file selected in the tree is due to be loaded; moving the
+    /// selection pushes this back so scrolling the tree stays cheap.
+    file_preview_due: Option<Instant>,
     help_open: bool,
+    help_scroll: u16,
+    /// Front matter header above the document (shown only when present).
+    metadata_open: bool,
     timeline_open: bool,
     timeline_height: u16,
 
-    selected_link: Option<usize>,
-    backstack: Vec<HistoryEntry>,
-
     search_mode: bool,
-    search_query: String,
-    search_matches: Vec<usize>,
-    current_match: usize,
+    search_flags: SearchFlags,
+    search_history: SearchHistory,
     quick_task_mode: bool,
+    command_palette: Option<CommandPalette>,
     quick_task_input: String,
+    pending_task_delete: Option<usize>,
 
-    agent_tasks: Vec<AgentTask>,
-    open_agent_tasks: Vec<usize>,
+    agent_sort: AgentSort,
+    agent_owner_filter: Option<String>,
 
     status: String,
 
-    watcher: Option<FileWatcher>,
-    watch_requested: bool,
+    editor_requested: Option<usize>,
+
+    /// Keys typed so far of a sequence such as `Ctrl-x k`, and when it started.
+    pending_keys: Vec<Key>,
+    pending_since: Option<Instant>,
+    /// Background tabs in order; the shown tab sits at `active_tab` among them.
+    tabs: Vec<Tab>,
+    active_tab: usize,
 }
 
 impl App {
@@ -162,11 +387,93 @@ impl App {
     }
 
     fn reset_selected_link(&mut self) {
-        self.selected_link = Self::first_link_selection(&self.doc.rendered);
+        self.tab.selected_link = Self::first_link_selection(&self.tab.doc.rendered);
+    }
+
+    /// The revision timeline exists while watching or when git history was loaded.
+    fn timeline_enabled(&self) -> bool {
+        self.cli.watch || self.cli.git_log.is_some_and(|count| count > 0)
+    }
+
+    fn palette(&self) -> &Palette {
+        &self.tab.render_options.palette
+    }
+
+    /// Opens the panels `[panels]` in the config file asks for.
+    fn apply_panel_config(&mut self, panels: &PanelConfig) {
+        self.metadata_open = panels.metadata;
+        self.timeline_open = panels.timeline && self.timeline_enabled();
+        match panels.sidebar {
+            Sidebar::None => {}
+            Sidebar::Toc => self.toggle_toc(),
+            Sidebar::AgentInbox => self.toggle_agent_inbox(),
+            Sidebar::Callouts => {
+                if !self.tab.doc.rendered.callouts.is_empty() {
+                    self.toggle_callouts();
+                }
+            }
+        }
+    }
+
+    fn render(&self, source: &str) -> RenderedDocument {
+        render_markdown(
+            source,
+            &self.syntax_set,
+            &self.theme,
+            &self.tab.render_options,
+        )
+    }
+
+    /// Re-renders the document for a new content width, keeping the top
+    /// visible source line in view.
+    fn relayout(&mut self, width: usize) {
+        self.tab.render_options.width = Some(width);
+        self.rerender();
+    }
+
+    /// Re-renders the document after `render_options` changed, keeping the
+    /// top visible source line in view. Only the shown snapshot is rendered
+    /// now; the others are marked stale and re-rendered once they are needed.
+    fn rerender(&mut self) {
+        let anchor = self
+            .tab
+            .doc
+            .rendered
+            .source_line_at(usize::from(self.tab.scroll));
+
+        for snapshot in &mut self.tab.snapshots {
+            snapshot.stale = true;
+        }
+        self.refresh_snapshot(self.tab.active_snapshot);
+        self.tab.doc.rendered = match self.current_snapshot() {
+            Some(snapshot) => snapshot.rendered.clone(),
+            None => self.render(&self.tab.doc.source),
+        };
+
+        if self
+            .tab
+            .selected_link
+            .is_some_and(|idx| idx >= self.tab.doc.rendered.links.len())
+        {
+            self.reset_selected_link();
+        }
+        self.refresh_agent_tasks();
+        let current_match = self.tab.current_match;
+        self.update_search_matches();
+        self.tab.current_match = current_match.min(self.tab.search_matches.len().saturating_sub(1));
+        self.tab.scroll = anchor
+            .and_then(|line| self.tab.doc.rendered.line_for_source_line(line))
+            .map(usize_to_u16_saturating)
+            .unwrap_or(self.tab.scroll);
+        self.clamp_scroll();
+        self.refresh_diff_view();
+        self.sync_toc_selected_with_scroll();
+        self.sync_callout_selected_with_scroll();
+        self.sync_agent_selected_with_scroll();
     }
 
     fn require_watch_mode(&mut self, status: &str) -> bool {
-        if self.cli.watch {
+        if self.timeline_enabled() {
             true
         } else {
             self.status = status.to_string();
@@ -180,221 +487,770 @@ impl App {
         rendered: RenderedDocument,
         syntax_set: SyntaxSet,
         theme: Theme,
+        render_options: RenderOptions,
+        keymap: Keymap,
     ) -> Self {
-        let selected_link = Self::first_link_selection(&rendered);
-        let agent_tasks = extract_agent_tasks(&rendered);
-        let open_agent_tasks = agent_tasks
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, task)| task.is_open().then_some(idx))
-            .collect();
-        let mut snapshots = VecDeque::new();
-        snapshots.push_back(WatchSnapshot {
-            revision: 1,
-            created_at: SystemTime::now(),
-            created_instant: Instant::now(),
-            rendered: rendered.clone(),
-            diff: SnapshotDiff::default(),
-        });
-
-        let history_capacity = cli.history.max(1);
+        let mut tab = Tab::new(
+            LoadedDocument {
+                path: load.path,
+                source: load.source,
+                rendered,
+            },
+            render_options,
+        );
+        tab.selected_link = Self::first_link_selection(&tab.doc.rendered);
+        tab.agent_tasks = extract_agent_tasks(&tab.doc.rendered);
+        tab.open_agent_tasks = inbox_order(&tab.agent_tasks, AgentSort::default(), None);
+        // Loaded git commits do not count against the in-memory watch history.
+        let history_capacity = cli
+            .history
+            .unwrap_or(DEFAULT_HISTORY)
+            .max(1)
+            .saturating_add(cli.git_log.unwrap_or(0));
+        let history_store = if cli.watch && !cli.no_persist_history && tab.doc.path.is_some() {
+            HistoryStore::open_default()
+        } else {
+            None
+        };
 
-        Self {
+        let mut app = Self {
             cli,
             syntax_set,
             theme,
-            doc: LoadedDocument {
-                path: load.path,
-                rendered,
-            },
-            snapshots,
-            active_snapshot: 0,
-            next_revision: 2,
+            tab,
             history_capacity,
-            scroll: 0,
+            history_store,
+            keymap,
             viewport_height: 1,
             toc_open: false,
-            toc_selected: 0,
             agent_inbox_open: false,
-            agent_selected: 0,
+            callouts_open: false,
+            file_tree: None,
+            file_tree_open: false,
+            file_tree_focused: false,
+            file_tree_watcher: None,
+            file_preview_due: None,
             help_open: false,
+            help_scroll: 0,
+            metadata_open: true,
             timeline_open: false,
             timeline_height: TIMELINE_DEFAULT_HEIGHT,
-            selected_link,
-            backstack: Vec::new(),
             search_mode: false,
-            search_query: String::new(),
-            search_matches: Vec::new(),
-            current_match: 0,
+            search_flags: SearchFlags::default(),
+            search_history: SearchHistory::default(),
             quick_task_mode: false,
+            command_palette: None,
             quick_task_input: String::new(),
-            agent_tasks,
-            open_agent_tasks,
+            pending_task_delete: None,
+            agent_sort: AgentSort::default(),
+            agent_owner_filter: None,
             status: String::new(),
-            watcher: None,
-            watch_requested: false,
+            editor_requested: None,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            tabs: Vec::new(),
+            active_tab: 0,
+        };
+        app.reset_snapshots_from_current_doc();
+        if app.cli.watch && app.cli.no_persist_history {
+            app.status = "Persistent history disabled".to_string();
         }
+        app
     }
 
     fn refresh_agent_tasks(&mut self) {
         let selected_line = self
+            .tab
             .open_agent_tasks
-            .get(self.agent_selected)
-            .and_then(|idx| self.agent_tasks.get(*idx))
+            .get(self.tab.agent_selected)
+            .and_then(|idx| self.tab.agent_tasks.get(*idx))
             .map(|task| task.line);
 
-        self.agent_tasks = extract_agent_tasks(&self.doc.rendered);
-        self.open_agent_tasks = self
-            .agent_tasks
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, task)| task.is_open().then_some(idx))
-            .collect();
+        self.tab.agent_tasks = extract_agent_tasks(&self.tab.doc.rendered);
+        self.tab.open_agent_tasks = inbox_order(
+            &self.tab.agent_tasks,
+            self.agent_sort,
+            self.agent_owner_filter.as_deref(),
+        );
 
-        if self.open_agent_tasks.is_empty() {
-            self.agent_selected = 0;
+        if self.tab.open_agent_tasks.is_empty() {
+            self.tab.agent_selected = 0;
             return;
         }
 
         if let Some(line) = selected_line {
             if let Some(position) = self
+                .tab
                 .open_agent_tasks
                 .iter()
-                .position(|idx| self.agent_tasks[*idx].line == line)
+                .position(|idx| self.tab.agent_tasks[*idx].line == line)
             {
-                self.agent_selected = position;
+                self.tab.agent_selected = position;
                 return;
             }
         }
 
-        self.agent_selected = self
+        self.tab.agent_selected = self
+            .tab
             .agent_selected
-            .min(self.open_agent_tasks.len().saturating_sub(1));
+            .min(self.tab.open_agent_tasks.len().saturating_sub(1));
     }
 
     fn selected_open_agent_task(&self) -> Option<&AgentTask> {
-        self.open_agent_tasks
-            .get(self.agent_selected)
-            .and_then(|idx| self.agent_tasks.get(*idx))
+        self.tab
+            .open_agent_tasks
+            .get(self.tab.agent_selected)
+            .and_then(|idx| self.tab.agent_tasks.get(*idx))
     }
 
     fn selected_open_agent_line(&self) -> Option<usize> {
         self.selected_open_agent_task().map(|task| task.line)
     }
 
+    fn unresolved_agent_task_count(&self) -> usize {
+        self.tab
+            .agent_tasks
+            .iter()
+            .filter(|task| task.is_open())
+            .count()
+    }
+
     fn status_for_empty_open_tasks(&mut self) {
-        self.status = if self.agent_tasks.is_empty() {
+        self.status = if self.tab.agent_tasks.is_empty() {
             NO_AGENT_TASKS_STATUS.to_string()
+        } else if let Some(owner) = self
+            .agent_owner_filter
+            .as_ref()
+            .filter(|_| self.unresolved_agent_task_count() > 0)
+        {
+            format!("No open agent tasks for @{owner}")
         } else {
             NO_OPEN_AGENT_TASKS_STATUS.to_string()
         };
     }
 
+    /// Inbox position of the unresolved task nearest to `line` in the given
+    /// direction: the last one at or above it, or the first one below it.
+    fn agent_position_near_line(&self, line: usize, above: bool) -> Option<usize> {
+        let lines = self
+            .tab
+            .open_agent_tasks
+            .iter()
+            .enumerate()
+            .map(|(position, idx)| (position, self.tab.agent_tasks[*idx].line));
+        if above {
+            lines
+                .filter(|(_, task_line)| *task_line <= line)
+                .max_by_key(|(_, task_line)| *task_line)
+                .map(|(position, _)| position)
+        } else {
+            lines
+                .filter(|(_, task_line)| *task_line >= line)
+                .min_by_key(|(_, task_line)| *task_line)
+                .map(|(position, _)| position)
+        }
+    }
+
     fn sync_agent_selected_with_scroll(&mut self) {
-        if self.open_agent_tasks.is_empty() {
-            self.agent_selected = 0;
+        if self.tab.open_agent_tasks.is_empty() {
+            self.tab.agent_selected = 0;
+            return;
+        }
+        if self.agent_sort != AgentSort::Document {
             return;
         }
 
-        let line = usize::from(self.scroll);
-        self.agent_selected = self
-            .open_agent_tasks
-            .iter()
-            .enumerate()
-            .rfind(|(_, idx)| self.agent_tasks[**idx].line <= line)
-            .map_or(0, |(position, _)| position);
+        self.tab.agent_selected = self
+            .agent_position_near_line(usize::from(self.tab.scroll), true)
+            .unwrap_or(0);
+    }
+
+    fn cycle_agent_sort(&mut self) {
+        self.agent_sort = self.agent_sort.next();
+        self.tab.agent_selected = 0;
+        self.refresh_agent_tasks();
+        self.status = format!("Agent Inbox sorted by {}", self.agent_sort.label());
+    }
+
+    fn cycle_agent_owner_filter(&mut self) {
+        let owners = agent_owners(&self.tab.agent_tasks);
+        if owners.is_empty() {
+            self.agent_owner_filter = None;
+            self.status = "No @agent:<name> addressees in this document".to_string();
+            return;
+        }
+
+        let next = match &self.agent_owner_filter {
+            None => owners.first().cloned(),
+            Some(current) => owners
+                .iter()
+                .position(|owner| owner == current)
+                .and_then(|idx| owners.get(idx + 1))
+                .cloned(),
+        };
+        self.agent_owner_filter = next;
+        self.tab.agent_selected = 0;
+        self.refresh_agent_tasks();
+        self.status = match &self.agent_owner_filter {
+            Some(owner) => format!(
+                "Agent Inbox: @{owner} ({} open)",
+                self.tab.open_agent_tasks.len()
+            ),
+            None => "Agent Inbox: all addressees".to_string(),
+        };
     }
 
     fn toggle_toc(&mut self) {
         self.toc_open = !self.toc_open;
         if self.toc_open {
+            self.file_tree_focused = false;
             self.agent_inbox_open = false;
+            self.callouts_open = false;
             self.sync_toc_selected_with_scroll();
         }
     }
 
-    fn toggle_agent_inbox(&mut self) {
-        self.agent_inbox_open = !self.agent_inbox_open;
-        if self.agent_inbox_open {
-            self.toc_open = false;
-            self.sync_agent_selected_with_scroll();
+    fn toggle_metadata(&mut self) {
+        if self.tab.doc.rendered.front_matter.is_none() {
+            self.status = "No front matter in this document".to_string();
+            return;
         }
+        self.metadata_open = !self.metadata_open;
+        self.status = if self.metadata_open {
+            "Metadata panel shown".to_string()
+        } else {
+            "Metadata panel hidden".to_string()
+        };
     }
 
-    fn move_agent_selection(&mut self, reverse: bool) {
-        let len = self.open_agent_tasks.len();
-        if len == 0 {
-            self.status_for_empty_open_tasks();
+    fn toggle_html_comments(&mut self) {
+        self.tab.render_options.show_comments = !self.tab.render_options.show_comments;
+        self.rerender();
+        self.status = if self.tab.render_options.show_comments {
+            "HTML comments shown".to_string()
+        } else {
+            "HTML comments hidden".to_string()
+        };
+    }
+
+    /// Expands or collapses the first `<details>` block whose summary is on
+    /// screen.
+    fn toggle_details(&mut self) {
+        let top = usize::from(self.tab.scroll);
+        let bottom = self.viewport_end();
+        let Some(details) = self
+            .tab
+            .doc
+            .rendered
+            .details
+            .iter()
+            .find(|details| {
+                (top..bottom).contains(&details.line)
+                    && !self.tab.doc.rendered.is_hidden(details.line)
+            })
+            .cloned()
+        else {
+            self.status = NO_DETAILS_STATUS.to_string();
             return;
-        }
+        };
 
-        if reverse {
-            self.agent_selected = self.agent_selected.saturating_sub(1);
+        let options = &mut self.tab.render_options;
+        if options.expand_details {
+            options.expand_details = false;
+            options.expanded_details = self
+                .tab
+                .doc
+                .rendered
+                .details
+                .iter()
+                .map(|details| details.key.clone())
+                .collect();
+        }
+        if details.open {
+            options.expanded_details.remove(&details.key);
         } else {
-            self.agent_selected = (self.agent_selected + 1).min(len.saturating_sub(1));
+            options.expanded_details.insert(details.key);
         }
+        self.rerender();
+        let action = if details.open {
+            "Collapsed"
+        } else {
+            "Expanded"
+        };
+        self.status = format!("{action}: {}", details.summary);
     }
 
-    fn jump_to_open_agent_index(&mut self, index: usize) {
-        let len = self.open_agent_tasks.len();
-        if len == 0 {
-            self.status_for_empty_open_tasks();
+    /// Expands the collapsed `<details>` blocks hiding `line`.
+    fn reveal_line(&mut self, line: usize) {
+        let keys = self.tab.doc.rendered.collapsed_around(line);
+        if keys.is_empty() {
             return;
         }
+        self.tab.render_options.expanded_details.extend(keys);
+        self.rerender();
+    }
 
-        self.agent_selected = index.min(len.saturating_sub(1));
-        let Some(task) = self.selected_open_agent_task().cloned() else {
-            self.status_for_empty_open_tasks();
+    fn toggle_all_details(&mut self) {
+        if self.tab.doc.rendered.details.is_empty() {
+            self.status = "No <details> blocks in this document".to_string();
             return;
+        }
+        let expand = !self.tab.render_options.expand_details;
+        self.tab.render_options.expand_details = expand;
+        self.tab.render_options.expanded_details.clear();
+        self.rerender();
+        self.status = if expand {
+            "Expanded all <details> blocks".to_string()
+        } else {
+            "Collapsed all <details> blocks".to_string()
         };
-
-        self.set_scroll_to_line(task.line);
-        self.status = format!(
-            "Agent task {}/{}: {}",
-            self.agent_selected + 1,
-            len,
-            truncate_label(&task.text, 48)
-        );
     }
 
-    fn jump_to_selected_agent_task(&mut self) {
-        self.jump_to_open_agent_index(self.agent_selected);
+    fn toggle_agent_inbox(&mut self) {
+        self.agent_inbox_open = !self.agent_inbox_open;
+        if self.agent_inbox_open {
+            self.file_tree_focused = false;
+            self.toc_open = false;
+            self.callouts_open = false;
+            self.sync_agent_selected_with_scroll();
+        }
     }
 
-    fn jump_agent_task_relative(&mut self, reverse: bool) {
-        let len = self.open_agent_tasks.len();
-        if len == 0 {
-            self.status_for_empty_open_tasks();
+    fn toggle_callouts(&mut self) {
+        if self.tab.doc.rendered.callouts.is_empty() && !self.callouts_open {
+            self.status = NO_CALLOUTS_STATUS.to_string();
             return;
         }
+        self.callouts_open = !self.callouts_open;
+        if self.callouts_open {
+            self.file_tree_focused = false;
+            self.toc_open = false;
+            self.agent_inbox_open = false;
+            self.sync_callout_selected_with_scroll();
+        }
+    }
 
-        let line = usize::from(self.scroll);
-        let target = if reverse {
-            self.open_agent_tasks
-                .iter()
-                .enumerate()
-                .rev()
-                .find(|(_, idx)| self.agent_tasks[**idx].line < line)
-                .map_or(len.saturating_sub(1), |(position, _)| position)
-        } else {
-            self.open_agent_tasks
-                .iter()
-                .enumerate()
-                .find(|(_, idx)| self.agent_tasks[**idx].line > line)
-                .map_or(0, |(position, _)| position)
-        };
-
-        self.jump_to_open_agent_index(target);
+    fn file_tree_has_focus(&self) -> bool {
+        self.file_tree.is_some() && self.file_tree_open && self.file_tree_focused
     }
 
-    fn begin_quick_task_capture(&mut self) {
-        if self.doc.path.is_none() {
-            self.status = "Quick task capture requires file input".to_string();
+    /// Shows the file tree, focuses it if it is shown, hides it if focused.
+    fn toggle_file_tree(&mut self) {
+        let Some(tree) = self.file_tree.as_mut() else {
+            self.status = "No file tree: open a directory (catmd docs/)".to_string();
+            return;
+        };
+        if self.file_tree_open && self.file_tree_focused {
+            self.file_tree_open = false;
+            self.file_tree_focused = false;
             return;
         }
-        self.quick_task_mode = true;
-        self.quick_task_input.clear();
-        self.status = "New @agent task: type text, Enter to save, Esc to cancel".to_string();
+        if let Some(path) = &self.tab.doc.path {
+            tree.select_path(path);
+        }
+        self.file_tree_open = true;
+        self.file_tree_focused = true;
+    }
+
+    fn cycle_file_sort(&mut self) {
+        if let Some(tree) = self.file_tree.as_mut() {
+            tree.toggle_sort();
+            self.status = format!("File tree sorted by {}", tree.sort.label());
+        }
+    }
+
+    fn move_file_selection(&mut self, up: bool) {
+        if let Some(tree) = self.file_tree.as_mut() {
+            tree.move_selection(up);
+            self.file_preview_due = Some(Instant::now() + FILE_PREVIEW_DELAY);
+        }
+    }
+
+    /// Loads the file selected in the tree once the selection has rested
+    /// for `FILE_PREVIEW_DELAY`.
+    fn poll_file_preview(&mut self) {
+        if self.file_preview_due.is_none_or(|due| Instant::now() < due) {
+            return;
+        }
+        self.preview_selected_file();
+    }
+
+    /// Shows the file selected in the tree, unless it already is. Failures
+    /// land in the status bar; the tree stays usable.
+    fn preview_selected_file(&mut self) {
+        self.file_preview_due = None;
+        let Some(path) = self
+            .file_tree
+            .as_ref()
+            .and_then(FileTree::selected_file)
+            .map(|file| file.path.clone())
+        else {
+            return;
+        };
+        if self.tab.doc.path.as_ref() == Some(&path) {
+            return;
+        }
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) => {
+                self.status = format!("Failed to open {}: {err}", path.display());
+                return;
+            }
+        };
+        self.set_doc(
+            LoadResult {
+                path: Some(path.clone()),
+                source,
+            },
+            false,
+        );
+        self.status = match self.ensure_watcher() {
+            Ok(()) => format!("Previewing {}", path.display()),
+            Err(err) => format!("Previewing {} (not watched: {err:#})", path.display()),
+        };
+    }
+
+    /// `Enter` in the file tree: folds a directory, or hands focus to the
+    /// selected file.
+    fn select_in_file_tree(&mut self) {
+        if self
+            .file_tree
+            .as_mut()
+            .is_some_and(FileTree::toggle_selected_dir)
+        {
+            return;
+        }
+        self.preview_selected_file();
+        let selected = self
+            .file_tree
+            .as_ref()
+            .and_then(FileTree::selected_file)
+            .map(|file| &file.path);
+        if selected.is_some() && selected == self.tab.doc.path.as_ref() {
+            self.file_tree_focused = false;
+            self.status = format!(
+                "Reading {} (F returns to the file tree)",
+                self.tab.doc.title()
+            );
+        }
+    }
+
+    fn open_selected_file_in_tab(&mut self) {
+        let Some(path) = self
+            .file_tree
+            .as_ref()
+            .and_then(FileTree::selected_file)
+            .map(|file| file.path.clone())
+        else {
+            self.status = "No file selected".to_string();
+            return;
+        };
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) => {
+                self.status = format!("Failed to open {}: {err}", path.display());
+                return;
+            }
+        };
+        let opened = self.open_tab(LoadResult {
+            path: Some(path.clone()),
+            source,
+        });
+        self.status = match opened {
+            Ok(()) => format!(
+                "Opened {} in tab {}",
+                path.display(),
+                self.active_tab.saturating_add(1)
+            ),
+            Err(err) => format!("Opened {} (not watched: {err:#})", path.display()),
+        };
+    }
+
+    /// Watches the directories the file tree scan entered. Failing to watch
+    /// (e.g. at the inotify limit) is not fatal: the tree then only updates
+    /// on `R`.
+    fn watch_file_tree(&mut self) {
+        let Some(dirs) = self.file_tree.as_ref().map(|tree| tree.dirs.clone()) else {
+            return;
+        };
+        self.file_tree_watcher = None;
+        let (tx, rx) = mpsc::channel();
+        let watched = RecommendedWatcher::new(
+            move |res| {
+                let _ = tx.send(res);
+            },
+            Config::default(),
+        )
+        .and_then(|mut watcher| {
+            for dir in &dirs {
+                watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            }
+            Ok(watcher)
+        });
+        match watched {
+            Ok(watcher) => {
+                self.file_tree_watcher = Some(FileWatcher {
+                    _watcher: watcher,
+                    rx,
+                });
+            }
+            Err(err) => {
+                self.status =
+                    format!("File tree not watched ({err}); press R in the tree to rescan");
+            }
+        }
+    }
+
+    /// Re-reads the file tree and watches directories that appeared.
+    /// Returns false (with the error in the status bar) if the scan failed.
+    fn rescan_file_tree(&mut self) -> bool {
+        let Some(tree) = self.file_tree.as_mut() else {
+            return false;
+        };
+        let dirs = tree.dirs.clone();
+        if let Err(err) = tree.rescan() {
+            self.status = format!("File tree rescan failed: {err:#}");
+            return false;
+        }
+        if tree.dirs != dirs || self.file_tree_watcher.is_none() {
+            self.watch_file_tree();
+        }
+        true
+    }
+
+    /// Rescans the file tree after markdown files or directories under it
+    /// changed.
+    fn poll_file_tree(&mut self) {
+        let Some(watcher) = self.file_tree_watcher.as_mut() else {
+            return;
+        };
+        let mut changed = false;
+        while let Ok(event) = watcher.rx.try_recv() {
+            if let Ok(event) = event {
+                changed |= event
+                    .paths
+                    .iter()
+                    .any(|path| is_markdown_path(path) || path.is_dir());
+            }
+        }
+        if changed {
+            self.rescan_file_tree();
+        }
+    }
+
+    fn draw_file_tree(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let Some(tree) = &self.file_tree else {
+            return;
+        };
+        let palette = self.palette();
+        let now = SystemTime::now();
+        let visible = usize::from(area.height.saturating_sub(1)).max(1);
+        let skip = tree.selected.saturating_sub(visible - 1);
+        let items: Vec<ListItem> = tree
+            .rows
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .take(visible)
+            .map(|(idx, row)| {
+                let selected = idx == tree.selected;
+                let row_style = if selected && self.file_tree_focused {
+                    Style::default()
+                        .fg(palette.highlight)
+                        .add_modifier(Modifier::BOLD)
+                } else if selected {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                let mut spans = vec![Span::styled(if selected { "> " } else { "  " }, row_style)];
+                match row {
+                    TreeRow::Dir { path, depth, open } => {
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        spans.push(Span::styled(
+                            format!(
+                                "{}{} {name}/",
+                                "  ".repeat(*depth),
+                                if *open { "▾" } else { "▸" }
+                            ),
+                            row_style.fg(palette.subtle),
+                        ));
+                    }
+                    TreeRow::File { index, depth } => {
+                        let Some(file) = tree.files.get(*index) else {
+                            return ListItem::new(Line::raw(""));
+                        };
+                        let name = if tree.sort == FileSort::Recent {
+                            file.relative.display().to_string()
+                        } else {
+                            file.path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_default()
+                        };
+                        let current = self.tab.doc.path.as_ref() == Some(&file.path);
+                        let name_style = if current {
+                            row_style.fg(palette.link)
+                        } else {
+                            row_style
+                        };
+                        spans.push(Span::styled(
+                            format!("{}{name}", "  ".repeat(*depth)),
+                            name_style,
+                        ));
+                        if tree.sort == FileSort::Recent {
+                            spans.push(Span::styled(
+                                format!(" {}", format_age(file.modified, now)),
+                                Style::default().fg(palette.muted),
+                            ));
+                        }
+                    }
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let title = match tree.sort {
+            FileSort::Name => " Files ".to_string(),
+            FileSort::Recent => " Files (recent) ".to_string(),
+        };
+        let list = if items.is_empty() {
+            List::new(vec![ListItem::new(Line::raw("  (no markdown files)"))])
+        } else {
+            List::new(items)
+        }
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(if self.file_tree_focused {
+                    palette.accent
+                } else {
+                    palette.muted
+                }))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+        frame.render_widget(list, area);
+    }
+
+    fn sync_callout_selected_with_scroll(&mut self) {
+        self.tab.callout_selected = self
+            .tab
+            .doc
+            .rendered
+            .callouts
+            .iter()
+            .rposition(|callout| callout.line <= usize::from(self.tab.scroll))
+            .unwrap_or(0);
+    }
+
+    fn move_callout_selection(&mut self, reverse: bool) {
+        let len = self.tab.doc.rendered.callouts.len();
+        if len == 0 {
+            self.tab.callout_selected = 0;
+            self.status = NO_CALLOUTS_STATUS.to_string();
+            return;
+        }
+        if reverse {
+            self.tab.callout_selected = self.tab.callout_selected.saturating_sub(1);
+        } else {
+            self.tab.callout_selected = (self.tab.callout_selected + 1).min(len.saturating_sub(1));
+        }
+    }
+
+    fn jump_to_selected_callout(&mut self) {
+        let len = self.tab.doc.rendered.callouts.len();
+        let Some(callout) = self
+            .tab
+            .doc
+            .rendered
+            .callouts
+            .get(self.tab.callout_selected)
+            .cloned()
+        else {
+            self.status = NO_CALLOUTS_STATUS.to_string();
+            return;
+        };
+        self.set_scroll_to_line(callout.line);
+        self.status = format!(
+            "Callout {}/{len}: {} (line {})",
+            self.tab.callout_selected + 1,
+            callout.kind.label(),
+            callout.source.start_line.saturating_add(1)
+        );
+    }
+
+    fn move_agent_selection(&mut self, reverse: bool) {
+        let len = self.tab.open_agent_tasks.len();
+        if len == 0 {
+            self.status_for_empty_open_tasks();
+            return;
+        }
+
+        if reverse {
+            self.tab.agent_selected = self.tab.agent_selected.saturating_sub(1);
+        } else {
+            self.tab.agent_selected = (self.tab.agent_selected + 1).min(len.saturating_sub(1));
+        }
+    }
+
+    fn jump_to_open_agent_index(&mut self, index: usize) {
+        let len = self.tab.open_agent_tasks.len();
+        if len == 0 {
+            self.status_for_empty_open_tasks();
+            return;
+        }
+
+        self.tab.agent_selected = index.min(len.saturating_sub(1));
+        let Some(task) = self.selected_open_agent_task().cloned() else {
+            self.status_for_empty_open_tasks();
+            return;
+        };
+
+        self.set_scroll_to_line(task.line);
+        self.status = format!(
+            "Agent task {}/{}: {}",
+            self.tab.agent_selected + 1,
+            len,
+            truncate_label(&task.text, 48)
+        );
+    }
+
+    fn jump_to_selected_agent_task(&mut self) {
+        self.jump_to_open_agent_index(self.tab.agent_selected);
+    }
+
+    fn jump_agent_task_relative(&mut self, reverse: bool) {
+        let len = self.tab.open_agent_tasks.len();
+        if len == 0 {
+            self.status_for_empty_open_tasks();
+            return;
+        }
+
+        let line = usize::from(self.tab.scroll);
+        let target = if reverse {
+            line.checked_sub(1)
+                .and_then(|above| self.agent_position_near_line(above, true))
+                .or_else(|| self.agent_position_near_line(usize::MAX, true))
+        } else {
+            self.agent_position_near_line(line.saturating_add(1), false)
+                .or_else(|| self.agent_position_near_line(0, false))
+        };
+
+        self.jump_to_open_agent_index(target.unwrap_or(0));
+    }
+
+    fn begin_quick_task_capture(&mut self) {
+        if self.tab.doc.path.is_none() {
+            self.status = "Quick task capture requires file input".to_string();
+            return;
+        }
+        self.quick_task_mode = true;
+        self.quick_task_input.clear();
+        self.status = "New @agent task: type text, Enter to save, Esc to cancel".to_string();
     }
 
     fn submit_quick_task_capture(&mut self) {
@@ -404,7 +1260,7 @@ impl App {
             return;
         }
 
-        let Some(path) = self.doc.path.clone() else {
+        let Some(path) = self.tab.doc.path.clone() else {
             self.quick_task_mode = false;
             self.quick_task_input.clear();
             self.status = "Quick task capture requires file input".to_string();
@@ -441,10 +1297,170 @@ impl App {
             self.status = format!("Reload failed after adding task: {err:#}");
             return;
         }
-        self.watch_requested = false;
+        self.tab.watch_requested = false;
         self.status = format!("Added agent task: {}", truncate_label(&task_text, 48));
     }
 
+    /// 0-based source line the editor should open at: the selected agent task
+    /// or TOC heading when a panel is open, otherwise the top visible line.
+    fn editor_target_line(&self) -> usize {
+        let panel_line = if self.agent_inbox_open {
+            self.selected_open_agent_task()
+                .and_then(AgentTask::source_line)
+        } else if self.toc_open && self.tab.toc_focused {
+            self.tab
+                .doc
+                .rendered
+                .toc
+                .get(self.tab.toc_selected)
+                .map(|entry| entry.source.start_line)
+        } else if self.callouts_open {
+            self.tab
+                .doc
+                .rendered
+                .callouts
+                .get(self.tab.callout_selected)
+                .map(|callout| callout.source.start_line)
+        } else {
+            None
+        };
+        panel_line
+            .or_else(|| {
+                self.tab
+                    .doc
+                    .rendered
+                    .source_line_at(usize::from(self.tab.scroll))
+            })
+            .unwrap_or(0)
+    }
+
+    fn request_editor(&mut self) {
+        if self.tab.doc.path.is_none() {
+            self.status = "Editing requires file input".to_string();
+            return;
+        }
+        self.editor_requested = Some(self.editor_target_line().saturating_add(1));
+    }
+
+    fn finish_editor(&mut self, line: usize, result: Result<()>) {
+        if let Err(err) = result {
+            self.status = format!("Editor failed: {err:#}");
+            return;
+        }
+        match self.reload_current() {
+            Ok(true) => {}
+            Ok(false) => {
+                self.status = format!("Returned from editor at line {line} (no text changes)");
+            }
+            Err(err) => {
+                self.status = format!("Reload failed after editing: {err:#}");
+                return;
+            }
+        }
+        self.tab.watch_requested = false;
+    }
+
+    /// Task targeted by in-place edits: the Agent Inbox selection when the
+    /// inbox is open, otherwise the first agent task visible in the viewport.
+    fn focused_agent_task(&self) -> Option<AgentTask> {
+        if self.agent_inbox_open {
+            return self.selected_open_agent_task().cloned();
+        }
+        let top = usize::from(self.tab.scroll);
+        let bottom = self.viewport_end();
+        self.tab
+            .agent_tasks
+            .iter()
+            .find(|task| {
+                task.line >= top
+                    && task.line < bottom
+                    && !self.tab.doc.rendered.is_hidden(task.line)
+            })
+            .cloned()
+    }
+
+    fn edit_focused_agent_task(&mut self, delete: bool) {
+        let Some(path) = self.tab.doc.path.clone() else {
+            self.status = "Editing agent tasks requires file input".to_string();
+            return;
+        };
+        if !self.is_live_mode() {
+            self.status = format!(
+                "Return to LIVE ({}) before editing agent tasks",
+                self.keymap.label(Action::LiveRevision)
+            );
+            return;
+        }
+        let Some(task) = self.focused_agent_task() else {
+            self.status_for_empty_open_tasks();
+            return;
+        };
+        let Some(span) = task.source.clone() else {
+            self.status = "Agent task has no source location".to_string();
+            return;
+        };
+
+        if delete && self.pending_task_delete != Some(span.start_line) {
+            self.pending_task_delete = Some(span.start_line);
+            self.status = format!(
+                "Press {} again to delete: {}",
+                self.keymap.label(Action::DeleteTask),
+                truncate_label(&task.text, 48)
+            );
+            return;
+        }
+        self.pending_task_delete = None;
+
+        let stamp = self
+            .cli
+            .done_stamp
+            .then(|| format_date_ymd(SystemTime::now()));
+        let edit = if delete {
+            TaskEdit::Delete
+        } else {
+            TaskEdit::Toggle {
+                done_stamp: stamp.as_deref(),
+            }
+        };
+
+        let result: Result<()> = (|| {
+            let on_disk = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            if on_disk != self.tab.doc.source {
+                return Err(anyhow!(
+                    "file changed on disk since last render; reload first"
+                ));
+            }
+            let edited = edit_task_in_source(&on_disk, span.start_line, span.end_line, edit)
+                .ok_or_else(|| {
+                    anyhow!("line {} is not a task", span.start_line.saturating_add(1))
+                })?;
+            fs::write(&path, edited).with_context(|| format!("Failed to write {}", path.display()))
+        })();
+
+        if let Err(err) = result {
+            self.status = format!("Agent task not changed: {err:#}");
+            return;
+        }
+        if let Err(err) = self.reload_current() {
+            self.status = format!("Reload failed after editing task: {err:#}");
+            return;
+        }
+        self.tab.watch_requested = false;
+
+        let label = truncate_label(&task.text, 48);
+        self.status = if delete {
+            format!("Deleted agent task: {label}")
+        } else if task.is_open() {
+            match &stamp {
+                Some(date) => format!("Completed agent task: {label} (stamped {date} UTC)"),
+                None => format!("Completed agent task: {label}"),
+            }
+        } else {
+            format!("Reopened agent task: {label}")
+        };
+    }
+
     fn handle_quick_task_input(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Esc => {
@@ -469,53 +1485,314 @@ impl App {
         }
     }
 
-    fn latest_snapshot_index(&self) -> usize {
-        self.snapshots.len().saturating_sub(1)
+    fn latest_snapshot_index(&self) -> usize {
+        self.tab.snapshots.len().saturating_sub(1)
+    }
+
+    fn current_snapshot(&self) -> Option<&WatchSnapshot> {
+        self.tab.snapshots.get(self.tab.active_snapshot)
+    }
+
+    fn is_live_mode(&self) -> bool {
+        self.tab.active_snapshot == self.latest_snapshot_index()
+    }
+
+    fn history_key(&self) -> Option<PathBuf> {
+        self.history_store.as_ref()?;
+        let path = self.tab.doc.path.as_ref()?;
+        Some(fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+    }
+
+    /// Loads persisted snapshots for the current document into the timeline,
+    /// oldest first, and rebuilds their diffs. Returns the status to show.
+    fn restore_persisted_snapshots(&mut self) -> Option<String> {
+        let store = self.history_store.clone()?;
+        let key = self.history_key()?;
+        let restored = match store.load(&key, SystemTime::now()) {
+            Ok(restored) => restored,
+            Err(err) => return Some(format!("History restore failed: {err:#}")),
+        };
+        if restored.records.is_empty() {
+            return Some(if restored.corrupt > 0 {
+                format!(
+                    "No persisted history found (skipped {} corrupt records)",
+                    restored.corrupt
+                )
+            } else {
+                "No persisted history found".to_string()
+            });
+        }
+
+        let skip = restored.records.len().saturating_sub(self.history_capacity);
+        let count = restored.records.len().saturating_sub(skip);
+        for record in restored.records.into_iter().skip(skip) {
+            let rendered = self.render(&record.source);
+            let diff = self
+                .tab
+                .snapshots
+                .back()
+                .map(|previous| build_snapshot_diff(&previous.rendered, &rendered))
+                .unwrap_or_default();
+            let created_at = record.created_at();
+            self.tab.next_revision = self
+                .tab
+                .next_revision
+                .max(record.revision.saturating_add(1));
+            self.tab.snapshots.push_back(WatchSnapshot {
+                revision: record.revision,
+                created_at,
+                created_instant: instant_for(created_at),
+                source: record.source,
+                rendered,
+                diff,
+                label: None,
+                stale: false,
+            });
+        }
+
+        let mut status = format!(
+            "Restored {count} snapshots (last {}d)",
+            history::RETENTION_DAYS
+        );
+        if restored.corrupt > 0 {
+            status.push_str(&format!("; skipped {} corrupt records", restored.corrupt));
+        }
+        Some(status)
+    }
+
+    fn persist_latest_snapshot(&self) -> Result<()> {
+        let (Some(store), Some(key), Some(snapshot)) = (
+            &self.history_store,
+            self.history_key(),
+            self.tab.snapshots.back(),
+        ) else {
+            return Ok(());
+        };
+        let record = SnapshotRecord {
+            path: key.to_string_lossy().into_owned(),
+            timestamp: history::unix_seconds(snapshot.created_at),
+            revision: snapshot.revision,
+            added: snapshot.diff.added,
+            removed: snapshot.diff.removed,
+            hunks: snapshot.diff.hunks.len(),
+            top_section: snapshot.diff.top_section.clone(),
+            source: snapshot.source.clone(),
+        };
+        store.append(&record, SystemTime::now())
+    }
+
+    /// Prepends the last `--git-log N` commits of the current file to the
+    /// timeline. Returns the status to show, if any.
+    fn push_git_log(&mut self) -> Option<String> {
+        let count = self.cli.git_log.filter(|count| *count > 0)?;
+        let path = self.tab.doc.path.clone()?;
+        let commits = match file_history(&path, count) {
+            Ok(commits) => commits,
+            Err(err) => return Some(format!("No git history: {err:#}")),
+        };
+        let loaded = commits.len();
+        for commit in commits {
+            let rendered = self.render(&commit.source);
+            let diff = self
+                .tab
+                .snapshots
+                .back()
+                .map(|previous| build_snapshot_diff(&previous.rendered, &rendered))
+                .unwrap_or_default();
+            self.tab.snapshots.push_back(WatchSnapshot {
+                revision: 0,
+                created_at: commit.committed_at,
+                created_instant: instant_for(commit.committed_at),
+                source: commit.source,
+                rendered,
+                diff,
+                label: Some(format!(
+                    "{} {}: {}",
+                    commit.short_sha,
+                    commit.author,
+                    truncate_label(&commit.subject, 40)
+                )),
+                stale: false,
+            });
+        }
+        Some(format!("Loaded {loaded} commits from git history"))
+    }
+
+    /// Seeds the timeline with the current file as of `--since` (default
+    /// `HEAD`) as revision 0. Returns the status to show, if any.
+    fn push_git_baseline(&mut self) -> Option<String> {
+        if self.cli.no_since {
+            return None;
+        }
+        let path = self.tab.doc.path.clone()?;
+        let rev = self.cli.since.as_deref().unwrap_or("HEAD");
+        let baseline = match file_at_revision(&path, rev) {
+            Ok(baseline) => baseline,
+            // Without an explicit --since, files outside a repo just have no baseline.
+            Err(_) if self.cli.since.is_none() => return None,
+            Err(err) => return Some(format!("No git baseline: {err:#}")),
+        };
+
+        // With --git-log the baseline is usually one of the loaded commits
+        // already, or has the same text as one (when `rev` did not touch the file).
+        let in_log = self.tab.snapshots.iter().any(|snapshot| {
+            snapshot.source == baseline.source
+                || snapshot
+                    .label
+                    .as_deref()
+                    .and_then(|label| label.split(' ').next())
+                    == Some(baseline.short_sha.as_str())
+        });
+        if in_log {
+            return None;
+        }
+
+        let rendered = self.render(&baseline.source);
+        let label = format!("{rev}@{}", baseline.short_sha);
+        let status = format!("Comparing against {label}");
+        let position = self
+            .tab
+            .snapshots
+            .iter()
+            .position(|snapshot| snapshot.created_at > baseline.committed_at)
+            .unwrap_or(self.tab.snapshots.len());
+        self.tab.snapshots.insert(
+            position,
+            WatchSnapshot {
+                revision: 0,
+                created_at: baseline.committed_at,
+                created_instant: instant_for(baseline.committed_at),
+                source: baseline.source,
+                rendered,
+                diff: SnapshotDiff::default(),
+                label: Some(label),
+                stale: false,
+            },
+        );
+        self.rebuild_snapshot_diff(position);
+        self.rebuild_snapshot_diff(position.saturating_add(1));
+        Some(status)
+    }
+
+    /// Re-renders snapshot `idx` and its diff if a layout change left them stale.
+    fn refresh_snapshot(&mut self, idx: usize) {
+        if !self
+            .tab
+            .snapshots
+            .get(idx)
+            .is_some_and(|snapshot| snapshot.stale)
+        {
+            return;
+        }
+        let rendered = self.render(&self.tab.snapshots[idx].source);
+        let diff = match idx
+            .checked_sub(1)
+            .and_then(|previous| self.tab.snapshots.get(previous))
+        {
+            Some(previous) if previous.stale => {
+                build_snapshot_diff(&self.render(&previous.source), &rendered)
+            }
+            Some(previous) => build_snapshot_diff(&previous.rendered, &rendered),
+            None => SnapshotDiff::default(),
+        };
+        let snapshot = &mut self.tab.snapshots[idx];
+        snapshot.rendered = rendered;
+        snapshot.diff = diff;
+        snapshot.stale = false;
     }
 
-    fn current_snapshot(&self) -> Option<&WatchSnapshot> {
-        self.snapshots.get(self.active_snapshot)
+    /// Refreshes every stale snapshot, for views that list all revisions.
+    fn refresh_snapshots(&mut self) {
+        for idx in 0..self.tab.snapshots.len() {
+            self.refresh_snapshot(idx);
+        }
     }
 
-    fn is_live_mode(&self) -> bool {
-        self.active_snapshot == self.latest_snapshot_index()
+    /// Recomputes the diff of snapshot `idx` against its predecessor.
+    fn rebuild_snapshot_diff(&mut self, idx: usize) {
+        let diff = idx
+            .checked_sub(1)
+            .and_then(|previous| self.tab.snapshots.get(previous))
+            .zip(self.tab.snapshots.get(idx))
+            .map(|(previous, snapshot)| build_snapshot_diff(&previous.rendered, &snapshot.rendered))
+            .unwrap_or_default();
+        if let Some(snapshot) = self.tab.snapshots.get_mut(idx) {
+            snapshot.diff = diff;
+        }
     }
 
     fn reset_snapshots_from_current_doc(&mut self) {
-        let revision = self.next_revision;
-        self.next_revision = self.next_revision.saturating_add(1);
-        self.snapshots.clear();
-        self.snapshots.push_back(WatchSnapshot {
-            revision,
-            created_at: SystemTime::now(),
-            created_instant: Instant::now(),
-            rendered: self.doc.rendered.clone(),
-            diff: SnapshotDiff::default(),
-        });
-        self.active_snapshot = 0;
+        self.tab.snapshots.clear();
+        let log_status = self.push_git_log();
+        let baseline_status = self.push_git_baseline();
+        let restore_status = self.restore_persisted_snapshots();
+
+        let diff = self
+            .tab
+            .snapshots
+            .back()
+            .map(|previous| build_snapshot_diff(&previous.rendered, &self.tab.doc.rendered));
+        let unchanged = diff
+            .as_ref()
+            .is_some_and(|diff| diff.hunks.is_empty() && diff.added == 0 && diff.removed == 0);
+        if !unchanged {
+            let revision = self.tab.next_revision;
+            self.tab.next_revision = self.tab.next_revision.saturating_add(1);
+            self.tab.snapshots.push_back(WatchSnapshot {
+                revision,
+                created_at: SystemTime::now(),
+                created_instant: Instant::now(),
+                source: self.tab.doc.source.clone(),
+                rendered: self.tab.doc.rendered.clone(),
+                diff: diff.unwrap_or_default(),
+                label: None,
+                stale: false,
+            });
+            while self.tab.snapshots.len() > self.history_capacity {
+                self.tab.snapshots.pop_front();
+            }
+        }
+        self.tab.active_snapshot = self.latest_snapshot_index();
+
+        let statuses: Vec<String> = [log_status, baseline_status, restore_status]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !statuses.is_empty() {
+            self.status = statuses.join("; ");
+        }
+        if !unchanged {
+            if let Err(err) = self.persist_latest_snapshot() {
+                self.status = format!("History write failed: {err:#}");
+            }
+        }
     }
 
     fn sync_doc_with_active_snapshot(&mut self, old_scroll: u16, fallback_to_first_hunk: bool) {
+        self.refresh_snapshot(self.tab.active_snapshot);
         let Some(snapshot) = self.current_snapshot().cloned() else {
             return;
         };
 
-        self.doc.rendered = snapshot.rendered;
+        self.tab.doc.rendered = snapshot.rendered;
         self.reset_selected_link();
         self.refresh_agent_tasks();
 
         self.update_search_matches();
-        if self.search_query.is_empty() || self.search_matches.is_empty() {
+        if self.tab.search_query.is_empty() || self.tab.search_matches.is_empty() {
             if old_scroll <= self.max_scroll() {
-                self.scroll = old_scroll;
+                self.tab.scroll = old_scroll;
             } else if fallback_to_first_hunk {
                 if let Some(hunk) = snapshot.diff.hunks.first() {
-                    self.set_scroll_to_line(hunk_anchor_line(hunk, self.doc.rendered.lines.len()));
+                    self.set_scroll_to_line(hunk_anchor_line(
+                        hunk,
+                        self.tab.doc.rendered.lines.len(),
+                    ));
                 } else {
-                    self.scroll = self.max_scroll();
+                    self.tab.scroll = self.max_scroll();
                 }
             } else {
-                self.scroll = self.max_scroll();
+                self.tab.scroll = self.max_scroll();
             }
         }
 
@@ -524,8 +1801,10 @@ impl App {
         self.sync_agent_selected_with_scroll();
     }
 
-    fn push_watch_snapshot(&mut self, rendered: RenderedDocument) -> bool {
+    fn push_watch_snapshot(&mut self, source: String, rendered: RenderedDocument) -> bool {
+        self.refresh_snapshot(self.latest_snapshot_index());
         let diff = self
+            .tab
             .snapshots
             .back()
             .map(|previous| build_snapshot_diff(&previous.rendered, &rendered))
@@ -536,38 +1815,276 @@ impl App {
         }
 
         let was_live = self.is_live_mode();
-        let old_scroll = self.scroll;
-        let revision = self.next_revision;
-        self.next_revision = self.next_revision.saturating_add(1);
+        let diff_follows_live = self.tab.diff_view.as_ref().is_some_and(|view| {
+            self.tab.snapshots.back().map(WatchSnapshot::name).as_ref() == Some(&view.target)
+        });
+        let old_scroll = self.tab.scroll;
+        let revision = self.tab.next_revision;
+        self.tab.next_revision = self.tab.next_revision.saturating_add(1);
 
-        self.snapshots.push_back(WatchSnapshot {
+        self.tab.snapshots.push_back(WatchSnapshot {
             revision,
             created_at: SystemTime::now(),
             created_instant: Instant::now(),
+            source,
             rendered,
             diff,
+            label: None,
+            stale: false,
         });
 
         let mut selected_evicted = false;
-        while self.snapshots.len() > self.history_capacity {
-            self.snapshots.pop_front();
-            if self.active_snapshot > 0 {
-                self.active_snapshot = self.active_snapshot.saturating_sub(1);
+        while self.tab.snapshots.len() > self.history_capacity {
+            self.tab.snapshots.pop_front();
+            if self.tab.active_snapshot > 0 {
+                self.tab.active_snapshot = self.tab.active_snapshot.saturating_sub(1);
             } else {
                 selected_evicted = true;
             }
         }
 
         if was_live {
-            self.active_snapshot = self.latest_snapshot_index();
+            self.tab.active_snapshot = self.latest_snapshot_index();
             self.sync_doc_with_active_snapshot(old_scroll, true);
         } else if selected_evicted {
             self.sync_doc_with_active_snapshot(old_scroll, true);
         }
 
+        if let Some(view) = self.tab.diff_view.as_mut() {
+            if diff_follows_live {
+                view.target = format!("r{revision:03}");
+            }
+            self.refresh_diff_view();
+        }
+
         true
     }
 
+    fn snapshot_index_for_name(&self, name: &str) -> Option<usize> {
+        self.tab
+            .snapshots
+            .iter()
+            .position(|snapshot| snapshot.name() == name)
+    }
+
+    fn toggle_diff_view(&mut self) {
+        if !self.require_watch_mode("Diff view is available only in --watch mode") {
+            return;
+        }
+        if self.tab.diff_view.take().is_some() {
+            self.status = "Diff view closed".to_string();
+            return;
+        }
+        if self.tab.snapshots.len() <= 1 {
+            self.status = "No prior revisions yet".to_string();
+            return;
+        }
+
+        let target = self.tab.active_snapshot.max(1);
+        let base = target.saturating_sub(1);
+        self.tab.diff_view = Some(DiffView {
+            base: self.tab.snapshots[base].name(),
+            target: self.tab.snapshots[target].name(),
+            layout: DiffLayout::default(),
+            rows: Vec::new(),
+            inline: Vec::new(),
+            added: 0,
+            removed: 0,
+            scroll: 0,
+        });
+        self.refresh_diff_view();
+        self.show_diff_status();
+        if let Some(row) = self
+            .tab
+            .diff_view
+            .as_ref()
+            .and_then(|view| view.hunk_starts().first().copied())
+        {
+            self.set_diff_scroll(row);
+        }
+    }
+
+    /// Recomputes the open diff view, falling back to the oldest/latest
+    /// snapshot when a compared revision has been evicted.
+    fn refresh_diff_view(&mut self) {
+        let Some(view) = self.tab.diff_view.as_ref() else {
+            return;
+        };
+        let base = self.snapshot_index_for_name(&view.base).unwrap_or(0);
+        let target = self
+            .snapshot_index_for_name(&view.target)
+            .unwrap_or_else(|| self.latest_snapshot_index());
+        self.refresh_snapshot(base);
+        self.refresh_snapshot(target);
+        let (Some(base_snapshot), Some(target_snapshot)) =
+            (self.tab.snapshots.get(base), self.tab.snapshots.get(target))
+        else {
+            self.tab.diff_view = None;
+            return;
+        };
+
+        let old_lines: Vec<&str> = base_snapshot
+            .rendered
+            .lines
+            .iter()
+            .map(|line| line.plain.as_str())
+            .collect();
+        let new_lines: Vec<&str> = target_snapshot
+            .rendered
+            .lines
+            .iter()
+            .map(|line| line.plain.as_str())
+            .collect();
+        let (rows, line_diff) = diff_rows(&old_lines, &new_lines);
+        let (base_name, target_name) = (base_snapshot.name(), target_snapshot.name());
+
+        let Some(view) = self.tab.diff_view.as_mut() else {
+            return;
+        };
+        view.base = base_name;
+        view.target = target_name;
+        view.inline = inline_rows(&rows);
+        view.rows = rows;
+        view.added = line_diff.added;
+        view.removed = line_diff.removed;
+        let max_scroll = usize_to_u16_saturating(
+            view.visible_rows()
+                .len()
+                .saturating_sub(usize::from(self.viewport_height)),
+        );
+        view.scroll = view.scroll.min(max_scroll);
+    }
+
+    fn show_diff_status(&mut self) {
+        if let Some(view) = self.tab.diff_view.as_ref() {
+            self.status = format!(
+                "Diff {} -> {} (+{}/-{}, {})",
+                view.base,
+                view.target,
+                view.added,
+                view.removed,
+                view.layout.label()
+            );
+        }
+    }
+
+    fn move_diff_revision(&mut self, base: bool, older: bool) {
+        let Some(view) = self.tab.diff_view.as_ref() else {
+            return;
+        };
+        let name = if base { &view.base } else { &view.target };
+        let Some(index) = self.snapshot_index_for_name(name) else {
+            return;
+        };
+        let next = if older {
+            index.saturating_sub(1)
+        } else {
+            index.saturating_add(1).min(self.latest_snapshot_index())
+        };
+        if next == index {
+            self.status = if older {
+                "Already at oldest revision".to_string()
+            } else {
+                "Already at latest revision".to_string()
+            };
+            return;
+        }
+
+        let name = self.tab.snapshots[next].name();
+        if let Some(view) = self.tab.diff_view.as_mut() {
+            if base {
+                view.base = name;
+            } else {
+                view.target = name;
+            }
+        }
+        self.refresh_diff_view();
+        self.show_diff_status();
+    }
+
+    fn toggle_diff_layout(&mut self) {
+        if let Some(view) = self.tab.diff_view.as_mut() {
+            view.layout = view.layout.toggle();
+            view.scroll = 0;
+        }
+        self.refresh_diff_view();
+        self.show_diff_status();
+    }
+
+    fn set_diff_scroll(&mut self, row: usize) {
+        let viewport = usize::from(self.viewport_height);
+        if let Some(view) = self.tab.diff_view.as_mut() {
+            let max_scroll = view.visible_rows().len().saturating_sub(viewport);
+            view.scroll = usize_to_u16_saturating(row.min(max_scroll));
+        }
+    }
+
+    fn scroll_diff_by(&mut self, delta: isize) {
+        let Some(view) = self.tab.diff_view.as_ref() else {
+            return;
+        };
+        let row = usize::from(view.scroll).saturating_add_signed(delta);
+        self.set_diff_scroll(row);
+    }
+
+    fn jump_diff_hunk_relative(&mut self, reverse: bool) {
+        let Some(view) = self.tab.diff_view.as_ref() else {
+            return;
+        };
+        let starts = view.hunk_starts();
+        if starts.is_empty() {
+            self.status = "No differences between selected revisions".to_string();
+            return;
+        }
+        let cursor = usize::from(view.scroll);
+        let target = if reverse {
+            starts
+                .iter()
+                .rfind(|row| **row < cursor)
+                .or(starts.last())
+                .copied()
+        } else {
+            starts
+                .iter()
+                .find(|row| **row > cursor)
+                .or(starts.first())
+                .copied()
+        }
+        .unwrap_or(0);
+        self.set_diff_scroll(target);
+        let number = starts
+            .iter()
+            .position(|row| *row == target)
+            .map_or(1, |idx| idx.saturating_add(1));
+        self.status = format!("Diff hunk {number}/{}", starts.len());
+    }
+
+    fn handle_diff_view_action(&mut self, action: Action) {
+        let half_page =
+            isize::from(i16::try_from(self.viewport_height / 2).unwrap_or(i16::MAX)).max(1);
+        let page = isize::from(i16::try_from(self.viewport_height).unwrap_or(i16::MAX)).max(1);
+        match action {
+            Action::ToggleDiff | Action::Quit => self.toggle_diff_view(),
+            Action::Help => self.open_help(),
+            Action::DiffLayout => self.toggle_diff_layout(),
+            Action::OlderRevision => self.move_diff_revision(false, true),
+            Action::NewerRevision => self.move_diff_revision(false, false),
+            Action::PrevHeading => self.move_diff_revision(true, true),
+            Action::NextHeading => self.move_diff_revision(true, false),
+            Action::PrevHunk => self.jump_diff_hunk_relative(true),
+            Action::NextHunk => self.jump_diff_hunk_relative(false),
+            Action::ScrollDown => self.scroll_diff_by(1),
+            Action::ScrollUp => self.scroll_diff_by(-1),
+            Action::HalfPageDown => self.scroll_diff_by(half_page),
+            Action::HalfPageUp => self.scroll_diff_by(-half_page),
+            Action::PageDown => self.scroll_diff_by(page),
+            Action::PageUp => self.scroll_diff_by(-page),
+            Action::Top => self.set_diff_scroll(0),
+            Action::Bottom => self.set_diff_scroll(usize::MAX),
+            _ => {}
+        }
+    }
+
     fn toggle_timeline(&mut self) {
         if !self.require_watch_mode("Timeline is available only in --watch mode") {
             return;
@@ -579,20 +2096,21 @@ impl App {
         if !self.require_watch_mode("Revision navigation is available only in --watch mode") {
             return;
         }
-        if self.snapshots.len() <= 1 {
+        if self.tab.snapshots.len() <= 1 {
             self.status = "No prior revisions yet".to_string();
             return;
         }
 
         let next_index = if older {
-            self.active_snapshot.saturating_sub(1)
+            self.tab.active_snapshot.saturating_sub(1)
         } else {
-            self.active_snapshot
+            self.tab
+                .active_snapshot
                 .saturating_add(1)
                 .min(self.latest_snapshot_index())
         };
 
-        if next_index == self.active_snapshot {
+        if next_index == self.tab.active_snapshot {
             self.status = if older {
                 "Already at oldest revision".to_string()
             } else {
@@ -601,18 +2119,22 @@ impl App {
             return;
         }
 
-        let old_scroll = self.scroll;
-        self.active_snapshot = next_index;
+        self.jump_to_revision(next_index);
+    }
+
+    fn jump_to_revision(&mut self, index: usize) {
+        let old_scroll = self.tab.scroll;
+        self.tab.active_snapshot = index.min(self.latest_snapshot_index());
         self.sync_doc_with_active_snapshot(old_scroll, true);
 
         if let Some(snapshot) = self.current_snapshot() {
             let behind = self
                 .latest_snapshot_index()
-                .saturating_sub(self.active_snapshot);
+                .saturating_sub(self.tab.active_snapshot);
             if behind == 0 {
-                self.status = format!("LIVE r{:03}", snapshot.revision);
+                self.status = format!("LIVE {}", snapshot.name());
             } else {
-                self.status = format!("HISTORY r{:03} ({behind} behind LIVE)", snapshot.revision);
+                self.status = format!("HISTORY {} ({behind} behind LIVE)", snapshot.name());
             }
         }
     }
@@ -621,7 +2143,7 @@ impl App {
         if !self.require_watch_mode("Jump-to-live is available only in --watch mode") {
             return;
         }
-        if self.snapshots.is_empty() {
+        if self.tab.snapshots.is_empty() {
             return;
         }
         if self.is_live_mode() {
@@ -629,11 +2151,11 @@ impl App {
             return;
         }
 
-        let old_scroll = self.scroll;
-        self.active_snapshot = self.latest_snapshot_index();
+        let old_scroll = self.tab.scroll;
+        self.tab.active_snapshot = self.latest_snapshot_index();
         self.sync_doc_with_active_snapshot(old_scroll, true);
         if let Some(snapshot) = self.current_snapshot() {
-            self.status = format!("Returned to LIVE r{:03}", snapshot.revision);
+            self.status = format!("Returned to LIVE {}", snapshot.name());
         }
     }
 
@@ -647,7 +2169,7 @@ impl App {
             return;
         }
 
-        let total_lines = self.doc.rendered.lines.len();
+        let total_lines = self.tab.doc.rendered.lines.len();
         let anchors: Vec<usize> = snapshot
             .diff
             .hunks
@@ -655,7 +2177,7 @@ impl App {
             .map(|hunk| hunk_anchor_line(hunk, total_lines))
             .collect();
 
-        let cursor = usize::from(self.scroll);
+        let cursor = usize::from(self.tab.scroll);
         let target = if reverse {
             anchors
                 .iter()
@@ -676,87 +2198,136 @@ impl App {
             .position(|line| *line == target)
             .map(|idx| idx.saturating_add(1))
             .unwrap_or(1);
-        self.status = format!("Hunk {hunk_number}/{}", anchors.len());
+        self.status = match self.tab.doc.rendered.source_line_at(target) {
+            Some(source_line) => format!(
+                "Hunk {hunk_number}/{} (line {})",
+                anchors.len(),
+                source_line.saturating_add(1)
+            ),
+            None => format!("Hunk {hunk_number}/{}", anchors.len()),
+        };
     }
 
+    /// Top line that still fills the viewport; lines hidden in collapsed
+    /// `<details>` blocks take no room.
     fn max_scroll(&self) -> u16 {
-        let total = self.doc.rendered.lines.len();
-        let visible = self.viewport_height.max(1) as usize;
-        usize_to_u16_saturating(total.saturating_sub(visible))
+        let rendered = &self.tab.doc.rendered;
+        let visible = usize::from(self.viewport_height.max(1));
+        let last = rendered.lines.len().saturating_sub(1);
+        usize_to_u16_saturating(rendered.step_shown(last, visible - 1, true))
+    }
+
+    /// Line index just past the last line on screen.
+    fn viewport_end(&self) -> usize {
+        let visible = usize::from(self.viewport_height.max(1));
+        self.tab
+            .doc
+            .rendered
+            .step_shown(usize::from(self.tab.scroll), visible - 1, false)
+            .saturating_add(1)
     }
 
     fn set_scroll_and_sync(&mut self, scroll: u16) {
-        self.scroll = scroll.min(self.max_scroll());
+        let shown = self.tab.doc.rendered.shown_line(usize::from(scroll));
+        self.tab.scroll = usize_to_u16_saturating(shown).min(self.max_scroll());
         self.sync_toc_selected_with_scroll();
+        self.sync_callout_selected_with_scroll();
         self.sync_agent_selected_with_scroll();
     }
 
+    /// Scrolls by `count` shown lines.
+    fn scroll_by(&mut self, count: u16, up: bool) {
+        let line =
+            self.tab
+                .doc
+                .rendered
+                .step_shown(usize::from(self.tab.scroll), usize::from(count), up);
+        self.set_scroll_and_sync(usize_to_u16_saturating(line));
+    }
+
+    /// Scrolls `line` to the top, expanding `<details>` blocks that hide it.
     fn set_scroll_to_line(&mut self, line: usize) {
+        self.reveal_line(line);
         self.set_scroll_and_sync(usize_to_u16_saturating(line));
     }
 
     fn clamp_scroll(&mut self) {
-        self.scroll = self.scroll.min(self.max_scroll());
+        let shown = self
+            .tab
+            .doc
+            .rendered
+            .shown_line(usize::from(self.tab.scroll));
+        self.tab.scroll = usize_to_u16_saturating(shown).min(self.max_scroll());
     }
 
     fn selected_link_line(&self) -> Option<usize> {
-        self.selected_link
-            .and_then(|idx| self.doc.rendered.links.get(idx))
+        self.tab
+            .selected_link
+            .and_then(|idx| self.tab.doc.rendered.links.get(idx))
             .map(|link| link.line)
     }
 
     fn sync_toc_selected_with_scroll(&mut self) {
-        self.toc_selected = self
+        self.tab.toc_focused = false;
+        self.tab.toc_selected = self
+            .tab
             .doc
             .rendered
             .toc
             .iter()
-            .rposition(|entry| entry.line <= usize::from(self.scroll))
+            .rposition(|entry| entry.line <= usize::from(self.tab.scroll))
             .unwrap_or(0);
     }
 
     fn move_toc_selection(&mut self, reverse: bool) {
-        let len = self.doc.rendered.toc.len();
+        let len = self.tab.doc.rendered.toc.len();
         if len == 0 {
-            self.toc_selected = 0;
+            self.tab.toc_selected = 0;
             self.status = NO_TOC_HEADINGS_STATUS.to_string();
             return;
         }
         if reverse {
-            self.toc_selected = self.toc_selected.saturating_sub(1);
+            self.tab.toc_selected = self.tab.toc_selected.saturating_sub(1);
         } else {
-            self.toc_selected = (self.toc_selected + 1).min(len.saturating_sub(1));
+            self.tab.toc_selected = (self.tab.toc_selected + 1).min(len.saturating_sub(1));
         }
+        self.tab.toc_focused = true;
     }
 
     fn jump_to_toc_index(&mut self, index: usize) {
-        if let Some((line, title)) = self
-            .doc
-            .rendered
-            .toc
-            .get(index)
-            .map(|entry| (entry.line, entry.title.clone()))
+        if let Some((line, title, slug, source_line)) =
+            self.tab.doc.rendered.toc.get(index).map(|entry| {
+                (
+                    entry.line,
+                    entry.title.clone(),
+                    entry.slug.clone(),
+                    entry.source.start_line,
+                )
+            })
         {
-            self.toc_selected = index;
+            self.tab.toc_selected = index;
             self.set_scroll_to_line(line);
-            self.status = format!("Jumped to {title}");
+            self.status = format!(
+                "Jumped to {title} (#{slug}, line {})",
+                source_line.saturating_add(1)
+            );
         } else {
             self.status = NO_TOC_HEADINGS_STATUS.to_string();
         }
     }
 
     fn jump_to_toc_selected(&mut self) {
-        self.jump_to_toc_index(self.toc_selected);
+        self.jump_to_toc_index(self.tab.toc_selected);
     }
 
     fn jump_heading_relative(&mut self, reverse: bool) {
-        let toc = &self.doc.rendered.toc;
+        let toc = &self.tab.doc.rendered.toc;
         if toc.is_empty() {
             self.status = NO_TOC_HEADINGS_STATUS.to_string();
             return;
         }
 
-        let line = usize::from(self.scroll);
+        let line = usize::from(self.tab.scroll);
         let target_index = if reverse {
             toc.iter()
                 .enumerate()
@@ -774,64 +2345,83 @@ impl App {
     }
 
     fn update_search_matches(&mut self) {
-        if self.search_query.is_empty() {
-            self.search_matches.clear();
-            self.current_match = 0;
-            return;
-        }
-
-        let needle = self.search_query.to_ascii_lowercase();
-        self.search_matches = self
-            .doc
-            .rendered
-            .lines
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, line)| {
-                if line.plain.to_ascii_lowercase().contains(&needle) {
-                    Some(idx)
-                } else {
+        self.tab.search_error = None;
+        self.tab.search_pattern =
+            match SearchPattern::compile(&self.tab.search_query, self.search_flags) {
+                Ok(pattern) => pattern,
+                Err(err) => {
+                    self.tab.search_error = Some(err.to_string());
                     None
                 }
-            })
-            .collect();
+            };
+        self.tab.search_matches = self
+            .tab
+            .search_pattern
+            .as_ref()
+            .map(|pattern| pattern.find_all(&self.tab.doc.rendered.lines))
+            .unwrap_or_default();
 
-        if self.search_matches.is_empty() {
-            self.current_match = 0;
+        if self.tab.search_matches.is_empty() {
+            self.tab.current_match = 0;
             return;
         }
 
-        self.current_match = self
+        self.tab.current_match = self
+            .tab
             .current_match
-            .min(self.search_matches.len().saturating_sub(1));
-        self.set_scroll_to_line(self.search_matches[self.current_match]);
+            .min(self.tab.search_matches.len().saturating_sub(1));
+        self.set_scroll_to_line(self.tab.search_matches[self.tab.current_match].line);
+    }
+
+    /// Byte ranges of the search matches on rendered line `line`, plus the
+    /// current match's range if it is on that line.
+    fn search_ranges_on_line(&self, line: usize) -> (Vec<Range<usize>>, Option<Range<usize>>) {
+        let start = self
+            .tab
+            .search_matches
+            .partition_point(|found| found.line < line);
+        let end = self
+            .tab
+            .search_matches
+            .partition_point(|found| found.line <= line);
+        let current = self
+            .tab
+            .search_matches
+            .get(self.tab.current_match)
+            .filter(|found| found.line == line)
+            .map(|found| found.range.clone());
+        let ranges = self.tab.search_matches[start..end]
+            .iter()
+            .map(|found| found.range.clone())
+            .collect();
+        (ranges, current)
     }
 
     fn jump_to_next_match(&mut self, reverse: bool) {
-        if self.search_matches.is_empty() {
+        if self.tab.search_matches.is_empty() {
             return;
         }
 
         if reverse {
-            if self.current_match == 0 {
-                self.current_match = self.search_matches.len().saturating_sub(1);
+            if self.tab.current_match == 0 {
+                self.tab.current_match = self.tab.search_matches.len().saturating_sub(1);
             } else {
-                self.current_match -= 1;
+                self.tab.current_match -= 1;
             }
         } else {
-            self.current_match = (self.current_match + 1) % self.search_matches.len();
+            self.tab.current_match = (self.tab.current_match + 1) % self.tab.search_matches.len();
         }
-        self.set_scroll_to_line(self.search_matches[self.current_match]);
+        self.set_scroll_to_line(self.tab.search_matches[self.tab.current_match].line);
     }
 
     fn cycle_link(&mut self, reverse: bool) {
-        if self.doc.rendered.links.is_empty() {
-            self.selected_link = None;
+        if self.tab.doc.rendered.links.is_empty() {
+            self.tab.selected_link = None;
             return;
         }
 
-        let len = self.doc.rendered.links.len();
-        let idx = self.selected_link.unwrap_or(0);
+        let len = self.tab.doc.rendered.links.len();
+        let idx = self.tab.selected_link.unwrap_or(0);
         let next = if reverse {
             if idx == 0 {
                 len - 1
@@ -841,28 +2431,30 @@ impl App {
         } else {
             (idx + 1) % len
         };
-        self.selected_link = Some(next);
+        self.tab.selected_link = Some(next);
         if let Some(line) = self.selected_link_line() {
             self.set_scroll_to_line(line);
         }
     }
 
     fn set_doc(&mut self, load: LoadResult, preserve_scroll: bool) {
-        let old_scroll = self.scroll;
-        let rendered = render_markdown(&load.source, &self.syntax_set, &self.theme);
-        self.doc = LoadedDocument {
+        let old_scroll = self.tab.scroll;
+        let rendered = self.render(&load.source);
+        self.tab.doc = LoadedDocument {
             path: load.path,
+            source: load.source,
             rendered,
         };
 
         self.reset_selected_link();
 
         if preserve_scroll {
-            self.scroll = old_scroll;
+            self.tab.scroll = old_scroll;
         } else {
-            self.scroll = 0;
+            self.tab.scroll = 0;
         }
 
+        self.tab.diff_view = None;
         self.reset_snapshots_from_current_doc();
         self.refresh_agent_tasks();
         self.update_search_matches();
@@ -871,9 +2463,11 @@ impl App {
         self.sync_agent_selected_with_scroll();
     }
 
-    fn reload_current(&mut self) -> Result<()> {
-        let Some(path) = self.doc.path.clone() else {
-            return Ok(());
+    /// Reads the file again and returns whether its text changed, i.e.
+    /// whether a new revision was recorded.
+    fn reload_current(&mut self) -> Result<bool> {
+        let Some(path) = self.tab.doc.path.clone() else {
+            return Ok(false);
         };
 
         let load = LoadResult {
@@ -882,13 +2476,15 @@ impl App {
             path: Some(path.clone()),
         };
 
-        let rendered = render_markdown(&load.source, &self.syntax_set, &self.theme);
-        self.doc.path = load.path;
+        let rendered = self.render(&load.source);
+        self.tab.doc.path = load.path;
+        self.tab.doc.source = load.source;
         let was_live = self.is_live_mode();
 
-        if self.push_watch_snapshot(rendered) {
+        let changed = self.push_watch_snapshot(self.tab.doc.source.clone(), rendered);
+        if changed {
             if was_live {
-                if let Some(snapshot) = self.snapshots.back() {
+                if let Some(snapshot) = self.tab.snapshots.back() {
                     if snapshot.diff.overflow {
                         self.status = format!(
                             "Reloaded {} -> r{:03} (+{}/-{}, fallback diff)",
@@ -912,34 +2508,38 @@ impl App {
                 }
             } else {
                 let latest_rev = self
+                    .tab
                     .snapshots
                     .back()
                     .map(|snapshot| snapshot.revision)
                     .unwrap_or(0);
                 let behind = self
                     .latest_snapshot_index()
-                    .saturating_sub(self.active_snapshot);
+                    .saturating_sub(self.tab.active_snapshot);
                 self.status = format!(
                     "LIVE advanced to r{:03}; viewing historical snapshot ({behind} behind)",
                     latest_rev
                 );
             }
+            if let Err(err) = self.persist_latest_snapshot() {
+                self.status = format!("History write failed: {err:#}");
+            }
         } else {
             self.status = format!("Reloaded {} (no text changes)", path.display());
         }
 
         self.ensure_watcher()?;
-        Ok(())
+        Ok(changed)
     }
 
     fn ensure_watcher(&mut self) -> Result<()> {
         if !self.cli.watch {
-            self.watcher = None;
+            self.tab.watcher = None;
             return Ok(());
         }
 
-        let Some(path) = self.doc.path.clone() else {
-            self.watcher = None;
+        let Some(path) = self.tab.doc.path.clone() else {
+            self.tab.watcher = None;
             return Ok(());
         };
 
@@ -952,7 +2552,7 @@ impl App {
         )?;
 
         watcher.watch(&path, RecursiveMode::NonRecursive)?;
-        self.watcher = Some(FileWatcher {
+        self.tab.watcher = Some(FileWatcher {
             _watcher: watcher,
             rx,
         });
@@ -960,40 +2560,82 @@ impl App {
     }
 
     fn poll_watch(&mut self) {
-        if let Some(watcher) = self.watcher.as_mut() {
+        if let Some(watcher) = self.tab.watcher.as_mut() {
             while let Ok(event) = watcher.rx.try_recv() {
                 if event.is_ok() {
-                    self.watch_requested = true;
+                    self.tab.watch_requested = true;
+                }
+            }
+        }
+        for index in 0..self.tabs.len() {
+            let mut changed = false;
+            if let Some(watcher) = self.tabs[index].watcher.as_mut() {
+                while let Ok(event) = watcher.rx.try_recv() {
+                    changed |= event.is_ok();
                 }
             }
+            if changed {
+                self.reload_background_tab(index);
+            }
+        }
+    }
+
+    /// Records a revision for the background tab at `index` in `tabs`
+    /// without showing it. The status line keeps describing the shown tab.
+    fn reload_background_tab(&mut self, index: usize) {
+        let status = mem::take(&mut self.status);
+        mem::swap(&mut self.tab, &mut self.tabs[index]);
+        let result = self.reload_current();
+        if matches!(result, Ok(true)) {
+            self.tab.unseen_revision = true;
         }
+        let title = self.tab.doc.title();
+        mem::swap(&mut self.tab, &mut self.tabs[index]);
+        self.status = match result {
+            Ok(_) => status,
+            Err(err) => format!("Reload failed in tab {title}: {err:#}"),
+        };
     }
 
     fn open_selected_link(&mut self, force_external: bool) -> Result<()> {
-        let Some(link_idx) = self.selected_link else {
+        let Some(link_idx) = self.tab.selected_link else {
             self.status = "No link selected".to_string();
             return Ok(());
         };
 
-        let link = if let Some(link) = self.doc.rendered.links.get(link_idx) {
+        let link = if let Some(link) = self.tab.doc.rendered.links.get(link_idx) {
             link.clone()
         } else {
             self.status = "Invalid link selection".to_string();
             return Ok(());
         };
 
-        let action = classify_link(&link.target, self.doc.path.as_deref());
+        let action = classify_link(&link.target, self.tab.doc.path.as_deref());
 
         match (force_external, action) {
-            (_, LinkAction::Anchor(anchor)) => {
-                self.status = format!("Anchor links not yet implemented: {anchor}");
+            (_, LinkAction::Anchor(fragment)) => {
+                if self.tab.doc.rendered.anchor_line(&fragment).is_none() {
+                    self.status = format!(
+                        "Anchor not found: #{fragment} (linked from line {})",
+                        link.source.start_line.saturating_add(1)
+                    );
+                    return Ok(());
+                }
+                self.tab.backstack.push(HistoryEntry {
+                    path: self.tab.doc.path.clone(),
+                    scroll: self.tab.scroll,
+                    selected_link: self.tab.selected_link,
+                });
+                self.jump_to_anchor(&fragment);
             }
-            (false, LinkAction::InternalMarkdown(path)) => {
+            (false, LinkAction::InternalMarkdown(path, fragment)) => {
                 let canonical = fs::canonicalize(&path).unwrap_or(path.clone());
-                if let Some(current_path) = self.doc.path.clone() {
-                    self.backstack.push(HistoryEntry {
-                        path: current_path,
-                        scroll: self.scroll,
+                // Standard input cannot be read again, so leaving it is final.
+                if let Some(current_path) = self.tab.doc.path.clone() {
+                    self.tab.backstack.push(HistoryEntry {
+                        path: Some(current_path),
+                        scroll: self.tab.scroll,
+                        selected_link: self.tab.selected_link,
                     });
                 }
                 let source = fs::read_to_string(&canonical)
@@ -1007,8 +2649,11 @@ impl App {
                 );
                 self.ensure_watcher()?;
                 self.status = format!("Opened {}", canonical.display());
+                if let Some(fragment) = fragment {
+                    self.jump_to_anchor(&fragment);
+                }
             }
-            (true, LinkAction::InternalMarkdown(path)) => {
+            (true, LinkAction::InternalMarkdown(path, _)) => {
                 system_open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
                 self.status = format!("Opened {}", path.display());
             }
@@ -1029,31 +2674,236 @@ impl App {
         Ok(())
     }
 
+    fn jump_to_anchor(&mut self, fragment: &str) {
+        if let Some(line) = self.tab.doc.rendered.anchor_line(fragment) {
+            self.set_scroll_to_line(line);
+            self.status = format!("Jumped to #{fragment}");
+        } else {
+            self.status = format!("Anchor not found: #{fragment}");
+        }
+    }
+
     fn go_back(&mut self) -> Result<()> {
-        let Some(entry) = self.backstack.pop() else {
+        let Some(entry) = self.tab.backstack.pop() else {
             self.status = "Backstack is empty".to_string();
             return Ok(());
         };
 
-        let source = fs::read_to_string(&entry.path)
-            .with_context(|| format!("Failed to open {}", entry.path.display()))?;
+        // Anchor and footnote jumps stay in the same document; keep its
+        // timeline instead of reloading it.
+        if self.tab.doc.path == entry.path {
+            self.set_scroll_and_sync(entry.scroll);
+            self.restore_selected_link(entry.selected_link);
+            self.status = format!(
+                "Returned to line {}",
+                usize::from(entry.scroll).saturating_add(1)
+            );
+            return Ok(());
+        }
+        let Some(path) = entry.path else {
+            self.status = "Standard input cannot be reopened".to_string();
+            return Ok(());
+        };
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
         self.set_doc(
             LoadResult {
-                path: Some(entry.path.clone()),
+                path: Some(path.clone()),
                 source,
             },
             false,
         );
         self.set_scroll_and_sync(entry.scroll);
+        self.restore_selected_link(entry.selected_link);
         self.ensure_watcher()?;
-        self.status = format!("Returned to {}", entry.path.display());
+        self.status = format!("Returned to {}", path.display());
         Ok(())
     }
 
+    fn restore_selected_link(&mut self, selected: Option<usize>) {
+        if selected.is_some_and(|idx| idx < self.tab.doc.rendered.links.len()) {
+            self.tab.selected_link = selected;
+        }
+    }
+
+    fn tab_count(&self) -> usize {
+        self.tabs.len().saturating_add(1)
+    }
+
+    /// Every tab's document in tab bar order, with whether a background
+    /// tab recorded a revision that has not been shown yet.
+    fn tab_docs(&self) -> Vec<(&LoadedDocument, bool)> {
+        let mut docs: Vec<(&LoadedDocument, bool)> = self
+            .tabs
+            .iter()
+            .map(|tab| (&tab.doc, tab.unseen_revision))
+            .collect();
+        docs.insert(self.active_tab, (&self.tab.doc, false));
+        docs
+    }
+
+    /// Shows `tab` and returns the tab it replaces. `tab` is re-rendered if
+    /// the layout changed while it was parked.
+    fn swap_tab(&mut self, mut tab: Tab) -> Tab {
+        let layout = &self.tab.render_options;
+        let stale = tab.render_options.width != layout.width
+            || tab.render_options.show_comments != layout.show_comments;
+        tab.render_options.width = layout.width;
+        tab.render_options.show_comments = layout.show_comments;
+        tab.unseen_revision = false;
+        let previous = mem::replace(&mut self.tab, tab);
+        self.pending_task_delete = None;
+        if stale {
+            self.rerender();
+        } else {
+            self.refresh_agent_tasks();
+        }
+        if self.callouts_open && self.tab.doc.rendered.callouts.is_empty() {
+            self.callouts_open = false;
+        }
+        previous
+    }
+
+    /// Opens `load` in a new tab right after the current one and shows it.
+    fn open_tab(&mut self, load: LoadResult) -> Result<()> {
+        let mut render_options = self.tab.render_options.clone();
+        render_options.expanded_details.clear();
+        let current = mem::replace(
+            &mut self.tab,
+            Tab::new(LoadedDocument::default(), render_options),
+        );
+        self.tabs.insert(self.active_tab, current);
+        self.active_tab = self.active_tab.saturating_add(1);
+        self.pending_task_delete = None;
+        self.set_doc(load, false);
+        self.ensure_watcher()
+    }
+
+    /// Shows the tab at `index` (0-based, in tab bar order).
+    fn switch_tab(&mut self, index: usize) {
+        let active = self.active_tab;
+        if index == active || index >= self.tab_count() {
+            return;
+        }
+        // `tabs` skips the shown tab, so positions past it shift by one.
+        let next = self
+            .tabs
+            .remove(if index < active { index } else { index - 1 });
+        let current = self.swap_tab(next);
+        self.tabs
+            .insert(if active < index { active } else { active - 1 }, current);
+        self.active_tab = index;
+        self.status = format!(
+            "Tab {}/{}: {}",
+            index.saturating_add(1),
+            self.tab_count(),
+            self.tab.doc.title()
+        );
+    }
+
+    fn switch_tab_relative(&mut self, reverse: bool) {
+        let count = self.tab_count();
+        if count == 1 {
+            self.status = "Only one tab open".to_string();
+            return;
+        }
+        let index = if reverse {
+            self.active_tab.checked_sub(1).unwrap_or(count - 1)
+        } else {
+            self.active_tab.saturating_add(1) % count
+        };
+        self.switch_tab(index);
+    }
+
+    fn close_tab(&mut self) {
+        if self.tabs.is_empty() {
+            self.status = "Last tab; press q to quit".to_string();
+            return;
+        }
+        let closed = self.tab.doc.title();
+        let index = self.active_tab.min(self.tabs.len() - 1);
+        let next = self.tabs.remove(index);
+        self.active_tab = index;
+        self.swap_tab(next);
+        self.status = format!("Closed {closed}");
+    }
+
+    /// Opens the selected link in a new tab. Markdown files and anchors get
+    /// a tab of their own; anything else opens as with `Enter`.
+    fn open_selected_link_in_tab(&mut self) -> Result<()> {
+        let Some(link) = self
+            .tab
+            .selected_link
+            .and_then(|idx| self.tab.doc.rendered.links.get(idx))
+            .cloned()
+        else {
+            return self.open_selected_link(false);
+        };
+        let (path, fragment) = match classify_link(&link.target, self.tab.doc.path.as_deref()) {
+            LinkAction::InternalMarkdown(path, fragment) => {
+                (fs::canonicalize(&path).unwrap_or(path), fragment)
+            }
+            LinkAction::Anchor(fragment) => match self.tab.doc.path.clone() {
+                Some(path) => (path, Some(fragment)),
+                None => return self.open_selected_link(false),
+            },
+            _ => return self.open_selected_link(false),
+        };
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        self.open_tab(LoadResult {
+            path: Some(path.clone()),
+            source,
+        })?;
+        self.status = format!(
+            "Opened {} in tab {}",
+            path.display(),
+            self.active_tab.saturating_add(1)
+        );
+        if let Some(fragment) = fragment {
+            self.jump_to_anchor(&fragment);
+        }
+        Ok(())
+    }
+
+    fn draw_tab_bar(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let palette = self.palette();
+        let mut spans = Vec::new();
+        for (idx, (doc, changed)) in self.tab_docs().into_iter().enumerate() {
+            let label = format!(
+                " {}:{}{} ",
+                idx.saturating_add(1),
+                doc.title(),
+                if changed { " •" } else { "" }
+            );
+            let style = if idx == self.active_tab {
+                Style::default()
+                    .fg(palette.selection_fg)
+                    .bg(palette.selection_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else if changed {
+                Style::default().fg(palette.changed)
+            } else {
+                Style::default().fg(palette.subtle)
+            };
+            spans.push(Span::styled(label, style));
+            spans.push(Span::raw(" "));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
     fn draw(&mut self, frame: &mut ratatui::Frame<'_>) {
         let root = inset_rect(frame.size(), 1, 0);
+        let root = if self.tabs.is_empty() || root.height < 4 {
+            root
+        } else {
+            let rows = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(root);
+            self.draw_tab_bar(frame, rows[0]);
+            rows[1]
+        };
         let max_dock_height = root.height.saturating_sub(3);
-        let (body, timeline_area, status) = if self.cli.watch
+        let (body, timeline_area, status) = if self.timeline_enabled()
             && self.timeline_open
             && max_dock_height >= TIMELINE_MIN_HEIGHT
             && root.height >= 5
@@ -1074,7 +2924,20 @@ impl App {
             (chunks[0], None, inset_rect(chunks[1], 1, 0))
         };
 
-        let content_area = if self.toc_open || self.agent_inbox_open {
+        let body = if self.file_tree.is_some() && self.file_tree_open {
+            let cols = Layout::horizontal([
+                Constraint::Length(body.width.saturating_div(4).max(20)),
+                Constraint::Length(1),
+                Constraint::Min(1),
+            ])
+            .split(body);
+            self.draw_file_tree(frame, cols[0]);
+            cols[2]
+        } else {
+            body
+        };
+
+        let content_area = if self.toc_open || self.agent_inbox_open || self.callouts_open {
             let widths = [
                 Constraint::Length(body.width.saturating_div(3).max(24)),
                 Constraint::Length(1),
@@ -1083,6 +2946,8 @@ impl App {
             let cols = Layout::horizontal(widths).split(body);
             if self.agent_inbox_open {
                 self.draw_agent_inbox(frame, cols[0]);
+            } else if self.callouts_open {
+                self.draw_callouts(frame, cols[0]);
             } else {
                 self.draw_toc(frame, cols[0]);
             }
@@ -1091,9 +2956,34 @@ impl App {
             body
         };
 
+        let content_area = match self.tab.doc.rendered.front_matter.as_ref() {
+            Some(meta)
+                if self.metadata_open && !meta.fields.is_empty() && content_area.height >= 12 =>
+            {
+                let height = usize_to_u16_saturating(meta.fields.len().saturating_add(1))
+                    .min(content_area.height / 3);
+                let rows = Layout::vertical([Constraint::Length(height), Constraint::Min(1)])
+                    .split(content_area);
+                self.draw_metadata(frame, rows[0]);
+                rows[1]
+            }
+            _ => content_area,
+        };
+
+        let text_width = content_text_width(content_area.width);
+        if self.tab.render_options.width != Some(text_width) {
+            self.relayout(text_width);
+        }
+        if timeline_area.is_some() {
+            self.refresh_snapshots();
+        }
         self.viewport_height = content_area.height.saturating_sub(1).max(1);
         self.clamp_scroll();
-        self.draw_content(frame, content_area);
+        if let Some(view) = &self.tab.diff_view {
+            Self::draw_diff_view(view, self.palette(), frame, content_area);
+        } else {
+            self.draw_content(frame, content_area);
+        }
         if let Some(area) = timeline_area {
             self.draw_timeline(frame, area);
         }
@@ -1104,12 +2994,16 @@ impl App {
         if self.quick_task_mode {
             self.draw_quick_task_capture(frame);
         }
+        if let Some(palette) = &self.command_palette {
+            self.draw_command_palette(palette, frame);
+        }
     }
 
     fn draw_toc(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
         let selected = self
+            .tab
             .toc_selected
-            .min(self.doc.rendered.toc.len().saturating_sub(1));
+            .min(self.tab.doc.rendered.toc.len().saturating_sub(1));
         let (active_diff, freshness) = if let Some(snapshot) = self.current_snapshot() {
             (
                 Some(&snapshot.diff),
@@ -1120,6 +3014,7 @@ impl App {
         };
 
         let items: Vec<ListItem> = self
+            .tab
             .doc
             .rendered
             .toc
@@ -1129,7 +3024,7 @@ impl App {
                 let indent = "  ".repeat(entry.level.saturating_sub(1) as usize);
                 let row_style = if idx == selected {
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(self.palette().highlight)
                         .add_modifier(Modifier::BOLD)
                 } else {
                     Style::default()
@@ -1144,9 +3039,9 @@ impl App {
                 let change_marker = if section_delta.is_some() && freshness.is_some() {
                     let marker_style = match freshness {
                         Some(ChangeFreshness::Bright) => Style::default()
-                            .fg(Color::LightRed)
+                            .fg(self.palette().changed)
                             .add_modifier(Modifier::BOLD),
-                        Some(ChangeFreshness::Dim) => Style::default().fg(Color::DarkGray),
+                        Some(ChangeFreshness::Dim) => Style::default().fg(self.palette().muted),
                         None => Style::default(),
                     };
                     Span::styled("● ", marker_style)
@@ -1172,34 +3067,93 @@ impl App {
             Block::default()
                 .title(" TOC ")
                 .borders(Borders::TOP)
-                .border_style(Style::default().fg(Color::DarkGray))
+                .border_style(Style::default().fg(self.palette().muted))
                 .padding(Padding::new(1, 1, 0, 0)),
         );
 
         frame.render_widget(toc, area);
     }
 
+    fn draw_metadata(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let Some(meta) = self.tab.doc.rendered.front_matter.as_ref() else {
+            return;
+        };
+        let key_width = meta
+            .fields
+            .iter()
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Line> = meta
+            .fields
+            .iter()
+            .map(|(key, values)| {
+                let value_style = match key.to_ascii_lowercase().as_str() {
+                    "title" => Style::default().add_modifier(Modifier::BOLD),
+                    "status" | "state" => Style::default().fg(self.palette().highlight),
+                    "owner" | "owners" | "author" => Style::default().fg(self.palette().accent),
+                    "tags" | "tag" | "labels" => Style::default().fg(self.palette().badge),
+                    _ => Style::default(),
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{key:<key_width$}  "),
+                        Style::default().fg(self.palette().muted),
+                    ),
+                    Span::styled(values.join(", "), value_style),
+                ])
+            })
+            .collect();
+
+        let paragraph = Paragraph::new(Text::from(lines)).block(
+            Block::default()
+                .title(" metadata ")
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(self.palette().muted))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+        frame.render_widget(paragraph, area);
+    }
+
     fn draw_agent_inbox(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let mut view = Vec::new();
+        if self.agent_sort != AgentSort::Document {
+            view.push(format!("by {}", self.agent_sort.label()));
+        }
+        if let Some(owner) = &self.agent_owner_filter {
+            view.push(format!("@{owner}"));
+        }
+        let title = if view.is_empty() {
+            " Agent Inbox ".to_string()
+        } else {
+            format!(" Agent Inbox ({}) ", view.join(", "))
+        };
         let block = Block::default()
-            .title(" Agent Inbox ")
+            .title(title)
             .borders(Borders::TOP)
-            .border_style(Style::default().fg(Color::DarkGray))
+            .border_style(Style::default().fg(self.palette().muted))
             .padding(Padding::new(1, 1, 0, 0));
 
-        if self.agent_tasks.is_empty() {
+        if self.tab.agent_tasks.is_empty() {
             frame.render_widget(
                 Paragraph::new(format!(" {NO_AGENT_TASKS_STATUS}"))
-                    .style(Style::default().fg(Color::Gray))
+                    .style(Style::default().fg(self.palette().subtle))
                     .block(block),
                 area,
             );
             return;
         }
 
-        if self.open_agent_tasks.is_empty() {
+        if self.tab.open_agent_tasks.is_empty() {
+            let message = match &self.agent_owner_filter {
+                Some(owner) if self.unresolved_agent_task_count() > 0 => {
+                    format!("No open agent tasks for @{owner}")
+                }
+                _ => NO_OPEN_AGENT_TASKS_STATUS.to_string(),
+            };
             frame.render_widget(
-                Paragraph::new(format!(" {NO_OPEN_AGENT_TASKS_STATUS}"))
-                    .style(Style::default().fg(Color::Gray))
+                Paragraph::new(format!(" {message}"))
+                    .style(Style::default().fg(self.palette().subtle))
                     .block(block),
                 area,
             );
@@ -1207,25 +3161,48 @@ impl App {
         }
 
         let selected = self
+            .tab
             .agent_selected
-            .min(self.open_agent_tasks.len().saturating_sub(1));
+            .min(self.tab.open_agent_tasks.len().saturating_sub(1));
         let items: Vec<ListItem> = self
+            .tab
             .open_agent_tasks
             .iter()
             .enumerate()
             .filter_map(|(position, task_index)| {
-                let task = self.agent_tasks.get(*task_index)?;
-                let row = format!("{:>4}  {}", task.line + 1, truncate_label(&task.text, 44));
-                let line = if position == selected {
-                    Line::styled(
-                        row,
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    )
+                let task = self.tab.agent_tasks.get(*task_index)?;
+                let line_number = task.source_line().unwrap_or(task.line).saturating_add(1);
+                let state = if task.state == AgentTaskState::InProgress {
+                    "~"
                 } else {
-                    Line::raw(row)
+                    " "
+                };
+                let badges = task.badges();
+                let summary = task.summary();
+                let text = if summary.is_empty() {
+                    &task.text
+                } else {
+                    &summary
+                };
+                let row_style = if position == selected {
+                    Style::default()
+                        .fg(self.palette().highlight)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
                 };
+                let mut spans = vec![Span::styled(
+                    format!("{line_number:>4} {state} "),
+                    row_style,
+                )];
+                if !badges.is_empty() {
+                    spans.push(Span::styled(
+                        format!("{badges} "),
+                        row_style.fg(self.palette().badge),
+                    ));
+                }
+                spans.push(Span::styled(truncate_label(text, 44), row_style));
+                let line = Line::from(spans);
                 Some(ListItem::new(line))
             })
             .collect();
@@ -1234,23 +3211,70 @@ impl App {
         frame.render_widget(list, area);
     }
 
+    fn draw_callouts(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let callouts = &self.tab.doc.rendered.callouts;
+        let selected = self
+            .tab
+            .callout_selected
+            .min(callouts.len().saturating_sub(1));
+        let items: Vec<ListItem> = callouts
+            .iter()
+            .enumerate()
+            .map(|(idx, callout)| {
+                let row_style = if idx == selected {
+                    Style::default()
+                        .fg(self.palette().highlight)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(if idx == selected { "> " } else { "  " }, row_style),
+                    Span::styled(
+                        format!("{} {:<9} ", callout.kind.icon(), callout.kind.label()),
+                        Style::default().fg(callout.kind.color(self.palette())),
+                    ),
+                    Span::styled(truncate_label(&callout.summary, 40), row_style),
+                ]))
+            })
+            .collect();
+
+        let list = if items.is_empty() {
+            List::new(vec![ListItem::new(Line::raw(format!(
+                "  {NO_CALLOUTS_STATUS}"
+            )))])
+        } else {
+            List::new(items)
+        }
+        .block(
+            Block::default()
+                .title(" Callouts ")
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(self.palette().muted))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+        frame.render_widget(list, area);
+    }
+
     fn draw_timeline(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
-        if self.snapshots.len() <= 1 {
+        if self.tab.snapshots.len() <= 1 {
             let empty = Paragraph::new(" No prior revisions yet")
                 .block(
                     Block::default()
                         .title(" Timeline ")
                         .borders(Borders::TOP)
-                        .border_style(Style::default().fg(Color::DarkGray))
+                        .border_style(Style::default().fg(self.palette().muted))
                         .padding(Padding::new(1, 1, 0, 0)),
                 )
-                .style(Style::default().fg(Color::Gray));
+                .style(Style::default().fg(self.palette().subtle));
             frame.render_widget(empty, area);
             return;
         }
 
         let latest = self.latest_snapshot_index();
+        let today = format_date_ymd(SystemTime::now());
         let items: Vec<ListItem> = self
+            .tab
             .snapshots
             .iter()
             .enumerate()
@@ -1262,10 +3286,16 @@ impl App {
                     .as_ref()
                     .map(|value| truncate_label(value, 32))
                     .unwrap_or_else(|| "-".to_string());
+                let date = format_date_ymd(snapshot.created_at);
+                let when = if date == today {
+                    format_clock_hms(snapshot.created_at)
+                } else {
+                    format!("{date} {}", format_clock_hms(snapshot.created_at))
+                };
                 let row = format!(
-                    "r{:03}  {}  +{}/-{}  h:{}  top:{}{}",
-                    snapshot.revision,
-                    format_clock_hms(snapshot.created_at),
+                    "{}  {}  +{}/-{}  h:{}  top:{}{}",
+                    snapshot.name(),
+                    when,
                     snapshot.diff.added,
                     snapshot.diff.removed,
                     snapshot.diff.section_deltas.len(),
@@ -1276,13 +3306,13 @@ impl App {
                         ""
                     }
                 );
-                let line = if idx == self.active_snapshot {
+                let line = if idx == self.tab.active_snapshot {
                     Line::styled(
                         row,
                         Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
                     )
                 } else if idx == latest {
-                    Line::styled(row, Style::default().fg(Color::Cyan))
+                    Line::styled(row, Style::default().fg(self.palette().accent))
                 } else {
                     Line::raw(row)
                 };
@@ -1292,9 +3322,9 @@ impl App {
 
         let list = List::new(items).block(
             Block::default()
-                .title(" Timeline ")
+                .title(" Timeline (UTC) ")
                 .borders(Borders::TOP)
-                .border_style(Style::default().fg(Color::DarkGray))
+                .border_style(Style::default().fg(self.palette().muted))
                 .padding(Padding::new(1, 1, 0, 0)),
         );
 
@@ -1304,17 +3334,24 @@ impl App {
     fn draw_content(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
         let selected_link_line = self.selected_link_line();
         let selected_open_agent_line = self.selected_open_agent_line();
-        let total_lines = self.doc.rendered.lines.len();
+        let total_lines = self.tab.doc.rendered.lines.len();
         let mut changed_lines = vec![false; total_lines];
         let mut hunk_anchors = vec![false; total_lines];
         let mut agent_states = vec![None; total_lines];
         let freshness = self
             .current_snapshot()
             .and_then(|snapshot| change_freshness(snapshot.created_instant));
-
-        for task in &self.agent_tasks {
-            if task.line < total_lines {
-                agent_states[task.line] = Some(task.state);
+        let word_changes = self
+            .current_snapshot()
+            .map(|snapshot| &snapshot.diff.word_changes);
+
+        for task in &self.tab.agent_tasks {
+            let lines = &self.tab.doc.rendered.lines;
+            let end = (task.line + 1..total_lines)
+                .find(|idx| !lines[*idx].wrapped)
+                .unwrap_or(total_lines);
+            for state in agent_states.iter_mut().take(end).skip(task.line) {
+                *state = Some(task.state);
             }
         }
 
@@ -1328,10 +3365,9 @@ impl App {
 
                 if freshness.is_some() {
                     if hunk.end_line > hunk.start_line {
-                        for line in hunk.start_line.min(total_lines)..hunk.end_line.min(total_lines)
-                        {
-                            changed_lines[line] = true;
-                        }
+                        let range =
+                            hunk.start_line.min(total_lines)..hunk.end_line.min(total_lines);
+                        changed_lines[range].fill(true);
                     } else {
                         changed_lines[anchor] = true;
                     }
@@ -1339,14 +3375,19 @@ impl App {
             }
         }
 
-        let lines: Vec<Line> = self
-            .doc
-            .rendered
+        // Bodies of collapsed `<details>` blocks stay rendered for the TOC,
+        // inbox and search, but take no room on screen.
+        let rendered = &self.tab.doc.rendered;
+        let scroll = (0..usize::from(self.tab.scroll))
+            .filter(|idx| !rendered.is_hidden(*idx))
+            .count();
+        let lines: Vec<Line> = rendered
             .lines
             .iter()
             .enumerate()
+            .filter(|(idx, _)| !rendered.is_hidden(*idx))
             .map(|(idx, line)| {
-                let is_match = self.search_matches.binary_search(&idx).is_ok();
+                let (match_ranges, current_match) = self.search_ranges_on_line(idx);
                 let is_selected_link_line = selected_link_line == Some(idx);
                 let is_selected_agent_line = selected_open_agent_line == Some(idx);
                 let is_changed = changed_lines.get(idx).copied().unwrap_or(false);
@@ -1355,59 +3396,106 @@ impl App {
 
                 let base_marker_style = match freshness {
                     Some(ChangeFreshness::Bright) => Style::default()
-                        .fg(Color::LightRed)
+                        .fg(self.palette().changed)
                         .add_modifier(Modifier::BOLD),
-                    Some(ChangeFreshness::Dim) => Style::default().fg(Color::Gray),
-                    None => Style::default().fg(Color::LightBlue),
+                    Some(ChangeFreshness::Dim) => Style::default().fg(self.palette().subtle),
+                    None => Style::default().fg(self.palette().info),
                 };
                 let marker_span = if is_hunk_anchor {
                     Span::styled("▌ ", base_marker_style)
-                } else if let Some(state) = agent_state {
+                } else if let Some(state) = agent_state.filter(|_| !line.wrapped) {
                     match state {
                         AgentTaskState::Open => Span::styled(
                             "@ ",
                             Style::default()
-                                .fg(Color::Cyan)
+                                .fg(self.palette().accent)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        AgentTaskState::InProgress => Span::styled(
+                            "~ ",
+                            Style::default()
+                                .fg(self.palette().highlight)
                                 .add_modifier(Modifier::BOLD),
                         ),
                         AgentTaskState::Done => {
-                            Span::styled("@ ", Style::default().fg(Color::DarkGray))
+                            Span::styled("@ ", Style::default().fg(self.palette().muted))
                         }
                     }
                 } else {
                     Span::styled("  ", Style::default())
                 };
 
+                let word_ranges = word_changes
+                    .and_then(|changes| changes.get(&idx))
+                    .filter(|_| is_changed);
+
                 let mut spans = vec![marker_span];
                 if line.segments.is_empty() {
                     spans.push(Span::raw(""));
                 } else {
-                    spans.extend(line.segments.iter().map(|segment| {
-                        let mut style = segment.style;
-                        if let Some(state) = agent_state {
-                            style = match state {
-                                AgentTaskState::Open => style.bg(Color::Rgb(16, 52, 44)),
-                                AgentTaskState::Done => style.fg(Color::DarkGray),
-                            };
-                        }
-                        if is_changed {
-                            style = match freshness {
-                                Some(ChangeFreshness::Bright) => style.bg(Color::Rgb(70, 35, 0)),
-                                Some(ChangeFreshness::Dim) => style.bg(Color::Rgb(36, 36, 36)),
-                                None => style,
-                            };
-                        }
-                        if is_match {
-                            style = style.bg(Color::Rgb(40, 40, 40));
-                        }
-                        if is_selected_link_line {
-                            style = style.bg(Color::Blue).fg(Color::White);
-                        }
-                        if is_selected_agent_line && !is_selected_link_line {
-                            style = style.add_modifier(Modifier::BOLD);
-                        }
-                        Span::styled(segment.text.clone(), style)
-                    }));
+                    let mut offset = 0usize;
+                    for segment in &line.segments {
+                        let pieces = match word_ranges {
+                            Some(ranges) => split_by_ranges(&segment.text, offset, ranges),
+                            None => vec![(segment.text.as_str(), is_changed)],
+                        };
+                        let mut piece_offset = offset;
+                        offset = offset.saturating_add(segment.text.len());
+                        let pieces = pieces.into_iter().flat_map(|(text, piece_changed)| {
+                            let start = piece_offset;
+                            piece_offset = piece_offset.saturating_add(text.len());
+                            let mut match_offset = start;
+                            split_by_ranges(text, start, &match_ranges).into_iter().map(
+                                move |(text, is_match)| {
+                                    let range = match_offset..match_offset + text.len();
+                                    match_offset = range.end;
+                                    (text, piece_changed, is_match, range)
+                                },
+                            )
+                        });
+                        spans.extend(pieces.map(|(text, piece_changed, is_match, range)| {
+                            let mut style = segment.style;
+                            if let Some(state) = agent_state {
+                                style = match state {
+                                    AgentTaskState::Open => style.bg(self.palette().task_open_bg),
+                                    AgentTaskState::InProgress => {
+                                        style.bg(self.palette().task_in_progress_bg)
+                                    }
+                                    AgentTaskState::Done => style.fg(self.palette().muted),
+                                };
+                            }
+                            if piece_changed {
+                                style = match freshness {
+                                    Some(ChangeFreshness::Bright) => {
+                                        style.bg(self.palette().changed_bg)
+                                    }
+                                    Some(ChangeFreshness::Dim) => {
+                                        style.bg(self.palette().changed_dim_bg)
+                                    }
+                                    None => style,
+                                };
+                            }
+                            if is_selected_link_line {
+                                style = style
+                                    .bg(self.palette().selection_bg)
+                                    .fg(self.palette().selection_fg);
+                            }
+                            if is_match {
+                                let is_current = current_match.as_ref().is_some_and(|current| {
+                                    current.start <= range.start && range.end <= current.end
+                                });
+                                style = style.fg(self.palette().search_fg).bg(if is_current {
+                                    self.palette().search_current_bg
+                                } else {
+                                    self.palette().search_bg
+                                });
+                            }
+                            if is_selected_agent_line && !is_selected_link_line {
+                                style = style.add_modifier(Modifier::BOLD);
+                            }
+                            Span::styled(text.to_string(), style)
+                        }));
+                    }
                 }
                 Line::from(spans)
             })
@@ -1418,30 +3506,133 @@ impl App {
                 Block::default()
                     .title(" catmd ")
                     .borders(Borders::TOP)
-                    .border_style(Style::default().fg(Color::DarkGray))
+                    .border_style(Style::default().fg(self.palette().muted))
                     .padding(Padding::new(1, 1, 0, 0)),
             )
-            .scroll((self.scroll, 0))
-            .wrap(Wrap { trim: false });
+            .scroll((usize_to_u16_saturating(scroll), 0));
 
         frame.render_widget(paragraph, area);
     }
 
+    fn diff_row_line(
+        palette: &Palette,
+        kind: DiffRowKind,
+        text: Option<&str>,
+        show_sign: bool,
+    ) -> Line<'static> {
+        let (sign, style) = match (kind, text.is_some()) {
+            (_, false) => (" ", Style::default()),
+            (DiffRowKind::Context, true) => (" ", Style::default().fg(palette.subtle)),
+            (DiffRowKind::Removed, true) => (
+                "-",
+                Style::default().fg(palette.removed).bg(palette.removed_bg),
+            ),
+            (DiffRowKind::Added, true) => {
+                ("+", Style::default().fg(palette.added).bg(palette.added_bg))
+            }
+            (DiffRowKind::Changed, true) => ("~", Style::default().fg(palette.highlight)),
+        };
+        let text = text.unwrap_or_default().to_string();
+        if show_sign {
+            Line::from(vec![
+                Span::styled(format!("{sign} "), style),
+                Span::styled(text, style),
+            ])
+        } else {
+            Line::styled(text, style)
+        }
+    }
+
+    fn draw_diff_view(
+        view: &DiffView,
+        palette: &Palette,
+        frame: &mut ratatui::Frame<'_>,
+        area: Rect,
+    ) {
+        let block = |title: String| {
+            Block::default()
+                .title(title)
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(palette.muted))
+                .padding(Padding::new(1, 1, 0, 0))
+        };
+        let summary = format!("+{}/-{}, {}", view.added, view.removed, view.layout.label());
+
+        match view.layout {
+            DiffLayout::Inline => {
+                let lines: Vec<Line> = view
+                    .inline
+                    .iter()
+                    .map(|row| {
+                        let text = row.new.as_deref().or(row.old.as_deref());
+                        Self::diff_row_line(palette, row.kind, text, true)
+                    })
+                    .collect();
+                let title = format!(" Diff {} -> {} ({summary}) ", view.base, view.target);
+                frame.render_widget(
+                    Paragraph::new(Text::from(lines))
+                        .block(block(title))
+                        .scroll((view.scroll, 0)),
+                    area,
+                );
+            }
+            DiffLayout::SideBySide => {
+                let cols = Layout::horizontal([
+                    Constraint::Percentage(50),
+                    Constraint::Length(1),
+                    Constraint::Percentage(50),
+                ])
+                .split(area);
+                let side = |new: bool| -> Vec<Line> {
+                    view.rows
+                        .iter()
+                        .map(|row| {
+                            let (kind, text) = match (row.kind, new) {
+                                (DiffRowKind::Changed, false) => {
+                                    (DiffRowKind::Removed, row.old.as_deref())
+                                }
+                                (DiffRowKind::Changed, true) => {
+                                    (DiffRowKind::Added, row.new.as_deref())
+                                }
+                                (kind, false) => (kind, row.old.as_deref()),
+                                (kind, true) => (kind, row.new.as_deref()),
+                            };
+                            Self::diff_row_line(palette, kind, text, false)
+                        })
+                        .collect()
+                };
+                frame.render_widget(
+                    Paragraph::new(Text::from(side(false)))
+                        .block(block(format!(" {} ", view.base)))
+                        .scroll((view.scroll, 0)),
+                    cols[0],
+                );
+                frame.render_widget(
+                    Paragraph::new(Text::from(side(true)))
+                        .block(block(format!(" {} ({summary}) ", view.target)))
+                        .scroll((view.scroll, 0)),
+                    cols[2],
+                );
+            }
+        }
+    }
+
     fn draw_status(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
         let path = self
+            .tab
             .doc
             .path
             .as_ref()
             .map_or_else(|| "<stdin>".to_string(), |p| p.display().to_string());
 
-        let link_hint = if let Some(idx) = self.selected_link {
-            self.doc.rendered.links.get(idx).map_or_else(
+        let link_hint = if let Some(idx) = self.tab.selected_link {
+            self.tab.doc.rendered.links.get(idx).map_or_else(
                 || "link: none".to_string(),
                 |link| {
                     format!(
                         "link[{}/{}]: {}",
                         idx + 1,
-                        self.doc.rendered.links.len(),
+                        self.tab.doc.rendered.links.len(),
                         link.label
                     )
                 },
@@ -1450,21 +3641,54 @@ impl App {
             "link: none".to_string()
         };
 
-        let search_hint = if self.search_mode {
-            format!(" /{}", self.search_query)
-        } else if self.search_query.is_empty() {
+        let search_hint = if self.tab.search_query.is_empty() && !self.search_mode {
             String::new()
         } else {
-            format!(
-                " search='{}' {}/{}",
-                self.search_query,
-                if self.search_matches.is_empty() {
-                    0
-                } else {
-                    self.current_match + 1
-                },
-                self.search_matches.len()
-            )
+            let mut modes = Vec::new();
+            if self.search_flags.regex
+                || self.tab.search_pattern.as_ref().is_some_and(|p| p.is_regex)
+            {
+                modes.push("regex");
+            }
+            if self.search_flags.whole_word {
+                modes.push("word");
+            }
+            if self
+                .tab
+                .search_pattern
+                .as_ref()
+                .is_some_and(|p| p.case_sensitive)
+            {
+                modes.push("case");
+            }
+            let modes = if modes.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", modes.join(","))
+            };
+            let counter = if let Some(err) = &self.tab.search_error {
+                format!(
+                    " invalid regex: {}",
+                    truncate_label(err.lines().last().unwrap_or(""), 48)
+                )
+            } else if self.tab.search_query.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " {}/{}",
+                    if self.tab.search_matches.is_empty() {
+                        0
+                    } else {
+                        self.tab.current_match + 1
+                    },
+                    self.tab.search_matches.len()
+                )
+            };
+            if self.search_mode {
+                format!(" /{}{modes}{counter}", self.tab.search_query)
+            } else {
+                format!(" search='{}'{modes}{counter}", self.tab.search_query)
+            }
         };
         let quick_task_hint = if self.quick_task_mode {
             if self.quick_task_input.is_empty() {
@@ -1476,23 +3700,24 @@ impl App {
             String::new()
         };
 
-        let mode_hint = if self.cli.watch {
+        let mode_hint = if self.timeline_enabled() {
             if let Some(snapshot) = self.current_snapshot() {
                 let behind = self
                     .latest_snapshot_index()
-                    .saturating_sub(self.active_snapshot);
+                    .saturating_sub(self.tab.active_snapshot);
                 if behind == 0 {
                     format!(
-                        "LIVE r{:03} | +{}/-{} | sections:{} | watch:on",
-                        snapshot.revision,
+                        "LIVE {} | +{}/-{} | sections:{}{}",
+                        snapshot.name(),
                         snapshot.diff.added,
                         snapshot.diff.removed,
-                        snapshot.diff.section_deltas.len()
+                        snapshot.diff.section_deltas.len(),
+                        if self.cli.watch { " | watch:on" } else { "" }
                     )
                 } else {
                     format!(
-                        "HISTORY r{:03} ({behind} behind LIVE) | +{}/-{} | hunks:{}",
-                        snapshot.revision,
+                        "HISTORY {} ({behind} behind LIVE) | +{}/-{} | hunks:{}",
+                        snapshot.name(),
                         snapshot.diff.added,
                         snapshot.diff.removed,
                         snapshot.diff.hunks.len()
@@ -1501,16 +3726,65 @@ impl App {
             } else {
                 "watch:on".to_string()
             }
+        } else if let (Some(base), Some(snapshot)) = (
+            self.tab
+                .snapshots
+                .front()
+                .and_then(|base| base.label.as_ref()),
+            self.current_snapshot(),
+        ) {
+            format!(
+                "since {base} | +{}/-{} | hunks:{}",
+                snapshot.diff.added,
+                snapshot.diff.removed,
+                snapshot.diff.hunks.len()
+            )
         } else {
             String::new()
         };
-        let agent_hint = if self.agent_tasks.is_empty() {
+        let meta_hint = self
+            .tab
+            .doc
+            .rendered
+            .front_matter
+            .as_ref()
+            .map(|meta| {
+                let mut hint = meta
+                    .title()
+                    .map(|title| truncate_label(title, 32))
+                    .unwrap_or_default();
+                if let Some(status) = meta.status() {
+                    hint.push_str(&format!(" [{status}]"));
+                }
+                for owner in meta.owners() {
+                    hint.push_str(&format!(" @{owner}"));
+                }
+                for tag in meta.tags() {
+                    hint.push_str(&format!(" #{tag}"));
+                }
+                hint.trim().to_string()
+            })
+            .unwrap_or_default();
+        let important = self
+            .tab
+            .doc
+            .rendered
+            .callouts
+            .iter()
+            .filter(|callout| callout.kind == CalloutKind::Important)
+            .count();
+        let callout_hint = if important == 0 {
+            String::new()
+        } else {
+            format!("important: {important}")
+        };
+        let agent_hint = if self.tab.agent_tasks.is_empty() {
             String::new()
         } else {
             format!(
                 "agent: {}/{} open",
-                self.open_agent_tasks.len(),
-                self.agent_tasks.len()
+                self.unresolved_agent_task_count(),
+                self.tab.agent_tasks.len()
             )
         };
 
@@ -1521,87 +3795,147 @@ impl App {
         if !mode_hint.is_empty() {
             parts.push(mode_hint);
         }
+        if !meta_hint.is_empty() {
+            parts.push(meta_hint);
+        }
+        if !callout_hint.is_empty() {
+            parts.push(callout_hint);
+        }
         if !agent_hint.is_empty() {
             parts.push(agent_hint);
         }
         parts.push(path);
-        parts.push(format!("{link_hint}{search_hint}{quick_task_hint}"));
+        let keys_hint = if self.pending_keys.is_empty() {
+            String::new()
+        } else {
+            format!(" keys='{}…'", sequence_label(&self.pending_keys))
+        };
+        parts.push(format!(
+            "{link_hint}{search_hint}{quick_task_hint}{keys_hint}"
+        ));
         let status_text = parts.join(" | ");
 
         frame.render_widget(
-            Paragraph::new(format!(" {status_text}")).style(Style::default().fg(Color::Gray)),
+            Paragraph::new(format!(" {status_text}"))
+                .style(Style::default().fg(self.palette().subtle)),
             area,
         );
     }
 
     fn draw_shortcuts_help(&self, frame: &mut ratatui::Frame<'_>) {
         let area = centered_rect(78, 88, frame.size());
-        let lines = vec![
-            Line::styled(
-                "General",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Line::raw("  q                Quit"),
-            Line::raw("  ?                Toggle this shortcuts view"),
-            Line::raw("  A or Ctrl-a      Add new @agent task (append to file)"),
-            Line::raw("  j / k            Scroll down / up"),
-            Line::raw("  Ctrl-d / Ctrl-u  Half-page down / up"),
-            Line::raw("  g / G            Top / bottom"),
-            Line::raw("  /                Search"),
-            Line::raw("  n / N            Next / previous match"),
-            Line::raw(""),
-            Line::styled(
-                "Navigation",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Line::raw("  t                Toggle TOC"),
-            Line::raw("  a                Toggle Agent Inbox"),
-            Line::raw("  [ / ]            Previous / next heading"),
-            Line::raw("  { / }            Previous / next unresolved @agent task"),
-            Line::raw("  Enter            Follow selected item/link"),
-            Line::raw("  Tab / Shift-Tab  Next / previous link"),
-            Line::raw("  o                Open selected link externally"),
-            Line::raw("  Backspace        Go back in local markdown history"),
-            Line::raw(""),
-            Line::styled(
-                "Watch Mode",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Line::raw("  v                Toggle timeline"),
-            Line::raw("  h / l            Older / newer revision"),
-            Line::raw("  L                Jump to live revision"),
-            Line::raw("  ( / )            Previous / next changed hunk"),
-            Line::raw(""),
-            Line::styled(
-                "Panels",
+        let sections = self.keymap.help();
+        let key_width = sections
+            .iter()
+            .flat_map(|(_, rows)| rows.iter().map(|(keys, _)| keys.chars().count()))
+            .max()
+            .unwrap_or(0);
+        let mut lines = Vec::new();
+        for (title, rows) in sections {
+            lines.push(Line::styled(
+                title,
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(self.palette().highlight)
                     .add_modifier(Modifier::BOLD),
+            ));
+            for (keys, text) in rows {
+                lines.push(Line::raw(format!("  {keys:<key_width$}  {text}")));
+            }
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::styled(
+            format!(
+                "Press {} (or Esc / {}) to close, {} / {} to scroll",
+                self.keymap.label(Action::Help),
+                self.keymap.label(Action::Quit),
+                self.keymap.label(Action::ScrollDown),
+                self.keymap.label(Action::ScrollUp),
             ),
-            Line::raw("  j / k            Move selection in TOC or Agent Inbox"),
-            Line::raw("  Enter            Jump to selected TOC heading or agent task"),
-            Line::raw(""),
-            Line::styled(
-                "Press '?' (or Esc / q) to close",
-                Style::default().fg(Color::Gray),
-            ),
-        ];
+            Style::default().fg(self.palette().subtle),
+        ));
 
         let panel = Paragraph::new(Text::from(lines))
             .block(
                 Block::default()
                     .title(" Keyboard Shortcuts ")
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan))
+                    .border_style(Style::default().fg(self.palette().accent))
                     .padding(Padding::new(1, 1, 0, 0)),
             )
-            .wrap(Wrap { trim: false });
+            .wrap(Wrap { trim: false })
+            .scroll((self.help_scroll, 0));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(panel, area);
+    }
+
+    fn draw_command_palette(&self, palette: &CommandPalette, frame: &mut ratatui::Frame<'_>) {
+        let area = centered_rect(74, 60, frame.size());
+        let inner_width = usize::from(area.width.saturating_sub(4));
+        // Input line plus a blank line above the matches.
+        let visible = usize::from(area.height.saturating_sub(4)).max(1);
+        let first = palette.selected.saturating_sub(visible.saturating_sub(1));
+
+        let mut lines = vec![
+            Line::styled(
+                format!("> {}", palette.query),
+                Style::default()
+                    .fg(self.palette().accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Line::raw(""),
+        ];
+        if palette.matches.is_empty() {
+            lines.push(Line::styled(
+                "No matches",
+                Style::default().fg(self.palette().subtle),
+            ));
+        }
+        for (row, found) in palette.matches.iter().enumerate().skip(first).take(visible) {
+            let Some(item) = palette.items.get(found.item) else {
+                continue;
+            };
+            let selected = row == palette.selected;
+            let base = if selected {
+                Style::default()
+                    .fg(self.palette().selection_fg)
+                    .bg(self.palette().selection_bg)
+            } else {
+                Style::default()
+            };
+            let kind = format!("{:<9}", item.target.kind());
+            let label = truncate_label(&item.label, inner_width.saturating_sub(30).max(12));
+            let mut spans = vec![Span::styled(kind, base.fg(self.palette().subtle))];
+            spans.extend(label.chars().enumerate().map(|(idx, ch)| {
+                let style = if found.positions.binary_search(&idx).is_ok() {
+                    base.fg(self.palette().highlight)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    base
+                };
+                Span::styled(ch.to_string(), style)
+            }));
+            if !item.detail.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", truncate_label(&item.detail, 40)),
+                    base.fg(self.palette().muted),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let panel = Paragraph::new(Text::from(lines)).block(
+            Block::default()
+                .title(format!(
+                    " Command Palette ({}/{}) ",
+                    palette.matches.len(),
+                    palette.items.len()
+                ))
+                .title_bottom(" Enter run · Up/Down select · Esc close ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.palette().accent))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
 
         frame.render_widget(Clear, area);
         frame.render_widget(panel, area);
@@ -1618,45 +3952,262 @@ impl App {
             Line::styled(
                 "Append unresolved @agent task",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(self.palette().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
             Line::raw(""),
             Line::styled(
                 format!("> {}", truncate_label(&entry, 120)),
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(self.palette().accent)
                     .add_modifier(Modifier::BOLD),
             ),
             Line::raw(""),
             Line::styled(
                 "Enter saves to file, Esc cancels",
-                Style::default().fg(Color::Gray),
+                Style::default().fg(self.palette().subtle),
             ),
         ];
 
-        let panel = Paragraph::new(Text::from(lines))
-            .block(
-                Block::default()
-                    .title(" New Agent Task ")
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan))
-                    .padding(Padding::new(1, 1, 0, 0)),
-            )
-            .wrap(Wrap { trim: false });
+        let panel = Paragraph::new(Text::from(lines))
+            .block(
+                Block::default()
+                    .title(" New Agent Task ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(self.palette().accent))
+                    .padding(Padding::new(1, 1, 0, 0)),
+            )
+            .wrap(Wrap { trim: false });
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(panel, area);
+    }
+
+    fn open_command_palette(&mut self) {
+        self.refresh_snapshots();
+        self.command_palette = Some(CommandPalette::new(self.command_palette_items()));
+    }
+
+    /// Headings, links, open agent tasks, revisions, other tabs and named
+    /// commands, in the order the palette lists them for an empty query.
+    fn command_palette_items(&self) -> Vec<PaletteItem> {
+        let rendered = &self.tab.doc.rendered;
+        let mut items: Vec<PaletteItem> = rendered
+            .toc
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| PaletteItem {
+                label: entry.title.clone(),
+                detail: format!(
+                    "h{} · line {}",
+                    entry.level,
+                    entry.source.start_line.saturating_add(1)
+                ),
+                target: PaletteTarget::Heading(idx),
+            })
+            .collect();
+        items.extend(
+            rendered
+                .links
+                .iter()
+                .enumerate()
+                .map(|(idx, link)| PaletteItem {
+                    label: if link.label.trim().is_empty() {
+                        link.target.clone()
+                    } else {
+                        link.label.clone()
+                    },
+                    detail: link.target.clone(),
+                    target: PaletteTarget::Link(idx),
+                }),
+        );
+        items.extend(
+            self.tab
+                .open_agent_tasks
+                .iter()
+                .enumerate()
+                .filter_map(|(position, idx)| {
+                    let task = self.tab.agent_tasks.get(*idx)?;
+                    Some(PaletteItem {
+                        label: task.text.clone(),
+                        detail: format!("line {}", task.line.saturating_add(1)),
+                        target: PaletteTarget::AgentTask(position),
+                    })
+                }),
+        );
+        if self.tab.snapshots.len() > 1 {
+            items.extend(
+                self.tab
+                    .snapshots
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(idx, snapshot)| PaletteItem {
+                        label: snapshot.name(),
+                        detail: format!(
+                            "{} {} UTC +{}/-{}",
+                            format_date_ymd(snapshot.created_at),
+                            format_clock_hms(snapshot.created_at),
+                            snapshot.diff.added,
+                            snapshot.diff.removed
+                        ),
+                        target: PaletteTarget::Revision(idx),
+                    }),
+            );
+        }
+        if !self.tabs.is_empty() {
+            items.extend(
+                self.tab_docs()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(idx, _)| *idx != self.active_tab)
+                    .map(|(idx, (doc, _))| PaletteItem {
+                        label: doc.title(),
+                        detail: format!("tab {}", idx.saturating_add(1)),
+                        target: PaletteTarget::Tab(idx),
+                    }),
+            );
+        }
+        items.extend(Action::ALL.into_iter().filter_map(|action| {
+            let keys = self.keymap.keys(action);
+            Some(PaletteItem {
+                label: action.title()?.to_string(),
+                detail: if keys.is_empty() {
+                    String::new()
+                } else {
+                    self.keymap.label(action)
+                },
+                target: PaletteTarget::Command(action),
+            })
+        }));
+        items
+    }
+
+    fn handle_command_palette_input(&mut self, key: KeyEvent) -> Result<bool> {
+        let Some(palette) = self.command_palette.as_mut() else {
+            return Ok(false);
+        };
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.command_palette = None,
+            KeyCode::Enter => {
+                let target = palette.selected_target();
+                self.command_palette = None;
+                if let Some(target) = target {
+                    return self.run_palette_target(target);
+                }
+            }
+            KeyCode::Up | KeyCode::BackTab => palette.move_selection(true),
+            KeyCode::Down | KeyCode::Tab => palette.move_selection(false),
+            KeyCode::Char('p') if control => palette.move_selection(true),
+            KeyCode::Char('n') if control => palette.move_selection(false),
+            KeyCode::Backspace => palette.pop(),
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                palette.push(c);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn run_palette_target(&mut self, target: PaletteTarget) -> Result<bool> {
+        match target {
+            PaletteTarget::Heading(idx) => self.jump_to_toc_index(idx),
+            PaletteTarget::Link(idx) => {
+                self.tab.selected_link = Some(idx);
+                if let Some(line) = self.selected_link_line() {
+                    self.set_scroll_to_line(line);
+                }
+                self.open_selected_link(false)?;
+            }
+            PaletteTarget::AgentTask(position) => self.jump_to_open_agent_index(position),
+            PaletteTarget::Revision(idx) => {
+                self.jump_to_revision(idx);
+            }
+            PaletteTarget::Tab(idx) => self.switch_tab(idx),
+            PaletteTarget::Command(action) => return self.run_action(action),
+        }
+        Ok(false)
+    }
 
-        frame.render_widget(Clear, area);
-        frame.render_widget(panel, area);
+    fn reload_from_disk(&mut self) -> Result<()> {
+        if self.tab.doc.path.is_none() {
+            self.status = "Nothing to reload: reading from stdin".to_string();
+            return Ok(());
+        }
+        self.reload_current()?;
+        Ok(())
+    }
+
+    /// Writes the rendered document next to the source as `<name>.txt`;
+    /// an existing file is left alone.
+    fn export_plain_text(&mut self) {
+        let Some(path) = self
+            .tab
+            .doc
+            .path
+            .as_ref()
+            .map(|path| path.with_extension("txt"))
+        else {
+            self.status = "Export needs a file: reading from stdin".to_string();
+            return;
+        };
+        let written = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| {
+                io::Write::write_all(&mut file, plain_render(&self.tab.doc.rendered).as_bytes())
+            });
+        self.status = match written {
+            Ok(()) => format!("Exported plain text to {}", path.display()),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                format!("Not exported: {} already exists", path.display())
+            }
+            Err(err) => format!("Export failed: {err}"),
+        };
     }
 
     fn handle_search_input(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Esc | KeyCode::Enter => {
+            KeyCode::Esc => {
                 self.search_mode = false;
             }
+            KeyCode::Enter => {
+                self.search_mode = false;
+                self.search_history.push(&self.tab.search_query);
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let recalled = if key.code == KeyCode::Up {
+                    self.search_history.older(&self.tab.search_query)
+                } else {
+                    self.search_history.newer()
+                };
+                if let Some(query) = recalled {
+                    self.tab.search_query = query.to_string();
+                    self.tab.current_match = 0;
+                    self.update_search_matches();
+                }
+            }
             KeyCode::Backspace => {
-                self.search_query.pop();
-                self.current_match = 0;
+                self.tab.search_query.pop();
+                self.search_history.reset_cursor();
+                self.tab.current_match = 0;
+                self.update_search_matches();
+            }
+            // Ctrl-r toggles regex syntax, Ctrl-w whole-word matching.
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_flags.regex = !self.search_flags.regex;
+                self.tab.current_match = 0;
+                self.update_search_matches();
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_flags.whole_word = !self.search_flags.whole_word;
+                self.tab.current_match = 0;
                 self.update_search_matches();
             }
             KeyCode::Char(c)
@@ -1664,14 +4215,34 @@ impl App {
                     .modifiers
                     .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
             {
-                self.search_query.push(c);
-                self.current_match = 0;
+                self.tab.search_query.push(c);
+                self.search_history.reset_cursor();
+                self.tab.current_match = 0;
                 self.update_search_matches();
             }
             _ => {}
         }
     }
 
+    /// Whether `action` does anything in the current context; keys bound
+    /// to several actions run the first one that applies.
+    fn action_applies(&self, action: Action) -> bool {
+        match action {
+            // `s` sorts the file tree while it has focus, the inbox otherwise.
+            Action::CycleAgentSort | Action::CycleAgentFilter => {
+                self.agent_inbox_open && !self.file_tree_has_focus() && self.tab.diff_view.is_none()
+            }
+            Action::CycleFileSort => self.file_tree_has_focus() && self.tab.diff_view.is_none(),
+            Action::DiffLayout => self.tab.diff_view.is_some(),
+            _ => true,
+        }
+    }
+
+    fn open_help(&mut self) {
+        self.help_open = true;
+        self.help_scroll = 0;
+    }
+
     fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
         if self.search_mode {
             self.handle_search_input(key);
@@ -1683,158 +4254,263 @@ impl App {
             return Ok(false);
         }
 
+        if self.command_palette.is_some() {
+            return self.handle_command_palette_input(key);
+        }
+
+        if key.code == KeyCode::Esc && !self.pending_keys.is_empty() {
+            self.pending_keys.clear();
+            self.pending_since = None;
+            return Ok(false);
+        }
+        let mut keys = mem::take(&mut self.pending_keys);
+        keys.push(self.keymap.key_for_event(&keys, &key));
+        if self.keymap.is_prefix(&keys) {
+            self.pending_keys = keys;
+            self.pending_since = Some(Instant::now());
+            return Ok(false);
+        }
+        self.pending_since = None;
+        if keys.len() > 1 && self.keymap.actions(&keys).next().is_none() {
+            // Not a sequence after all: run the prefix on its own, then this key.
+            keys.pop();
+            if self.dispatch_keys(&keys, false)? {
+                return Ok(true);
+            }
+            return self.handle_key(key);
+        }
+        self.dispatch_keys(&keys, key.code == KeyCode::Esc)
+    }
+
+    /// Runs a half-typed sequence once `KEY_SEQUENCE_TIMEOUT` passed without
+    /// its next key, so a prefix bound on its own (e.g. vim's `g` next to
+    /// `g t`) still runs.
+    fn flush_pending_keys(&mut self) -> Result<bool> {
+        if self
+            .pending_since
+            .is_none_or(|since| since.elapsed() < KEY_SEQUENCE_TIMEOUT)
+        {
+            return Ok(false);
+        }
+        self.pending_since = None;
+        let keys = mem::take(&mut self.pending_keys);
+        self.dispatch_keys(&keys, false)
+    }
+
+    fn dispatch_keys(&mut self, keys: &[Key], escape: bool) -> Result<bool> {
+        let action = self
+            .keymap
+            .actions(keys)
+            .find(|action| self.action_applies(*action));
+
         if self.help_open {
-            match key.code {
-                KeyCode::Char('?') | KeyCode::Esc | KeyCode::Char('q') => {
-                    self.help_open = false;
-                }
+            match action {
+                Some(Action::Help | Action::Quit) => self.help_open = false,
+                Some(Action::ScrollDown) => self.help_scroll = self.help_scroll.saturating_add(1),
+                Some(Action::ScrollUp) => self.help_scroll = self.help_scroll.saturating_sub(1),
+                _ if escape => self.help_open = false,
                 _ => {}
             }
             return Ok(false);
         }
 
-        match key.code {
-            KeyCode::Char('q') => return Ok(true),
-            KeyCode::Char('?') => {
-                self.help_open = true;
-            }
-            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.begin_quick_task_capture();
-            }
-            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::SHIFT) => {
-                self.begin_quick_task_capture();
-            }
-            KeyCode::Char('A') => {
-                self.begin_quick_task_capture();
-            }
-            KeyCode::Char('v') => {
-                self.toggle_timeline();
-            }
-            KeyCode::Char('a') => {
-                self.toggle_agent_inbox();
+        if self.tab.diff_view.is_some() {
+            if escape {
+                self.toggle_diff_view();
+            } else if let Some(action) = action {
+                self.handle_diff_view_action(action);
             }
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.move_revision_relative(true);
-            }
-            KeyCode::Char('l') | KeyCode::Right => {
-                self.move_revision_relative(false);
-            }
-            KeyCode::Char('{') => {
-                self.jump_agent_task_relative(true);
-            }
-            KeyCode::Char('}') => {
-                self.jump_agent_task_relative(false);
-            }
-            KeyCode::Char('L') => {
-                self.jump_to_live_revision();
-            }
-            KeyCode::Char('(') => {
-                self.jump_hunk_relative(true);
-            }
-            KeyCode::Char(')') => {
-                self.jump_hunk_relative(false);
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                if self.agent_inbox_open {
+            return Ok(false);
+        }
+
+        if action != Some(Action::DeleteTask) {
+            self.pending_task_delete = None;
+        }
+        let Some(action) = action else {
+            return Ok(false);
+        };
+        self.run_action(action)
+    }
+
+    /// Runs `action` in the document view; returns true to quit.
+    fn run_action(&mut self, action: Action) -> Result<bool> {
+        let half_page = self.viewport_height.saturating_div(2).max(1);
+        let page = self.viewport_height.max(1);
+        match action {
+            Action::Quit => return Ok(true),
+            Action::Help => self.open_help(),
+            Action::QuickCapture => self.begin_quick_task_capture(),
+            Action::ToggleTimeline => self.toggle_timeline(),
+            Action::ToggleDiff => self.toggle_diff_view(),
+            Action::ToggleMetadata => self.toggle_metadata(),
+            Action::ToggleCallouts => self.toggle_callouts(),
+            Action::ToggleDetails => self.toggle_details(),
+            Action::ToggleAllDetails => self.toggle_all_details(),
+            Action::ToggleHtmlComments => self.toggle_html_comments(),
+            Action::ToggleAgentInbox => self.toggle_agent_inbox(),
+            Action::ToggleToc => self.toggle_toc(),
+            Action::OlderRevision => self.move_revision_relative(true),
+            Action::NewerRevision => self.move_revision_relative(false),
+            Action::LiveRevision => self.jump_to_live_revision(),
+            Action::PrevAgentTask => self.jump_agent_task_relative(true),
+            Action::NextAgentTask => self.jump_agent_task_relative(false),
+            Action::PrevHunk => self.jump_hunk_relative(true),
+            Action::NextHunk => self.jump_hunk_relative(false),
+            Action::ScrollDown => {
+                if self.file_tree_has_focus() {
+                    self.move_file_selection(false);
+                } else if self.agent_inbox_open {
                     self.move_agent_selection(false);
                 } else if self.toc_open {
                     self.move_toc_selection(false);
+                } else if self.callouts_open {
+                    self.move_callout_selection(false);
                 } else {
-                    self.set_scroll_and_sync(self.scroll.saturating_add(1));
+                    self.scroll_by(1, false);
                 }
             }
-            KeyCode::Char('k') | KeyCode::Up => {
-                if self.agent_inbox_open {
+            Action::ScrollUp => {
+                if self.file_tree_has_focus() {
+                    self.move_file_selection(true);
+                } else if self.agent_inbox_open {
                     self.move_agent_selection(true);
                 } else if self.toc_open {
                     self.move_toc_selection(true);
+                } else if self.callouts_open {
+                    self.move_callout_selection(true);
                 } else {
-                    self.set_scroll_and_sync(self.scroll.saturating_sub(1));
+                    self.scroll_by(1, true);
                 }
             }
-            KeyCode::Char('g') => {
-                self.set_scroll_and_sync(0);
-            }
-            KeyCode::Char('G') => {
-                self.set_scroll_and_sync(self.max_scroll());
-            }
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                let delta = self.viewport_height.saturating_div(2).max(1);
-                self.set_scroll_and_sync(self.scroll.saturating_add(delta));
-            }
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                let delta = self.viewport_height.saturating_div(2).max(1);
-                self.set_scroll_and_sync(self.scroll.saturating_sub(delta));
-            }
-            KeyCode::Char('t') => {
-                self.toggle_toc();
-            }
-            KeyCode::Tab => {
-                self.cycle_link(false);
-            }
-            KeyCode::BackTab => {
-                self.cycle_link(true);
-            }
-            KeyCode::Enter => {
-                if self.agent_inbox_open {
+            Action::Top => self.set_scroll_and_sync(0),
+            Action::Bottom => self.set_scroll_and_sync(self.max_scroll()),
+            Action::HalfPageDown => self.scroll_by(half_page, false),
+            Action::HalfPageUp => self.scroll_by(half_page, true),
+            Action::PageDown => self.scroll_by(page, false),
+            Action::PageUp => self.scroll_by(page, true),
+            Action::NextLink => self.cycle_link(false),
+            Action::PrevLink => self.cycle_link(true),
+            Action::Select => {
+                if self.file_tree_has_focus() {
+                    self.select_in_file_tree();
+                } else if self.agent_inbox_open {
                     self.jump_to_selected_agent_task();
                 } else if self.toc_open {
                     self.jump_to_toc_selected();
+                } else if self.callouts_open {
+                    self.jump_to_selected_callout();
                 } else {
                     self.open_selected_link(false)?;
                 }
             }
-            KeyCode::Char('o') => {
-                self.open_selected_link(true)?;
-            }
-            KeyCode::Char(']') => {
-                self.jump_heading_relative(false);
-            }
-            KeyCode::Char('[') => {
-                self.jump_heading_relative(true);
-            }
-            KeyCode::Backspace => {
-                self.go_back()?;
-            }
-            KeyCode::Char('/') => {
+            Action::OpenExternal => self.open_selected_link(true)?,
+            Action::Edit => self.request_editor(),
+            Action::CycleAgentSort => self.cycle_agent_sort(),
+            Action::CycleAgentFilter => self.cycle_agent_owner_filter(),
+            Action::ToggleTask => self.edit_focused_agent_task(false),
+            Action::DeleteTask => self.edit_focused_agent_task(true),
+            Action::NextHeading => self.jump_heading_relative(false),
+            Action::PrevHeading => self.jump_heading_relative(true),
+            Action::Back => self.go_back()?,
+            Action::Search => {
                 self.search_mode = true;
-                self.search_query.clear();
-                self.search_matches.clear();
-                self.current_match = 0;
+                self.tab.search_query.clear();
+                self.tab.search_matches.clear();
+                self.tab.search_error = None;
+                self.search_history.reset_cursor();
+                self.tab.current_match = 0;
             }
-            KeyCode::Char('n') => {
-                self.jump_to_next_match(false);
+            Action::NextMatch => self.jump_to_next_match(false),
+            Action::PrevMatch => self.jump_to_next_match(true),
+            Action::CommandPalette => self.open_command_palette(),
+            Action::Reload => {
+                if self.file_tree_has_focus() {
+                    if self.rescan_file_tree() {
+                        let count = self.file_tree.as_ref().map_or(0, |tree| tree.files.len());
+                        let watched = if self.file_tree_watcher.is_some() {
+                            ""
+                        } else {
+                            " (not watched)"
+                        };
+                        self.status = format!("File tree rescanned: {count} files{watched}");
+                    }
+                } else {
+                    self.reload_from_disk()?;
+                }
             }
-            KeyCode::Char('N') => {
-                self.jump_to_next_match(true);
+            Action::ExportText => self.export_plain_text(),
+            Action::NextTab => self.switch_tab_relative(false),
+            Action::PrevTab => self.switch_tab_relative(true),
+            Action::CloseTab => self.close_tab(),
+            Action::OpenLinkInTab => {
+                if self.file_tree_has_focus() {
+                    self.open_selected_file_in_tab();
+                } else {
+                    self.open_selected_link_in_tab()?;
+                }
             }
-            _ => {}
+            Action::ToggleFileTree => self.toggle_file_tree(),
+            Action::CycleFileSort => self.cycle_file_sort(),
+            Action::DiffLayout => {}
         }
 
         Ok(false)
     }
 }
 
-struct TerminalGuard;
+struct TerminalGuard {
+    /// Keyboard enhancement flags were pushed and must be popped again.
+    enhanced: bool,
+}
 
 impl TerminalGuard {
     fn enter() -> Result<Self> {
         enable_raw_mode()?;
         io::stdout().execute(EnterAlternateScreen)?;
-        Ok(Self)
+        // Terminals with the kitty keyboard protocol report Shift-Enter as
+        // such; elsewhere it arrives as plain Enter.
+        let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced {
+            io::stdout().execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+            ))?;
+        }
+        Ok(Self { enhanced })
+    }
+
+    /// Hands the terminal to a child process and takes it back afterwards.
+    fn suspend_while<T>(&self, run: impl FnOnce() -> T) -> Result<T> {
+        if self.enhanced {
+            io::stdout().execute(PopKeyboardEnhancementFlags)?;
+        }
+        disable_raw_mode()?;
+        io::stdout().execute(LeaveAlternateScreen)?;
+        let result = run();
+        enable_raw_mode()?;
+        io::stdout().execute(EnterAlternateScreen)?;
+        if self.enhanced {
+            io::stdout().execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+            ))?;
+        }
+        Ok(result)
     }
 }
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
+        if self.enhanced {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
         let _ = disable_raw_mode();
         let _ = execute!(io::stdout(), LeaveAlternateScreen);
     }
 }
 
 fn run_interactive(mut app: App) -> Result<()> {
-    let _guard = TerminalGuard::enter()?;
+    let guard = TerminalGuard::enter()?;
     app.ensure_watcher()?;
+    app.watch_file_tree();
 
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
@@ -1843,23 +4519,33 @@ fn run_interactive(mut app: App) -> Result<()> {
     loop {
         terminal.draw(|frame| app.draw(frame))?;
 
-        if app.watch_requested {
+        if app.tab.watch_requested {
             if let Err(err) = app.reload_current() {
                 app.status = format!("Reload failed: {err:#}");
             }
-            app.watch_requested = false;
+            app.tab.watch_requested = false;
         }
 
         app.poll_watch();
+        app.poll_file_tree();
+        app.poll_file_preview();
+        if app.flush_pending_keys()? {
+            break;
+        }
 
         if event::poll(Duration::from_millis(120))? {
-            match event::read()? {
-                CEvent::Key(key) if key.kind == KeyEventKind::Press => {
-                    if app.handle_key(key)? {
-                        break;
-                    }
+            if let CEvent::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && app.handle_key(key)? {
+                    break;
                 }
-                _ => {}
+            }
+        }
+
+        if let Some(line) = app.editor_requested.take() {
+            if let Some(path) = app.tab.doc.path.clone() {
+                let result = guard.suspend_while(|| edit_at_line(&path, line))?;
+                terminal.clear()?;
+                app.finish_editor(line, result);
             }
         }
     }
@@ -1867,44 +4553,171 @@ fn run_interactive(mut app: App) -> Result<()> {
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> Result<ExitCode> {
+    let mut cli = Cli::parse();
+
+    if let Some(Command::Tasks(args)) = &cli.command {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = resolve_theme(
+            &ThemeSet::load_defaults(),
+            ThemePreset::default().syntax_theme(),
+        )?;
+        return run_tasks(args, &syntax_set, &theme);
+    }
+
+    if let Some(Command::Meta(args)) = &cli.command {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = resolve_theme(
+            &ThemeSet::load_defaults(),
+            ThemePreset::default().syntax_theme(),
+        )?;
+        run_meta(args, &syntax_set, &theme)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let config = load_config()?;
+    cli.history = cli.history.or(config.history);
+    let preset = cli.theme.unwrap_or(config.theme);
+    // `--theme` brings its own syntax theme; `syntax_theme` only refines
+    // the preset chosen in the config file.
+    let syntax_theme = match (cli.theme, &config.syntax_theme) {
+        (None, Some(name)) => name.clone(),
+        _ => preset.syntax_theme().to_string(),
+    };
+    let theme_set = ThemeSet::load_defaults();
+    if cli.list_themes {
+        print_themes(&theme_set, preset, &syntax_theme);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let theme = resolve_theme(&theme_set, &syntax_theme)?;
+
+    if cli.clear_history {
+        let store = HistoryStore::open_default()
+            .ok_or_else(|| anyhow!("Cannot locate state directory (XDG_STATE_HOME/HOME unset)"))?;
+        let removed = store.clear()?;
+        eprintln!(
+            "Cleared {removed} history files from {}",
+            store.dir().display()
+        );
+        if cli.inputs.is_empty() {
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
 
     if cli.interactive && cli.plain {
         return Err(anyhow!("--interactive and --plain cannot be used together"));
     }
 
-    let input = detect_input(&cli)?;
-    if cli.watch && matches!(input, InputSource::Stdin) {
+    let inputs = detect_inputs(&cli)?;
+    if cli.watch && matches!(inputs.as_slice(), [InputSource::Stdin]) {
         return Err(anyhow!("--watch requires file input"));
     }
 
     let interactive = match (cli.interactive, cli.plain) {
         (true, false) => true,
         (false, true) => false,
-        _ => default_interactive(&input),
+        _ => default_interactive(&inputs[0]),
     };
 
-    let load = read_input(&input)?;
+    let file_tree = match inputs.as_slice() {
+        [InputSource::Directory(root)] => Some(FileTree::new(root.clone())?),
+        _ => None,
+    };
+    let mut loads = match &file_tree {
+        Some(tree) => {
+            // The pager starts on the selected file; plain output prints them all.
+            let files: Vec<PathBuf> = if interactive {
+                tree.selected_file()
+                    .map(|file| file.path.clone())
+                    .into_iter()
+                    .collect()
+            } else {
+                tree.files.iter().map(|file| file.path.clone()).collect()
+            };
+            if files.is_empty() {
+                return Err(anyhow!("No markdown files under {}", tree.root.display()));
+            }
+            files
+                .into_iter()
+                .map(|path| read_input(&InputSource::File(path)))
+                .collect::<Result<Vec<LoadResult>>>()?
+        }
+        None => inputs
+            .iter()
+            .map(read_input)
+            .collect::<Result<Vec<LoadResult>>>()?,
+    };
 
     let syntax_set = SyntaxSet::load_defaults_newlines();
-    let theme_set = ThemeSet::load_defaults();
-    let theme = resolve_theme(&theme_set);
 
-    let rendered = render_markdown(&load.source, &syntax_set, &theme);
+    // The pager lays out for its content area; plain output for the terminal
+    // it is printed to, and at natural width when piped.
+    let render_width = terminal::size().ok().map(|(columns, _)| {
+        if interactive {
+            // The pager insets its frame by one column on each side.
+            content_text_width(columns.saturating_sub(2))
+        } else {
+            usize::from(columns)
+        }
+    });
+    let render_options = RenderOptions {
+        width: render_width.filter(|_| interactive || io::stdout().is_terminal()),
+        // Plain output cannot expand `<details>` later, so it shows them open.
+        expand_details: !interactive,
+        palette: preset.palette(),
+        ..RenderOptions::default()
+    };
 
     if !interactive {
-        print!("{}", plain_render(&rendered));
-        return Ok(());
+        let color = color_output_enabled(cli.color);
+        for (idx, load) in loads.iter().enumerate() {
+            if idx > 0 {
+                println!();
+            }
+            let rendered = render_markdown(&load.source, &syntax_set, &theme, &render_options);
+            if color {
+                print!("{}", ansi_render(&rendered, ColorDepth::detect()));
+            } else {
+                print!("{}", plain_render(&rendered));
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
     }
 
-    let app = App::new(cli, load, rendered, syntax_set, theme);
-    run_interactive(app)
+    let keymap = config.keys.keymap()?;
+    let extra_tabs = loads.split_off(1);
+    let load = loads.remove(0);
+    let rendered = render_markdown(&load.source, &syntax_set, &theme, &render_options);
+    let mut app = App::new(
+        cli,
+        load,
+        rendered,
+        syntax_set,
+        theme,
+        render_options,
+        keymap,
+    );
+    let status = app.status.clone();
+    for load in extra_tabs {
+        app.open_tab(load)?;
+    }
+    app.switch_tab(0);
+    app.status = status;
+    if file_tree.is_some() {
+        app.file_tree = file_tree;
+        app.file_tree_open = true;
+        app.file_tree_focused = true;
+    }
+    app.apply_panel_config(&config.panels);
+    run_interactive(app)?;
+    Ok(ExitCode::SUCCESS)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
+    use std::path::Path;
 
     fn test_doc(lines: &[&str], toc: &[(u8, &str, usize)]) -> RenderedDocument {
         RenderedDocument {
@@ -1913,6 +4726,8 @@ mod tests {
                 .map(|line| RenderedLine {
                     segments: Vec::new(),
                     plain: (*line).to_string(),
+                    source: None,
+                    wrapped: false,
                 })
                 .collect(),
             toc: toc
@@ -1920,10 +4735,16 @@ mod tests {
                 .map(|(level, title, line)| TocEntry {
                     level: *level,
                     title: (*title).to_string(),
+                    slug: heading_slug(title),
                     line: *line,
+                    source: Default::default(),
                 })
                 .collect(),
             links: Vec::new(),
+            anchors: Vec::new(),
+            front_matter: None,
+            callouts: Vec::new(),
+            details: Vec::new(),
         }
     }
 
@@ -1953,6 +4774,80 @@ mod tests {
         assert_eq!(diff.hunks[0].end_line, 2);
     }
 
+    #[test]
+    fn compute_line_diff_keeps_removed_text() {
+        let old_lines = vec!["a", "b", "c", "d"];
+        let new_lines = vec!["a", "x", "d"];
+        let diff = compute_line_diff(&old_lines, &new_lines, 1_000);
+
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].old_start_line, 1);
+        assert_eq!(diff.hunks[0].removed_lines, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn diff_rows_pair_removed_and_added_lines() {
+        let old_lines = vec!["a", "b", "c", "d"];
+        let new_lines = vec!["a", "x", "d", "e"];
+        let (rows, _) = diff_rows(&old_lines, &new_lines);
+        let kinds: Vec<DiffRowKind> = rows.iter().map(|row| row.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiffRowKind::Context,
+                DiffRowKind::Changed,
+                DiffRowKind::Removed,
+                DiffRowKind::Context,
+                DiffRowKind::Added,
+            ]
+        );
+
+        let inline: Vec<(DiffRowKind, String)> = inline_rows(&rows)
+            .into_iter()
+            .map(|row| (row.kind, row.new.or(row.old).unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            inline[1..4],
+            [
+                (DiffRowKind::Removed, "b".to_string()),
+                (DiffRowKind::Removed, "c".to_string()),
+                (DiffRowKind::Added, "x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_word_ranges_marks_only_edited_words() {
+        let old = "The quick brown fox jumps over the lazy dog";
+        let new = "The quick red fox jumps over the lazy dog";
+        let ranges = changed_word_ranges(old, new).expect("similar lines pair up");
+        let changed: Vec<&str> = ranges.iter().map(|range| &new[range.clone()]).collect();
+        assert_eq!(changed, vec!["red"]);
+
+        assert!(changed_word_ranges("alpha beta", "gamma delta epsilon").is_none());
+    }
+
+    #[test]
+    fn split_by_ranges_cuts_segments_at_change_boundaries() {
+        // Segment "quick red fox" starts at byte 4 of "The quick red fox".
+        let red = [Range { start: 10, end: 13 }];
+        let pieces = split_by_ranges("quick red fox", 4, &red);
+        assert_eq!(
+            pieces,
+            vec![("quick ", false), ("red", true), (" fox", false)]
+        );
+        assert_eq!(split_by_ranges("The ", 0, &red), vec![("The ", false)]);
+    }
+
+    #[test]
+    fn build_snapshot_diff_records_word_changes() {
+        let old_doc = test_doc(&["# Intro", "keep the first draft here"], &[]);
+        let new_doc = test_doc(&["# Intro", "keep the final draft here"], &[]);
+        let diff = build_snapshot_diff(&old_doc, &new_doc);
+        let ranges = diff.word_changes.get(&1).expect("replaced line pairs up");
+        assert_eq!(ranges.as_slice(), [Range { start: 9, end: 14 }]);
+    }
+
     #[test]
     fn build_snapshot_diff_maps_changed_section() {
         let old_doc = test_doc(
@@ -1970,6 +4865,174 @@ mod tests {
         assert_eq!(diff.top_section.as_deref(), Some("Details"));
     }
 
+    fn lcs_len(old_lines: &[&str], new_lines: &[&str]) -> usize {
+        let mut row = vec![0usize; new_lines.len() + 1];
+        for old in old_lines {
+            let mut diagonal = 0;
+            for (j, new) in new_lines.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if old == new {
+                    diagonal + 1
+                } else {
+                    above.max(row[j])
+                };
+                diagonal = above;
+            }
+        }
+        row[new_lines.len()]
+    }
+
+    /// Deterministic edits of a 10k-line document: `changes` replaced lines
+    /// spread evenly through the file plus a few insertions and deletions.
+    fn edited_document(lines: usize, changes: usize) -> (Vec<String>, Vec<String>) {
+        let old: Vec<String> = (0..lines).map(|idx| format!("line {idx}")).collect();
+        let mut new = old.clone();
+        let stride = (lines / changes.max(1)).max(3);
+        for (n, idx) in (stride / 2..lines).step_by(stride).enumerate().rev() {
+            match n % 3 {
+                0 => new[idx] = format!("edited {idx}"),
+                1 => new.insert(idx, format!("inserted {idx}")),
+                _ => {
+                    new.remove(idx);
+                }
+            }
+        }
+        (old, new)
+    }
+
+    #[test]
+    fn compute_line_diff_is_minimal() {
+        let mut seed = 0x2545_f491_u32;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+        let alphabet = ["a", "b", "c", "d"];
+        for _ in 0..200 {
+            let old: Vec<&str> = (0..next() % 12)
+                .map(|_| alphabet[(next() % 4) as usize])
+                .collect();
+            let new: Vec<&str> = (0..next() % 12)
+                .map(|_| alphabet[(next() % 4) as usize])
+                .collect();
+            let diff = compute_line_diff(&old, &new, usize::MAX);
+            let lcs = lcs_len(&old, &new);
+            assert_eq!(diff.removed, old.len() - lcs, "{old:?} -> {new:?}");
+            assert_eq!(diff.added, new.len() - lcs, "{old:?} -> {new:?}");
+            let hunk_added: usize = diff.hunks.iter().map(|hunk| hunk.added).sum();
+            assert_eq!(hunk_added, diff.added);
+        }
+    }
+
+    #[test]
+    fn compute_line_diff_keeps_hunks_on_large_documents() {
+        let (old, new) = edited_document(12_000, 30);
+        let old_refs: Vec<&str> = old.iter().map(String::as_str).collect();
+        let new_refs: Vec<&str> = new.iter().map(String::as_str).collect();
+
+        let diff = compute_line_diff(&old_refs, &new_refs, 50_000_000);
+        assert!(!diff.overflow);
+        assert_eq!(diff.hunks.len(), 30);
+    }
+
+    /// Rebuilds `new` from `old` and the hunks of `diff`, so a coarse
+    /// result can be checked for validity without being minimal.
+    fn apply_hunks(old: &[&str], new: &[&str], diff: &LineDiffResult) -> Vec<String> {
+        let mut rebuilt = Vec::new();
+        let mut old_idx = 0;
+        for hunk in &diff.hunks {
+            rebuilt.extend(
+                old[old_idx..hunk.old_start_line]
+                    .iter()
+                    .map(|line| line.to_string()),
+            );
+            let removed_end = hunk.old_start_line + hunk.removed;
+            assert_eq!(hunk.removed_lines, old[hunk.old_start_line..removed_end]);
+            assert_eq!(hunk.end_line - hunk.start_line, hunk.added);
+            rebuilt.extend(
+                new[hunk.start_line..hunk.end_line]
+                    .iter()
+                    .map(|line| line.to_string()),
+            );
+            old_idx = removed_end;
+        }
+        rebuilt.extend(old[old_idx..].iter().map(|line| line.to_string()));
+        rebuilt
+    }
+
+    #[test]
+    fn compute_line_diff_stops_at_its_budget() {
+        let framed = |body: Vec<String>| {
+            let mut lines = vec!["head".to_string()];
+            lines.extend(body);
+            lines.push("tail".to_string());
+            lines
+        };
+        let disjoint = (
+            framed((0..2_000).map(|idx| format!("a{idx}")).collect()),
+            framed((0..2_000).map(|idx| format!("b{idx}")).collect()),
+        );
+        let half_edited = (
+            framed((0..2_000).map(|idx| format!("line {idx}")).collect()),
+            framed(
+                (0..2_000)
+                    .map(|idx| {
+                        if idx % 2 == 0 {
+                            format!("edited {idx}")
+                        } else {
+                            format!("line {idx}")
+                        }
+                    })
+                    .collect(),
+            ),
+        );
+        // The last half-edited line is unchanged, so it joins the common suffix.
+        for ((old, new), changed) in [(disjoint, 2_000), (half_edited, 1_999)] {
+            let old_refs: Vec<&str> = old.iter().map(String::as_str).collect();
+            let new_refs: Vec<&str> = new.iter().map(String::as_str).collect();
+
+            let coarse = compute_line_diff(&old_refs, &new_refs, 10_000);
+            assert!(coarse.overflow);
+            assert_eq!(coarse.hunks.len(), 1);
+            assert_eq!(
+                (coarse.hunks[0].start_line, coarse.hunks[0].end_line),
+                (1, changed + 1)
+            );
+            assert_eq!((coarse.added, coarse.removed), (changed, changed));
+            assert_eq!(apply_hunks(&old_refs, &new_refs, &coarse), new);
+
+            let exact = compute_line_diff(&old_refs, &new_refs, usize::MAX);
+            assert!(!exact.overflow);
+            assert_eq!(apply_hunks(&old_refs, &new_refs, &exact), new);
+        }
+    }
+
+    /// Timing check for the watch loop at the real budget; run with
+    /// `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore]
+    fn bench_compute_line_diff() {
+        for (lines, changes) in [(1_000, 10), (10_000, 50), (10_000, 500), (50_000, 200)] {
+            let (old, new) = edited_document(lines, changes);
+            let old_refs: Vec<&str> = old.iter().map(String::as_str).collect();
+            let new_refs: Vec<&str> = new.iter().map(String::as_str).collect();
+            let runs = 5;
+            let started = Instant::now();
+            let mut diff = LineDiffResult::default();
+            for _ in 0..runs {
+                diff = compute_line_diff(&old_refs, &new_refs, DIFF_MAX_COST);
+            }
+            println!(
+                "{lines} lines, {changes} edits: {} hunks{}, {:?}/diff",
+                diff.hunks.len(),
+                if diff.overflow { " (fallback)" } else { "" },
+                started.elapsed() / runs
+            );
+        }
+    }
+
     #[test]
     fn compute_line_diff_falls_back_for_large_matrix() {
         let old_lines: Vec<String> = (0..60).map(|idx| format!("a{idx}")).collect();
@@ -1983,4 +5046,195 @@ mod tests {
         assert_eq!(diff.added, 60);
         assert_eq!(diff.removed, 60);
     }
+
+    fn temp_markdown(name: &str, source: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("catmd-tabs-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.md"));
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn app_for(path: &Path) -> App {
+        app_with(LoadResult {
+            path: Some(path.to_path_buf()),
+            source: fs::read_to_string(path).unwrap(),
+        })
+    }
+
+    fn app_with(load: LoadResult) -> App {
+        let cli = Cli::parse_from(["catmd", "--watch", "--no-persist-history", "--no-since"]);
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = Theme::default();
+        let render_options = RenderOptions::default();
+        let rendered = render_markdown(&load.source, &syntax_set, &theme, &render_options);
+        App::new(
+            cli,
+            load,
+            rendered,
+            syntax_set,
+            theme,
+            render_options,
+            Keymap::default(),
+        )
+    }
+
+    fn numbered(word: &str) -> String {
+        (1..=40).map(|idx| format!("{word} {idx}\n\n")).collect()
+    }
+
+    #[test]
+    fn back_returns_from_anchor_jumps_in_stdin() {
+        let source = format!("[notes](#notes)\n\n{}# Notes\n", numbered("para"));
+        let mut app = app_with(LoadResult { path: None, source });
+        app.viewport_height = 5;
+
+        app.open_selected_link(false).unwrap();
+        assert_eq!(app.status, "Jumped to #notes");
+        assert!(app.tab.scroll > 0);
+        app.go_back().unwrap();
+        assert_eq!(app.tab.scroll, 0);
+        assert_eq!(app.status, "Returned to line 1");
+        assert!(app.tab.backstack.is_empty());
+    }
+
+    #[test]
+    fn editor_opens_at_the_top_line_unless_the_toc_is_focused() {
+        let source = format!("# Intro\n\n{}# Next\n", numbered("para"));
+        let mut app = app_with(LoadResult { path: None, source });
+        app.viewport_height = 5;
+        app.toggle_toc();
+        app.scroll_by(10, false);
+        let top = app
+            .tab
+            .doc
+            .rendered
+            .source_line_at(usize::from(app.tab.scroll))
+            .unwrap();
+        assert!(top > 0);
+        assert_eq!(app.editor_target_line(), top);
+
+        app.move_toc_selection(false);
+        let next = app.tab.doc.rendered.toc[1].source.start_line;
+        assert_eq!(app.editor_target_line(), next);
+        app.scroll_by(1, false);
+        assert!(!app.tab.toc_focused);
+        let top = app
+            .tab
+            .doc
+            .rendered
+            .source_line_at(usize::from(app.tab.scroll))
+            .unwrap();
+        assert_ne!(top, next);
+        assert_eq!(app.editor_target_line(), top);
+    }
+
+    #[test]
+    fn completing_a_wrapped_task_stamps_its_last_line() {
+        let path = temp_markdown("stamp", "- [ ] @agent first line\n  second line\n");
+        let mut app = app_for(&path);
+        app.cli.done_stamp = true;
+        app.edit_focused_agent_task(false);
+
+        let date = format_date_ymd(SystemTime::now());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("- [x] @agent first line\n  second line (done {date})\n")
+        );
+        assert!(app.status.ends_with(&format!("(stamped {date} UTC)")));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn relayout_renders_other_snapshots_once_they_are_needed() {
+        let words = "word ".repeat(60);
+        let path = temp_markdown("layout", &format!("{words}\n"));
+        let mut app = app_for(&path);
+        app.relayout(80);
+        for extra in ["one", "two"] {
+            fs::write(&path, format!("{words}{extra}\n")).unwrap();
+            app.reload_current().unwrap();
+        }
+        app.toggle_diff_view();
+        app.status = "kept".to_string();
+
+        app.relayout(30);
+        assert_eq!(app.status, "kept");
+        let stale: Vec<bool> = app
+            .tab
+            .snapshots
+            .iter()
+            .map(|snapshot| snapshot.stale)
+            .collect();
+        assert_eq!(stale, vec![true, false, false]);
+        let lines = app.render(&app.tab.snapshots[2].source).lines.len();
+        assert_eq!(app.tab.doc.rendered.lines.len(), lines);
+
+        app.refresh_snapshots();
+        let first = app.render(&app.tab.snapshots[0].source);
+        let second = app.render(&app.tab.snapshots[1].source);
+        assert!(app.tab.snapshots.iter().all(|snapshot| !snapshot.stale));
+        assert_eq!(
+            app.tab.snapshots[1].diff.added,
+            build_snapshot_diff(&first, &second).added
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn tabs_keep_their_scroll_search_and_snapshots() {
+        let alpha = temp_markdown("alpha", &numbered("alpha"));
+        let beta = temp_markdown("beta", &numbered("beta"));
+        let mut app = app_for(&alpha);
+        app.viewport_height = 5;
+        app.set_scroll_to_line(3);
+
+        let source = fs::read_to_string(&beta).unwrap();
+        app.open_tab(LoadResult {
+            path: Some(beta.clone()),
+            source,
+        })
+        .unwrap();
+        assert_eq!((app.active_tab, app.tab.scroll), (1, 0));
+        app.set_scroll_to_line(20);
+        assert_eq!(app.tab.scroll, 20);
+        fs::write(&beta, numbered("beta").replace("beta 2\n", "beta two\n")).unwrap();
+        app.reload_current().unwrap();
+        assert_eq!(app.tab.snapshots.len(), 2);
+        assert_eq!(app.tab.scroll, 20);
+        app.tab.search_query = "beta 1".to_string();
+        app.update_search_matches();
+        let search_scroll = app.tab.scroll;
+
+        app.switch_tab_relative(true);
+        assert_eq!(app.tab.doc.path.as_ref(), Some(&alpha));
+        assert_eq!(app.tab.scroll, 3);
+        assert!(app.tab.search_query.is_empty());
+        assert_eq!(app.tab.snapshots.len(), 1);
+
+        app.switch_tab_relative(false);
+        assert_eq!(app.tab.doc.path.as_ref(), Some(&beta));
+        assert_eq!(app.tab.scroll, search_scroll);
+        assert_eq!(app.tab.search_query, "beta 1");
+        assert_eq!(app.tab.search_matches.len(), 11);
+        assert_eq!(app.tab.snapshots.len(), 2);
+        assert_eq!(app.tab_docs()[0].0.path.as_ref(), Some(&alpha));
+
+        app.switch_tab_relative(true);
+        app.status = "alpha status".to_string();
+        fs::write(&beta, numbered("beta")).unwrap();
+        app.reload_background_tab(0);
+        assert_eq!(app.tabs[0].snapshots.len(), 3);
+        assert_eq!(app.status, "alpha status");
+        assert!(app.tab_docs()[1].1);
+        app.switch_tab_relative(false);
+        assert!(!app.tab_docs()[1].1);
+        assert_eq!(app.tab.snapshots.len(), 3);
+
+        fs::remove_dir_all(alpha.parent().unwrap()).unwrap();
+        fs::remove_dir_all(beta.parent().unwrap()).unwrap();
+    }
 }
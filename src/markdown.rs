@@ -1,12 +1,41 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
 use pulldown_cmark::{
-    Alignment, CodeBlockKind, Event as MdEvent, HeadingLevel, Options, Parser as MdParser, Tag,
-    TagEnd,
+    Alignment, BlockQuoteKind, CodeBlockKind, Event as MdEvent, HeadingLevel, MetadataBlockKind,
+    Options, Parser as MdParser, Tag, TagEnd,
 };
 use ratatui::prelude::{Color, Modifier, Style};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::Theme;
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::frontmatter::{FrontMatter, FrontMatterFormat};
+use crate::html::{decode_entities, tokenize, HtmlTag, HtmlToken};
+use crate::theme::Palette;
+
+/// HTML tags that start a new line when they open or close.
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "blockquote",
+    "center",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
 
 const HORIZONTAL_RULE: &str = "────────────────────────────────────────────────────────────────";
 
@@ -16,10 +45,22 @@ pub(crate) struct StyledSegment {
     pub(crate) style: Style,
 }
 
+/// Markdown source behind a rendered element: a byte range plus the
+/// 0-based, inclusive line span it covers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SourceSpan {
+    pub(crate) bytes: Range<usize>,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+}
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct RenderedLine {
     pub(crate) segments: Vec<StyledSegment>,
     pub(crate) plain: String,
+    pub(crate) source: Option<SourceSpan>,
+    /// Continues the previous line after wrapping at the render width.
+    pub(crate) wrapped: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -27,26 +68,226 @@ pub(crate) struct LinkRef {
     pub(crate) label: String,
     pub(crate) target: String,
     pub(crate) line: usize,
+    pub(crate) source: SourceSpan,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct TocEntry {
     pub(crate) level: u8,
     pub(crate) title: String,
+    pub(crate) slug: String,
+    pub(crate) line: usize,
+    pub(crate) source: SourceSpan,
+}
+
+/// Link target addressed as `#slug`: a heading of any level, or a footnote
+/// definition (`#fn-name`).
+#[derive(Clone, Debug)]
+pub(crate) struct HeadingAnchor {
+    pub(crate) slug: String,
     pub(crate) line: usize,
 }
 
+/// Kind of a GitHub alert (`> [!NOTE]`), rendered as a callout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CalloutKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl CalloutKind {
+    fn from_blockquote(kind: BlockQuoteKind) -> Self {
+        match kind {
+            BlockQuoteKind::Note => Self::Note,
+            BlockQuoteKind::Tip => Self::Tip,
+            BlockQuoteKind::Important => Self::Important,
+            BlockQuoteKind::Warning => Self::Warning,
+            BlockQuoteKind::Caution => Self::Caution,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Note => "Note",
+            Self::Tip => "Tip",
+            Self::Important => "Important",
+            Self::Warning => "Warning",
+            Self::Caution => "Caution",
+        }
+    }
+
+    pub(crate) fn icon(self) -> &'static str {
+        match self {
+            Self::Note => "ℹ",
+            Self::Tip => "✓",
+            Self::Important => "‼",
+            Self::Warning => "⚠",
+            Self::Caution => "✖",
+        }
+    }
+
+    pub(crate) fn color(self, palette: &Palette) -> Color {
+        let [note, tip, important, warning, caution] = palette.callouts;
+        match self {
+            Self::Note => note,
+            Self::Tip => tip,
+            Self::Important => important,
+            Self::Warning => warning,
+            Self::Caution => caution,
+        }
+    }
+}
+
+/// A rendered callout: `line` is its title line, `summary` the first line
+/// of its body.
+#[derive(Clone, Debug)]
+pub(crate) struct Callout {
+    pub(crate) kind: CalloutKind,
+    pub(crate) line: usize,
+    pub(crate) summary: String,
+    pub(crate) source: SourceSpan,
+}
+
+/// Names a `<details>` block across edits and revisions: its summary and
+/// how many earlier blocks in the document share that summary.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct DetailsKey {
+    pub(crate) summary: String,
+    pub(crate) occurrence: usize,
+}
+
+/// A rendered `<details>` block. `line` is its summary line and `body` the
+/// lines below it. A collapsed block still renders its body, so headings,
+/// links and tasks inside stay indexed; the pager skips the lines instead.
+#[derive(Clone, Debug)]
+pub(crate) struct DetailsBlock {
+    pub(crate) key: DetailsKey,
+    pub(crate) line: usize,
+    pub(crate) body: Range<usize>,
+    pub(crate) summary: String,
+    pub(crate) open: bool,
+}
+
+/// View settings that change what gets rendered.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RenderOptions {
+    /// Display columns available for content; `None` renders at natural width.
+    pub(crate) width: Option<usize>,
+    /// Show HTML comments (dimmed) instead of hiding them.
+    pub(crate) show_comments: bool,
+    /// Expand every `<details>` block regardless of `expanded_details`.
+    pub(crate) expand_details: bool,
+    /// Keys of the expanded `<details>` blocks.
+    pub(crate) expanded_details: HashSet<DetailsKey>,
+    pub(crate) palette: Palette,
+}
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct RenderedDocument {
     pub(crate) lines: Vec<RenderedLine>,
     pub(crate) toc: Vec<TocEntry>,
     pub(crate) links: Vec<LinkRef>,
+    pub(crate) anchors: Vec<HeadingAnchor>,
+    /// Parsed `---` / `+++` metadata block; it is not rendered in `lines`.
+    pub(crate) front_matter: Option<FrontMatter>,
+    pub(crate) callouts: Vec<Callout>,
+    pub(crate) details: Vec<DetailsBlock>,
+}
+
+impl RenderedDocument {
+    /// First source line of the rendered line at `line`, or of the nearest
+    /// rendered line above it when `line` is synthesized (blank lines,
+    /// table separators).
+    pub(crate) fn source_line_at(&self, line: usize) -> Option<usize> {
+        let end = line.min(self.lines.len().saturating_sub(1));
+        self.lines
+            .get(..=end)?
+            .iter()
+            .rev()
+            .find_map(|rendered| rendered.source.as_ref())
+            .map(|span| span.start_line)
+    }
+
+    /// First rendered line that shows 0-based `source_line` or anything after it.
+    pub(crate) fn line_for_source_line(&self, source_line: usize) -> Option<usize> {
+        self.lines.iter().position(|line| {
+            line.source
+                .as_ref()
+                .is_some_and(|span| span.end_line >= source_line)
+        })
+    }
+
+    /// Whether `line` sits in the body of a collapsed `<details>` block.
+    pub(crate) fn is_hidden(&self, line: usize) -> bool {
+        self.details
+            .iter()
+            .any(|details| !details.open && details.body.contains(&line))
+    }
+
+    /// `line`, or the summary line of the outermost collapsed `<details>`
+    /// block hiding it.
+    pub(crate) fn shown_line(&self, line: usize) -> usize {
+        self.details
+            .iter()
+            .filter(|details| !details.open && details.body.contains(&line))
+            .map(|details| details.line)
+            .min()
+            .unwrap_or(line)
+    }
+
+    /// Moves `count` shown lines down (or up) from `from`, skipping the
+    /// bodies of collapsed `<details>` blocks; stops at either end.
+    pub(crate) fn step_shown(&self, from: usize, count: usize, up: bool) -> usize {
+        let last = self.lines.len().saturating_sub(1);
+        let mut line = self.shown_line(from.min(last));
+        let mut candidate = line;
+        let mut remaining = count;
+        while remaining > 0 {
+            let next = if up {
+                candidate.checked_sub(1)
+            } else {
+                (candidate < last).then(|| candidate.saturating_add(1))
+            };
+            let Some(next) = next else {
+                break;
+            };
+            candidate = next;
+            if !self.is_hidden(next) {
+                line = next;
+                remaining -= 1;
+            }
+        }
+        line
+    }
+
+    /// Keys of the collapsed `<details>` blocks around `line`, outermost first.
+    pub(crate) fn collapsed_around(&self, line: usize) -> Vec<DetailsKey> {
+        self.details
+            .iter()
+            .filter(|details| !details.open && details.body.contains(&line))
+            .map(|details| details.key.clone())
+            .collect()
+    }
+
+    pub(crate) fn anchor_line(&self, fragment: &str) -> Option<usize> {
+        let wanted = fragment.to_lowercase();
+        self.anchors
+            .iter()
+            .find(|anchor| anchor.slug == wanted)
+            .map(|anchor| anchor.line)
+    }
 }
 
 #[derive(Clone)]
 struct ActiveLink {
     target: String,
     text: String,
+    source: Range<usize>,
+    /// Byte offset of the link text in the line being built.
+    offset: usize,
 }
 
 #[derive(Clone)]
@@ -55,16 +296,83 @@ struct ActiveImage {
     alt: String,
 }
 
+/// Rendered contents of one table cell, plus the indices of links inside it.
+#[derive(Clone, Debug, Default)]
+struct TableCell {
+    segments: Vec<StyledSegment>,
+    links: Vec<(usize, usize)>,
+}
+
+impl TableCell {
+    fn width(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.text.width())
+            .sum()
+    }
+
+    fn longest_word(&self) -> usize {
+        let text: String = self
+            .segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+        text.split_whitespace()
+            .map(UnicodeWidthStr::width)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A footnote definition rendered aside while the main flow continues.
+struct FootnoteDef {
+    number: usize,
+    name: String,
+    lines: Vec<RenderedLine>,
+    /// Indices of links inside the definition; their lines are relative to
+    /// `lines` until the footnotes section is placed.
+    links: Range<usize>,
+    /// Headings, anchors, callouts and `<details>` blocks inside the
+    /// definition, with lines relative to `lines`. They move to the end of
+    /// their lists with the footnotes section, keeping them in line order.
+    toc: Vec<TocEntry>,
+    anchors: Vec<HeadingAnchor>,
+    callouts: Vec<Callout>,
+    details: Vec<DetailsBlock>,
+}
+
+/// Main-flow lines put away while a footnote definition renders.
+struct FootnoteStash {
+    number: usize,
+    name: String,
+    lines: Vec<RenderedLine>,
+    links_start: usize,
+    toc_start: usize,
+    anchors_start: usize,
+    callouts_start: usize,
+    details_start: usize,
+}
+
+/// A `<details>` block whose closing tag has not been seen yet.
+struct OpenDetails {
+    summary: String,
+    in_summary: bool,
+    /// Index of its `DetailsBlock` once the summary line has been rendered.
+    block: Option<usize>,
+    source: Range<usize>,
+}
+
 #[derive(Default)]
 struct TableState {
     in_head: bool,
     in_row: bool,
     in_cell: bool,
-    headers: Vec<String>,
-    rows: Vec<Vec<String>>,
-    current_row: Vec<String>,
-    current_cell: String,
+    headers: Vec<TableCell>,
+    rows: Vec<Vec<TableCell>>,
+    current_row: Vec<TableCell>,
     alignments: Vec<Alignment>,
+    header_source: Option<Range<usize>>,
+    row_sources: Vec<Range<usize>>,
 }
 
 impl TableState {
@@ -82,10 +390,13 @@ struct InlineState {
     strong: usize,
     strikethrough: usize,
     link_depth: usize,
+    /// `<code>` and `<kbd>` from inline HTML.
+    code: usize,
+    kbd: usize,
 }
 
 impl InlineState {
-    fn style(&self) -> Style {
+    fn style(&self, palette: &Palette) -> Style {
         let mut style = Style::default();
         if self.emphasis > 0 {
             style = style.add_modifier(Modifier::ITALIC);
@@ -97,7 +408,16 @@ impl InlineState {
             style = style.add_modifier(Modifier::CROSSED_OUT);
         }
         if self.link_depth > 0 {
-            style = style.fg(Color::Cyan).add_modifier(Modifier::UNDERLINED);
+            style = style.fg(palette.link).add_modifier(Modifier::UNDERLINED);
+        }
+        if self.code > 0 {
+            style = style.fg(palette.inline_code).add_modifier(Modifier::BOLD);
+        }
+        if self.kbd > 0 {
+            style = style
+                .fg(palette.kbd_fg)
+                .bg(palette.kbd_bg)
+                .add_modifier(Modifier::BOLD);
         }
         style
     }
@@ -106,27 +426,61 @@ impl InlineState {
 struct Renderer<'a> {
     syntax_set: &'a SyntaxSet,
     theme: &'a Theme,
+    /// Display columns available for content; `None` renders at natural width.
+    width: Option<usize>,
+    options: &'a RenderOptions,
+
+    line_starts: Vec<usize>,
+    event_range: Range<usize>,
+    current_source: Option<Range<usize>>,
 
     lines: Vec<RenderedLine>,
     toc: Vec<TocEntry>,
     links: Vec<LinkRef>,
+    anchors: Vec<HeadingAnchor>,
+    slug_counts: HashMap<String, usize>,
 
     inline: InlineState,
     current_segments: Vec<StyledSegment>,
     current_plain: String,
-    current_line_link_indices: Vec<usize>,
+    /// Links on the line being built, as (link index, byte offset in the line).
+    current_line_link_indices: Vec<(usize, usize)>,
+    /// Column where wrapped continuation lines start, set by list markers.
+    hang_width: Option<usize>,
 
     active_link: Option<ActiveLink>,
     active_image: Option<ActiveImage>,
 
     heading_level: Option<u8>,
-    blockquote_depth: usize,
+    heading_source: Range<usize>,
+    heading_line: usize,
+    /// One entry per open blockquote; `Some` for alert callouts.
+    quote_stack: Vec<Option<CalloutKind>>,
+    /// Indices into `callouts` of the callouts being rendered.
+    open_callouts: Vec<usize>,
+    callouts: Vec<Callout>,
     list_stack: Vec<ListState>,
 
     code_block_lang: Option<String>,
     code_block_buf: String,
+    code_block_first_line: usize,
 
     table: Option<TableState>,
+
+    footnote_numbers: HashMap<String, usize>,
+    footnotes: Vec<FootnoteDef>,
+    footnote_stash: Option<FootnoteStash>,
+
+    metadata_block: Option<(FrontMatterFormat, String)>,
+    front_matter: Option<FrontMatter>,
+
+    /// Inside a `<!-- -->` comment that continues in the next HTML event.
+    in_html_comment: bool,
+    /// Open `<details>` blocks, innermost last.
+    details_stack: Vec<OpenDetails>,
+    /// Blocks seen so far per summary, for `DetailsKey::occurrence`.
+    details_summaries: HashMap<String, usize>,
+    details: Vec<DetailsBlock>,
 }
 
 #[derive(Clone, Debug)]
@@ -136,30 +490,64 @@ struct ListState {
 }
 
 impl<'a> Renderer<'a> {
-    fn new(syntax_set: &'a SyntaxSet, theme: &'a Theme) -> Self {
+    fn new(
+        syntax_set: &'a SyntaxSet,
+        theme: &'a Theme,
+        source: &str,
+        options: &'a RenderOptions,
+    ) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
         Self {
             syntax_set,
             theme,
+            width: options.width,
+            options,
+            line_starts,
+            event_range: 0..0,
+            current_source: None,
             lines: Vec::new(),
             toc: Vec::new(),
             links: Vec::new(),
+            anchors: Vec::new(),
+            slug_counts: HashMap::new(),
             inline: InlineState::default(),
             current_segments: Vec::new(),
             current_plain: String::new(),
             current_line_link_indices: Vec::new(),
+            hang_width: None,
             active_link: None,
             active_image: None,
             heading_level: None,
-            blockquote_depth: 0,
+            heading_source: 0..0,
+            heading_line: 0,
+            quote_stack: Vec::new(),
+            open_callouts: Vec::new(),
+            callouts: Vec::new(),
             list_stack: Vec::new(),
             code_block_lang: None,
             code_block_buf: String::new(),
+            code_block_first_line: 0,
             table: None,
+            footnote_numbers: HashMap::new(),
+            footnotes: Vec::new(),
+            footnote_stash: None,
+            metadata_block: None,
+            front_matter: None,
+            in_html_comment: false,
+            details_stack: Vec::new(),
+            details_summaries: HashMap::new(),
+            details: Vec::new(),
         }
     }
 
     fn finish(mut self) -> RenderedDocument {
         self.flush_line(false);
+        while !self.details_stack.is_empty() {
+            self.close_details();
+        }
+        self.render_footnotes();
         if self.lines.is_empty() {
             self.lines.push(RenderedLine::default());
         }
@@ -167,9 +555,427 @@ impl<'a> Renderer<'a> {
             lines: self.lines,
             toc: self.toc,
             links: self.links,
+            anchors: self.anchors,
+            front_matter: self.front_matter,
+            callouts: self.callouts,
+            details: self.details,
+        }
+    }
+
+    /// Footnotes are numbered in order of first appearance, reference or
+    /// definition.
+    fn footnote_number(&mut self, name: &str) -> usize {
+        let next = self.footnote_numbers.len().saturating_add(1);
+        *self
+            .footnote_numbers
+            .entry(name.to_string())
+            .or_insert(next)
+    }
+
+    /// Renders the callout title line (icon and label) for an alert.
+    fn start_callout(&mut self, kind: CalloutKind) {
+        let start = self.event_range.start;
+        let line_end = self
+            .line_starts
+            .get(self.line_of(start).saturating_add(1))
+            .map_or(self.event_range.end, |next| next.saturating_sub(1));
+        let title_source = start..line_end.clamp(start, self.event_range.end.max(start));
+
+        self.push_prefix_if_needed();
+        self.push_text(
+            &format!("{} {}", kind.icon(), kind.label()),
+            Style::default()
+                .fg(kind.color(&self.options.palette))
+                .add_modifier(Modifier::BOLD),
+        );
+        self.current_source = Some(title_source.clone());
+        let line = self.lines.len();
+        self.flush_line(false);
+
+        self.open_callouts.push(self.callouts.len());
+        self.callouts.push(Callout {
+            kind,
+            line,
+            summary: String::new(),
+            source: self.span_for(title_source),
+        });
+    }
+
+    fn end_callout(&mut self) {
+        let Some(index) = self.open_callouts.pop() else {
+            return;
+        };
+        let Some(callout) = self.callouts.get_mut(index) else {
+            return;
+        };
+        callout.summary = self
+            .lines
+            .iter()
+            .skip(callout.line.saturating_add(1))
+            .map(|line| line.plain.trim_start_matches(['┃', '>', ' ']).trim_end())
+            .find(|text| !text.is_empty())
+            .unwrap_or_default()
+            .to_string();
+    }
+
+    fn in_details_summary(&self) -> bool {
+        self.details_stack
+            .last()
+            .is_some_and(|details| details.in_summary)
+    }
+
+    /// Renders inline or block HTML: comments are hidden unless enabled,
+    /// a few tags map onto Markdown styling and other tags are dropped
+    /// with their text kept.
+    fn add_html(&mut self, html: &str, block: bool) {
+        for token in tokenize(html, &mut self.in_html_comment) {
+            match token {
+                HtmlToken::Comment(comment) => {
+                    if self.options.show_comments {
+                        self.add_html_text(
+                            comment,
+                            block,
+                            Some(comment_style(&self.options.palette)),
+                        );
+                    }
+                }
+                HtmlToken::Text(text) => {
+                    let text = decode_entities(text);
+                    if self.in_details_summary() {
+                        if let Some(details) = self.details_stack.last_mut() {
+                            details.summary.push_str(&text);
+                        }
+                    } else {
+                        self.add_html_text(&text, block, None);
+                    }
+                }
+                HtmlToken::Tag(tag) => self.add_html_tag(&tag),
+            }
+        }
+    }
+
+    /// Adds text from HTML; in blocks, newlines end the line and
+    /// indentation at the start of a line is dropped.
+    fn add_html_text(&mut self, text: &str, block: bool, style: Option<Style>) {
+        if !block {
+            match style {
+                Some(style) => {
+                    self.push_prefix_if_needed();
+                    self.push_text(text, style);
+                    self.note_source();
+                }
+                None => self.add_text(text),
+            }
+            return;
+        }
+        for (idx, part) in text.split('\n').enumerate() {
+            if idx > 0 {
+                self.flush_line(false);
+            }
+            let part = if self.current_plain.is_empty() {
+                part.trim_start()
+            } else {
+                part
+            };
+            if part.is_empty() {
+                continue;
+            }
+            match style {
+                Some(style) => {
+                    self.ensure_details_started();
+                    self.push_prefix_if_needed();
+                    self.push_text(part, style);
+                    self.note_source();
+                }
+                None => self.add_text(part),
+            }
+        }
+    }
+
+    fn add_html_tag(&mut self, tag: &HtmlTag) {
+        match (tag.name.as_str(), tag.closing) {
+            ("details", false) => self.open_details(),
+            ("details", true) => self.close_details(),
+            ("summary", false) => {
+                if let Some(details) = self.details_stack.last_mut() {
+                    details.in_summary = details.block.is_none();
+                }
+            }
+            ("summary", true) if self.in_details_summary() => self.ensure_details_started(),
+            ("br", _) => {
+                self.ensure_details_started();
+                self.hard_break();
+            }
+            ("hr", false) => {
+                self.ensure_details_started();
+                self.add_rule();
+            }
+            ("img", false) => {
+                self.ensure_details_started();
+                let alt = tag.attr("alt").unwrap_or_default();
+                let target = tag.attr("src").unwrap_or_default();
+                self.add_image_placeholder(alt, target);
+            }
+            ("kbd", closing) => adjust_depth(&mut self.inline.kbd, closing),
+            ("code" | "tt" | "samp", closing) => adjust_depth(&mut self.inline.code, closing),
+            ("b" | "strong", closing) => adjust_depth(&mut self.inline.strong, closing),
+            ("i" | "em", closing) => adjust_depth(&mut self.inline.emphasis, closing),
+            ("s" | "del" | "strike", closing) => {
+                adjust_depth(&mut self.inline.strikethrough, closing);
+            }
+            (name, _) if HTML_BLOCK_TAGS.contains(&name) => self.flush_line(false),
+            _ => {}
+        }
+    }
+
+    fn open_details(&mut self) {
+        self.ensure_details_started();
+        self.flush_line(false);
+        self.details_stack.push(OpenDetails {
+            summary: String::new(),
+            in_summary: false,
+            block: None,
+            source: self.event_range.clone(),
+        });
+    }
+
+    /// Renders the summary line of the innermost `<details>` once its
+    /// `<summary>` is complete or its body begins.
+    fn ensure_details_started(&mut self) {
+        let Some(details) = self.details_stack.last_mut() else {
+            return;
+        };
+        if details.block.is_some() {
+            return;
+        }
+        details.in_summary = false;
+        let source = details.source.clone();
+        let summary = details.summary.split_whitespace().collect::<Vec<_>>();
+        let summary = if summary.is_empty() {
+            "Details".to_string()
+        } else {
+            summary.join(" ")
+        };
+        let occurrence = self.details_summaries.entry(summary.clone()).or_default();
+        let key = DetailsKey {
+            summary: summary.clone(),
+            occurrence: *occurrence,
+        };
+        *occurrence = occurrence.saturating_add(1);
+        let open = self.options.expand_details || self.options.expanded_details.contains(&key);
+
+        self.flush_line(false);
+        self.push_prefix_if_needed();
+        self.push_text(
+            if open { "▾ " } else { "▸ " },
+            Style::default().fg(self.options.palette.link),
+        );
+        self.push_text(&summary, Style::default().add_modifier(Modifier::BOLD));
+        self.current_source = Some(source);
+        let line = self.lines.len();
+        self.flush_line(false);
+        let block = self.details.len();
+        self.details.push(DetailsBlock {
+            key,
+            line,
+            body: self.lines.len()..self.lines.len(),
+            summary,
+            open,
+        });
+        if let Some(details) = self.details_stack.last_mut() {
+            details.block = Some(block);
         }
     }
 
+    fn close_details(&mut self) {
+        self.ensure_details_started();
+        self.flush_line(false);
+        let Some(details) = self.details_stack.pop() else {
+            return;
+        };
+        // Trailing blank lines stay visible to separate a collapsed summary
+        // from what follows.
+        let mut end = self.lines.len();
+        while end > 0 && self.lines[end - 1].plain.is_empty() {
+            end -= 1;
+        }
+        if let Some(block) = details.block.and_then(|block| self.details.get_mut(block)) {
+            block.body.end = end.max(block.body.start);
+        }
+        self.blank_line();
+    }
+
+    fn add_image_placeholder(&mut self, alt: &str, target: &str) {
+        let alt = if alt.trim().is_empty() {
+            "image"
+        } else {
+            alt.trim()
+        };
+        self.push_prefix_if_needed();
+        let placeholder = format!("[image: {alt}] ({target})");
+        self.push_text(&placeholder, Style::default().fg(self.options.palette.info));
+        self.note_source();
+    }
+
+    fn add_footnote_reference(&mut self, name: &str) {
+        self.push_prefix_if_needed();
+        let number = self.footnote_number(name);
+        let index = self.links.len();
+        self.links.push(LinkRef {
+            label: format!("[^{name}]"),
+            target: format!("#{}", footnote_slug(name)),
+            line: usize::MAX,
+            source: self.span_for(self.event_range.clone()),
+        });
+        self.current_line_link_indices
+            .push((index, self.current_plain.len()));
+        self.push_text(
+            &format!("[{number}]"),
+            Style::default().fg(self.options.palette.link),
+        );
+        self.note_source();
+    }
+
+    fn start_footnote_definition(&mut self, name: &str) {
+        self.flush_line(false);
+        let number = self.footnote_number(name);
+        self.footnote_stash = Some(FootnoteStash {
+            number,
+            name: name.to_string(),
+            lines: std::mem::take(&mut self.lines),
+            links_start: self.links.len(),
+            toc_start: self.toc.len(),
+            anchors_start: self.anchors.len(),
+            callouts_start: self.callouts.len(),
+            details_start: self.details.len(),
+        });
+        self.push_text(
+            &format!("[{number}] "),
+            Style::default().fg(self.options.palette.link),
+        );
+        self.hang_width = Some(self.current_plain.width());
+    }
+
+    fn end_footnote_definition(&mut self) {
+        self.flush_line(false);
+        let Some(stash) = self.footnote_stash.take() else {
+            return;
+        };
+        let mut lines = std::mem::replace(&mut self.lines, stash.lines);
+        while lines.last().is_some_and(|line| line.plain.is_empty()) {
+            lines.pop();
+        }
+        // Blocks still open past the definition keep their indices.
+        let details_start = self
+            .details_stack
+            .iter()
+            .filter_map(|details| details.block)
+            .map(|block| block.saturating_add(1))
+            .fold(stash.details_start, usize::max)
+            .min(self.details.len());
+        self.footnotes.push(FootnoteDef {
+            number: stash.number,
+            name: stash.name,
+            lines,
+            links: stash.links_start..self.links.len(),
+            toc: self.toc.drain(stash.toc_start..).collect(),
+            anchors: self.anchors.drain(stash.anchors_start..).collect(),
+            callouts: self.callouts.drain(stash.callouts_start..).collect(),
+            details: self.details.drain(details_start..).collect(),
+        });
+    }
+
+    /// Appends collected footnote definitions as a numbered section.
+    fn render_footnotes(&mut self) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+        let mut footnotes = std::mem::take(&mut self.footnotes);
+        footnotes.sort_by_key(|footnote| footnote.number);
+
+        self.blank_line();
+        self.push_text(
+            "Footnotes",
+            Style::default()
+                .fg(self.options.palette.muted)
+                .add_modifier(Modifier::BOLD),
+        );
+        self.flush_line(false);
+        for footnote in footnotes {
+            let base = self.lines.len();
+            for link in &mut self.links[footnote.links] {
+                if link.line != usize::MAX {
+                    link.line = link.line.saturating_add(base);
+                }
+            }
+            self.anchors.push(HeadingAnchor {
+                slug: footnote_slug(&footnote.name),
+                line: base,
+            });
+            self.toc.extend(footnote.toc.into_iter().map(|mut entry| {
+                entry.line = entry.line.saturating_add(base);
+                entry
+            }));
+            self.anchors
+                .extend(footnote.anchors.into_iter().map(|mut anchor| {
+                    anchor.line = anchor.line.saturating_add(base);
+                    anchor
+                }));
+            self.callouts
+                .extend(footnote.callouts.into_iter().map(|mut callout| {
+                    callout.line = callout.line.saturating_add(base);
+                    callout
+                }));
+            self.details
+                .extend(footnote.details.into_iter().map(|mut details| {
+                    details.line = details.line.saturating_add(base);
+                    details.body = details.body.start.saturating_add(base)
+                        ..details.body.end.saturating_add(base);
+                    details
+                }));
+            self.lines.extend(footnote.lines);
+        }
+    }
+
+    fn line_of(&self, byte: usize) -> usize {
+        self.line_starts
+            .partition_point(|start| *start <= byte)
+            .saturating_sub(1)
+    }
+
+    fn span_for(&self, bytes: Range<usize>) -> SourceSpan {
+        let start_line = self.line_of(bytes.start);
+        let end_line = self.line_of(bytes.end.saturating_sub(1).max(bytes.start));
+        SourceSpan {
+            bytes,
+            start_line,
+            end_line,
+        }
+    }
+
+    fn line_span(&self, line: usize) -> SourceSpan {
+        let start = self.line_starts.get(line).copied().unwrap_or(0);
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(start, |next| next.saturating_sub(1));
+        SourceSpan {
+            bytes: start..end,
+            start_line: line,
+            end_line: line,
+        }
+    }
+
+    /// Extends the source range of the line being built with the range of the
+    /// event currently being handled.
+    fn note_source(&mut self) {
+        let range = self.event_range.clone();
+        self.current_source = Some(match self.current_source.take() {
+            Some(current) => current.start.min(range.start)..current.end.max(range.end),
+            None => range,
+        });
+    }
+
     fn push_text(&mut self, text: &str, style: Style) {
         if text.is_empty() {
             return;
@@ -184,20 +990,34 @@ impl<'a> Renderer<'a> {
     fn push_styled_plain_text(&mut self, text: &str) {
         let style = self
             .heading_level
-            .map(heading_style)
-            .unwrap_or_else(|| self.inline.style());
+            .map(|level| heading_style(level, &self.options.palette))
+            .unwrap_or_else(|| self.inline.style(&self.options.palette));
         self.push_text(text, style);
     }
 
     fn push_prefix_if_needed(&mut self) {
-        if !self.current_plain.is_empty() {
+        if !self.current_plain.is_empty() || self.table.as_ref().is_some_and(|t| t.in_cell) {
             return;
         }
 
-        if self.blockquote_depth > 0 {
-            let prefix = "> ".repeat(self.blockquote_depth);
-            self.push_text(&prefix, Style::default().fg(Color::DarkGray));
+        for segment in self.quote_prefix() {
+            self.push_text(&segment.text, segment.style);
+        }
+    }
+
+    /// `> ` per enclosing blockquote, or a colored bar for callouts.
+    fn quote_prefix(&self) -> Vec<StyledSegment> {
+        let mut prefix = Vec::new();
+        let palette = &self.options.palette;
+        for kind in &self.quote_stack {
+            match kind {
+                Some(kind) => {
+                    push_merged(&mut prefix, "┃ ", Style::default().fg(kind.color(palette)))
+                }
+                None => push_merged(&mut prefix, "> ", Style::default().fg(palette.muted)),
+            }
         }
+        prefix
     }
 
     fn flush_line(&mut self, force_empty: bool) {
@@ -205,19 +1025,72 @@ impl<'a> Renderer<'a> {
             return;
         }
 
-        let line_index = self.lines.len();
-        for idx in &self.current_line_link_indices {
-            if let Some(link) = self.links.get_mut(*idx) {
-                link.line = line_index;
+        let source = self.current_source.take().map(|range| self.span_for(range));
+        let segments = std::mem::take(&mut self.current_segments);
+        let plain = std::mem::take(&mut self.current_plain);
+        let links = std::mem::take(&mut self.current_line_link_indices);
+        let hang_width = self.hang_width.take();
+
+        let Some(width) = self.width.filter(|width| plain.width() > *width) else {
+            let line_index = self.lines.len();
+            for (idx, _) in links {
+                if let Some(link) = self.links.get_mut(idx) {
+                    link.line = line_index;
+                }
+            }
+            self.lines.push(RenderedLine {
+                segments,
+                plain,
+                source,
+                wrapped: false,
+            });
+            return;
+        };
+
+        // Continuation lines repeat the blockquote prefix and hang under the
+        // text after a list marker.
+        let mut quote = self.quote_prefix();
+        let mut quote_text: String = quote.iter().map(|segment| segment.text.as_str()).collect();
+        if !plain.starts_with(&quote_text) {
+            quote.clear();
+            quote_text.clear();
+        }
+        let mut indent = hang_width.unwrap_or(0).max(quote_text.width());
+        if indent > width / 2 {
+            indent = quote_text.width().min(width / 2);
+        }
+        let first_line = self.lines.len();
+        let wrapped = wrap_styled(&segments, width, width.saturating_sub(indent));
+        for (idx, link_offset) in links {
+            let row = wrapped
+                .iter()
+                .rposition(|(start, _)| *start <= link_offset)
+                .unwrap_or(0);
+            if let Some(link) = self.links.get_mut(idx) {
+                link.line = first_line + row;
             }
         }
 
-        let line = RenderedLine {
-            segments: std::mem::take(&mut self.current_segments),
-            plain: std::mem::take(&mut self.current_plain),
-        };
-        self.current_line_link_indices.clear();
-        self.lines.push(line);
+        for (row, (_, mut line_segments)) in wrapped.into_iter().enumerate() {
+            if row > 0 {
+                let mut prefix = quote.clone();
+                let pad = indent.saturating_sub(quote_text.width());
+                if pad > 0 {
+                    push_merged(&mut prefix, &" ".repeat(pad), Style::default());
+                }
+                prefix.append(&mut line_segments);
+                line_segments = prefix;
+            }
+            self.lines.push(RenderedLine {
+                plain: line_segments
+                    .iter()
+                    .map(|segment| segment.text.as_str())
+                    .collect(),
+                segments: line_segments,
+                source: source.clone(),
+                wrapped: row > 0,
+            });
+        }
     }
 
     fn blank_line(&mut self) {
@@ -227,6 +1100,20 @@ impl<'a> Renderer<'a> {
         self.flush_line(true);
     }
 
+    /// Deduplicates slugs the way GitHub does: the second `Setup` heading
+    /// becomes `setup-1`, the third `setup-2`, and so on.
+    fn unique_slug(&mut self, title: &str) -> String {
+        let base = heading_slug(title);
+        let mut slug = base.clone();
+        while self.slug_counts.contains_key(&slug) {
+            let count = self.slug_counts.entry(base.clone()).or_default();
+            *count = count.saturating_add(1);
+            slug = format!("{base}-{count}");
+        }
+        self.slug_counts.insert(slug.clone(), 0);
+        slug
+    }
+
     fn heading_level_u8(level: HeadingLevel) -> u8 {
         match level {
             HeadingLevel::H1 => 1,
@@ -239,20 +1126,24 @@ impl<'a> Renderer<'a> {
     }
 
     fn handle_start(&mut self, tag: Tag<'_>) {
+        if !self.in_details_summary() && !matches!(tag, Tag::HtmlBlock) {
+            self.ensure_details_started();
+        }
         if let Some(table) = self.table.as_mut() {
             match tag {
                 Tag::TableHead => {
                     table.in_head = true;
+                    table.header_source = Some(self.event_range.clone());
                     return;
                 }
                 Tag::TableRow => {
                     table.in_row = true;
                     table.current_row.clear();
+                    table.row_sources.push(self.event_range.clone());
                     return;
                 }
                 Tag::TableCell => {
                     table.in_cell = true;
-                    table.current_cell.clear();
                     return;
                 }
                 _ => {}
@@ -263,16 +1154,30 @@ impl<'a> Renderer<'a> {
             Tag::Heading { level, .. } => {
                 self.flush_line(false);
                 self.heading_level = Some(Self::heading_level_u8(level));
+                self.heading_source = self.event_range.clone();
+                self.heading_line = self.lines.len();
+                self.note_source();
             }
-            Tag::BlockQuote(_) => {
+            Tag::BlockQuote(kind) => {
                 self.flush_line(false);
-                self.blockquote_depth = self.blockquote_depth.saturating_add(1);
+                let kind = kind.map(CalloutKind::from_blockquote);
+                self.quote_stack.push(kind);
+                if let Some(kind) = kind {
+                    self.start_callout(kind);
+                }
             }
             Tag::CodeBlock(kind) => {
                 self.flush_line(false);
+                let block_line = self.line_of(self.event_range.start);
                 let lang = match kind {
-                    CodeBlockKind::Fenced(name) => name.to_string(),
-                    CodeBlockKind::Indented => String::new(),
+                    CodeBlockKind::Fenced(name) => {
+                        self.code_block_first_line = block_line.saturating_add(1);
+                        name.to_string()
+                    }
+                    CodeBlockKind::Indented => {
+                        self.code_block_first_line = block_line;
+                        String::new()
+                    }
                 };
                 self.code_block_lang = Some(lang);
                 self.code_block_buf.clear();
@@ -303,8 +1208,9 @@ impl<'a> Renderer<'a> {
 
                 self.push_text(
                     &format!("{indent}{bullet}"),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(self.options.palette.muted),
                 );
+                self.hang_width = Some(self.current_plain.width());
             }
             Tag::Emphasis => self.inline.emphasis = self.inline.emphasis.saturating_add(1),
             Tag::Strong => self.inline.strong = self.inline.strong.saturating_add(1),
@@ -316,6 +1222,8 @@ impl<'a> Renderer<'a> {
                 self.active_link = Some(ActiveLink {
                     target: dest_url.to_string(),
                     text: String::new(),
+                    source: self.event_range.clone(),
+                    offset: self.current_plain.len(),
                 });
             }
             Tag::Image { dest_url, .. } => {
@@ -324,6 +1232,15 @@ impl<'a> Renderer<'a> {
                     alt: String::new(),
                 });
             }
+            Tag::FootnoteDefinition(name) => self.start_footnote_definition(&name),
+            Tag::HtmlBlock => self.flush_line(false),
+            Tag::MetadataBlock(kind) => {
+                let format = match kind {
+                    MetadataBlockKind::YamlStyle => FrontMatterFormat::Yaml,
+                    MetadataBlockKind::PlusesStyle => FrontMatterFormat::Toml,
+                };
+                self.metadata_block = Some((format, String::new()));
+            }
             Tag::Table(alignments) => {
                 self.flush_line(false);
                 self.table = Some(TableState::new(alignments));
@@ -337,10 +1254,14 @@ impl<'a> Renderer<'a> {
             match tag {
                 TagEnd::TableCell => {
                     if table.in_cell {
-                        table
-                            .current_row
-                            .push(table.current_cell.trim().to_string());
-                        table.current_cell.clear();
+                        let mut segments = std::mem::take(&mut self.current_segments);
+                        trim_segments(&mut segments);
+                        table.current_row.push(TableCell {
+                            segments,
+                            links: std::mem::take(&mut self.current_line_link_indices),
+                        });
+                        self.current_plain.clear();
+                        self.current_source = None;
                         table.in_cell = false;
                     }
                     return;
@@ -358,12 +1279,15 @@ impl<'a> Renderer<'a> {
                     return;
                 }
                 TagEnd::TableHead => {
+                    if !table.current_row.is_empty() {
+                        table.headers = std::mem::take(&mut table.current_row);
+                    }
                     table.in_head = false;
                     return;
                 }
                 TagEnd::Table => {
                     let table_state = self.table.take().unwrap_or_default();
-                    self.render_table(&table_state);
+                    self.render_table(table_state);
                     self.blank_line();
                     return;
                 }
@@ -378,19 +1302,27 @@ impl<'a> Renderer<'a> {
             }
             TagEnd::Heading(level) => {
                 self.flush_line(false);
-                let line_idx = self.lines.len().saturating_sub(1);
-                let title = self
-                    .lines
-                    .get(line_idx)
-                    .map(|line| line.plain.trim().to_string())
-                    .unwrap_or_default();
+                let line_idx = self.heading_line.min(self.lines.len().saturating_sub(1));
+                let title = self.lines[line_idx..]
+                    .iter()
+                    .map(|line| line.plain.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ");
 
                 let level_u8 = Self::heading_level_u8(level);
+                let slug = self.unique_slug(&title);
+                self.anchors.push(HeadingAnchor {
+                    slug: slug.clone(),
+                    line: line_idx,
+                });
                 if level_u8 <= 3 && !title.is_empty() {
+                    let source = self.span_for(self.heading_source.clone());
                     self.toc.push(TocEntry {
                         level: level_u8,
                         title,
+                        slug,
                         line: line_idx,
+                        source,
                     });
                 }
                 self.heading_level = None;
@@ -398,7 +1330,9 @@ impl<'a> Renderer<'a> {
             }
             TagEnd::BlockQuote => {
                 self.flush_line(false);
-                self.blockquote_depth = self.blockquote_depth.saturating_sub(1);
+                if self.quote_stack.pop().flatten().is_some() {
+                    self.end_callout();
+                }
                 self.blank_line();
             }
             TagEnd::CodeBlock => {
@@ -432,21 +1366,28 @@ impl<'a> Renderer<'a> {
                         label,
                         target: link.target,
                         line: usize::MAX,
+                        source: self.span_for(link.source),
                     };
                     let index = self.links.len();
                     self.links.push(link_ref);
-                    self.current_line_link_indices.push(index);
+                    self.current_line_link_indices.push((index, link.offset));
+                }
+            }
+            TagEnd::FootnoteDefinition => self.end_footnote_definition(),
+            TagEnd::MetadataBlock(_) => {
+                if let Some((format, text)) = self.metadata_block.take() {
+                    self.front_matter = Some(FrontMatter::parse(format, &text));
                 }
             }
             TagEnd::Image => {
                 if let Some(image) = self.active_image.take() {
-                    let alt = if image.alt.trim().is_empty() {
-                        "image".to_string()
-                    } else {
-                        image.alt.trim().to_string()
-                    };
-                    let placeholder = format!("[image: {alt}] ({})", image.target);
-                    self.push_text(&placeholder, Style::default().fg(Color::LightBlue));
+                    self.add_image_placeholder(&image.alt, &image.target);
+                }
+            }
+            TagEnd::HtmlBlock => {
+                self.flush_line(false);
+                if !self.lines.is_empty() {
+                    self.blank_line();
                 }
             }
             _ => {}
@@ -454,25 +1395,30 @@ impl<'a> Renderer<'a> {
     }
 
     fn add_text(&mut self, text: &str) {
+        if let Some((_, block)) = self.metadata_block.as_mut() {
+            block.push_str(text);
+            return;
+        }
         if self.code_block_lang.is_some() {
             self.code_block_buf.push_str(text);
             return;
         }
 
-        if let Some(table) = self.table.as_mut() {
-            if table.in_cell {
-                table.current_cell.push_str(text);
-                return;
-            }
-        }
-
         if let Some(image) = self.active_image.as_mut() {
             image.alt.push_str(text);
             return;
         }
+        if self.in_details_summary() {
+            if let Some(details) = self.details_stack.last_mut() {
+                details.summary.push_str(text);
+            }
+            return;
+        }
+        self.ensure_details_started();
 
         self.push_prefix_if_needed();
         self.push_styled_plain_text(text);
+        self.note_source();
         if let Some(link) = self.active_link.as_mut() {
             link.text.push_str(text);
         }
@@ -483,14 +1429,7 @@ impl<'a> Renderer<'a> {
             self.code_block_buf.push('\n');
             return;
         }
-        if let Some(table) = self.table.as_mut() {
-            if table.in_cell {
-                table.current_cell.push(' ');
-                return;
-            }
-        }
-
-        self.push_text(" ", self.inline.style());
+        self.push_text(" ", self.inline.style(&self.options.palette));
         if let Some(link) = self.active_link.as_mut() {
             link.text.push(' ');
         }
@@ -509,17 +1448,12 @@ impl<'a> Renderer<'a> {
             self.code_block_buf.push_str(code);
             return;
         }
-        if let Some(table) = self.table.as_mut() {
-            if table.in_cell {
-                table.current_cell.push_str(code);
-                return;
-            }
-        }
         self.push_prefix_if_needed();
         let style = Style::default()
-            .fg(Color::LightYellow)
+            .fg(self.options.palette.inline_code)
             .add_modifier(Modifier::BOLD);
         self.push_text(code, style);
+        self.note_source();
         if let Some(link) = self.active_link.as_mut() {
             link.text.push_str(code);
         }
@@ -527,7 +1461,12 @@ impl<'a> Renderer<'a> {
 
     fn add_rule(&mut self) {
         self.flush_line(false);
-        self.push_text(HORIZONTAL_RULE, Style::default().fg(Color::DarkGray));
+        let rule: String = HORIZONTAL_RULE
+            .chars()
+            .take(self.width.unwrap_or(usize::MAX))
+            .collect();
+        self.push_text(&rule, Style::default().fg(self.options.palette.muted));
+        self.note_source();
         self.flush_line(false);
         self.blank_line();
     }
@@ -535,7 +1474,11 @@ impl<'a> Renderer<'a> {
     fn add_task_marker(&mut self, done: bool) {
         self.push_prefix_if_needed();
         let marker = if done { "[x] " } else { "[ ] " };
-        self.push_text(marker, Style::default().fg(Color::DarkGray));
+        self.push_text(marker, Style::default().fg(self.options.palette.muted));
+        if self.hang_width.is_some() {
+            self.hang_width = Some(self.current_plain.width());
+        }
+        self.note_source();
     }
 
     fn render_code_block(&mut self, lang: &str, code: &str) {
@@ -549,7 +1492,7 @@ impl<'a> Renderer<'a> {
 
         let mut highlighter = HighlightLines::new(syntax, self.theme);
 
-        for line in LinesWithEndings::from(code) {
+        for (offset, line) in LinesWithEndings::from(code).enumerate() {
             let mut clean = line;
             if let Some(trimmed) = clean.strip_suffix('\n') {
                 clean = trimmed;
@@ -558,14 +1501,14 @@ impl<'a> Renderer<'a> {
                 clean = trimmed;
             }
 
-            self.push_text("  ", Style::default().fg(Color::DarkGray));
+            self.push_text("  ", Style::default().fg(self.options.palette.muted));
 
             let highlighted_tokens = highlighter
                 .highlight_line(line, self.syntax_set)
                 .unwrap_or_default();
 
             if highlighted_tokens.is_empty() {
-                self.push_text(clean, Style::default().fg(Color::LightGreen));
+                self.push_text(clean, Style::default().fg(self.options.palette.code_block));
             } else {
                 for (syn_style, token) in highlighted_tokens {
                     let style = Style::default()
@@ -584,95 +1527,405 @@ impl<'a> Renderer<'a> {
                 }
             }
 
+            let span = self.line_span(self.code_block_first_line.saturating_add(offset));
+            self.current_source = Some(span.bytes);
             self.flush_line(false);
         }
     }
 
-    fn render_table(&mut self, table: &TableState) {
-        let mut rows: Vec<Vec<String>> = Vec::new();
-        if !table.headers.is_empty() {
-            rows.push(table.headers.clone());
+    fn render_table(&mut self, table: TableState) {
+        let has_header = !table.headers.is_empty();
+        let mut rows: Vec<(Vec<TableCell>, Option<Range<usize>>)> = Vec::new();
+        if has_header {
+            rows.push((table.headers, table.header_source));
         }
-        rows.extend(table.rows.clone());
-
-        if rows.is_empty() {
+        rows.extend(
+            table
+                .rows
+                .into_iter()
+                .zip(table.row_sources.into_iter().map(Some)),
+        );
+
+        let col_count = rows.iter().map(|(row, _)| row.len()).max().unwrap_or(0);
+        if col_count == 0 {
             return;
         }
+        for (row, _) in &mut rows {
+            row.resize_with(col_count, TableCell::default);
+        }
 
-        let col_count = rows.iter().map(Vec::len).max().unwrap_or(0);
-        if col_count == 0 {
-            return;
+        let mut natural = vec![1usize; col_count];
+        let mut shortest = vec![1usize; col_count];
+        for (row, _) in &rows {
+            for (idx, cell) in row.iter().enumerate() {
+                natural[idx] = natural[idx].max(cell.width());
+                shortest[idx] = shortest[idx].max(cell.longest_word());
+            }
         }
+        // Every table line starts under the blockquote bars and list indent.
+        let mut lead = self.quote_prefix();
+        if !self.list_stack.is_empty() {
+            push_merged(
+                &mut lead,
+                &"  ".repeat(self.list_stack.len()),
+                Style::default(),
+            );
+        }
+        let lead_width: usize = lead.iter().map(|segment| segment.text.width()).sum();
+        // Borders take "│ " before each column, " │ " between and " │" after.
+        let available = self.width.map(|width| {
+            width
+                .saturating_sub(lead_width)
+                .saturating_sub(col_count.saturating_mul(3).saturating_add(1))
+        });
+        let (widths, truncate) = fit_column_widths(&natural, &shortest, available);
+        // Table lines are laid out to fit already; when they cannot, they are
+        // cut short rather than wrapped, which would tear the borders apart.
+        let width = self.width.take();
+
+        let border_style = Style::default().fg(self.options.palette.muted);
+        let header_style = Style::default()
+            .fg(self.options.palette.highlight)
+            .add_modifier(Modifier::BOLD);
 
-        for row in &mut rows {
-            while row.len() < col_count {
-                row.push(String::new());
+        self.push_segments(&lead);
+        self.push_text(&table_border(&widths, '┌', '┬', '┐'), border_style);
+        self.flush_line(false);
+        for (row_idx, (row, source)) in rows.iter().enumerate() {
+            let is_header = row_idx == 0 && has_header;
+            let wrapped: Vec<Vec<Vec<StyledSegment>>> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    if truncate {
+                        vec![truncate_segments(&cell.segments, *width)]
+                    } else {
+                        wrap_segments(&cell.segments, *width)
+                    }
+                })
+                .collect();
+            let height = wrapped.iter().map(Vec::len).max().unwrap_or(1).max(1);
+
+            for line_idx in 0..height {
+                self.push_segments(&lead);
+                self.push_text("│ ", border_style);
+                for (col, cell_lines) in wrapped.iter().enumerate() {
+                    if col > 0 {
+                        self.push_text(" │ ", border_style);
+                    }
+                    let content = cell_lines.get(line_idx).cloned().unwrap_or_default();
+                    let used: usize = content.iter().map(|segment| segment.text.width()).sum();
+                    let pad = widths[col].saturating_sub(used);
+                    let align = table
+                        .alignments
+                        .get(col)
+                        .copied()
+                        .unwrap_or(Alignment::None);
+                    let (left, right) = match align {
+                        Alignment::Right => (pad, 0),
+                        Alignment::Center => (pad / 2, pad - pad / 2),
+                        Alignment::Left | Alignment::None => (0, pad),
+                    };
+                    self.push_text(&" ".repeat(left), Style::default());
+                    for segment in content {
+                        let style = if is_header {
+                            header_style.patch(segment.style)
+                        } else {
+                            segment.style
+                        };
+                        self.push_text(&segment.text, style);
+                    }
+                    self.push_text(&" ".repeat(right), Style::default());
+                }
+                self.push_text(" │", border_style);
+                if line_idx == 0 {
+                    self.current_line_link_indices = row
+                        .iter()
+                        .flat_map(|cell| cell.links.iter().map(|(idx, _)| (*idx, 0)))
+                        .collect();
+                }
+                self.current_source = source.clone();
+                self.flush_line(false);
+            }
+
+            if is_header && rows.len() > 1 {
+                self.push_segments(&lead);
+                self.push_text(&table_border(&widths, '├', '┼', '┤'), border_style);
+                self.flush_line(false);
             }
         }
+        self.push_segments(&lead);
+        self.push_text(&table_border(&widths, '└', '┴', '┘'), border_style);
+        self.flush_line(false);
+        self.width = width;
+    }
+
+    fn push_segments(&mut self, segments: &[StyledSegment]) {
+        for segment in segments {
+            self.push_text(&segment.text, segment.style);
+        }
+    }
+}
 
-        let mut widths = vec![3usize; col_count];
-        for row in &rows {
-            for (idx, cell) in row.iter().enumerate() {
-                widths[idx] = widths[idx].max(cell.chars().count());
+/// Horizontal table rule such as `┌─────┬───┐` for the given column widths.
+fn table_border(widths: &[usize], left: char, junction: char, right: char) -> String {
+    let mut line = String::new();
+    line.push(left);
+    for (idx, width) in widths.iter().enumerate() {
+        if idx > 0 {
+            line.push(junction);
+        }
+        line.push_str(&"─".repeat(width.saturating_add(2)));
+    }
+    line.push(right);
+    line
+}
+
+/// Column widths that fit `available` cells: natural widths when they fit,
+/// otherwise each column keeps its longest word where possible and the rest
+/// of the space is shared in proportion to how much each column wants.
+/// The flag is set when not even the longest words fit, so cells have to be
+/// truncated instead of wrapped.
+fn fit_column_widths(
+    natural: &[usize],
+    longest_word: &[usize],
+    available: Option<usize>,
+) -> (Vec<usize>, bool) {
+    let total: usize = natural.iter().sum();
+    let Some(available) = available.filter(|available| total > *available) else {
+        return (natural.to_vec(), false);
+    };
+    let available = available.max(natural.len());
+
+    let mut widths: Vec<usize> = natural
+        .iter()
+        .zip(longest_word)
+        .map(|(natural, word)| (*word).min(*natural).max(1))
+        .collect();
+    let minimum: usize = widths.iter().sum();
+    if minimum > available {
+        // Not even the longest words fit; share the space evenly.
+        let even = available / natural.len();
+        let mut extra = available % natural.len();
+        let widths = natural
+            .iter()
+            .map(|natural| {
+                let bonus = usize::from(extra > 0);
+                extra = extra.saturating_sub(1);
+                (even + bonus).min(*natural).max(1)
+            })
+            .collect();
+        return (widths, true);
+    }
+
+    let mut remaining = available - minimum;
+    let wants: Vec<usize> = natural
+        .iter()
+        .zip(&widths)
+        .map(|(natural, width)| natural.saturating_sub(*width))
+        .collect();
+    let total_wants: usize = wants.iter().sum();
+    let budget = remaining;
+    for (width, want) in widths.iter_mut().zip(&wants) {
+        let share = (budget * want)
+            .checked_div(total_wants)
+            .unwrap_or(0)
+            .min(*want);
+        *width += share;
+        remaining -= share;
+    }
+    for (width, natural) in widths.iter_mut().zip(natural) {
+        if remaining == 0 {
+            break;
+        }
+        if *width < *natural {
+            *width += 1;
+            remaining -= 1;
+        }
+    }
+    (widths, false)
+}
+
+/// Styled text cut to at most `width` display columns, ending in `…` when
+/// anything was dropped.
+fn truncate_segments(segments: &[StyledSegment], width: usize) -> Vec<StyledSegment> {
+    let total: usize = segments.iter().map(|segment| segment.text.width()).sum();
+    if total <= width {
+        return segments.to_vec();
+    }
+    let mut out = Vec::new();
+    let mut budget = width.saturating_sub(1);
+    let mut last_style = Style::default();
+    for segment in segments {
+        let mut text = String::new();
+        for ch in segment.text.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            if ch_width > budget {
+                budget = 0;
+                break;
             }
+            budget -= ch_width;
+            text.push(ch);
         }
+        last_style = segment.style;
+        if !text.is_empty() {
+            push_merged(&mut out, &text, segment.style);
+        }
+        if budget == 0 {
+            break;
+        }
+    }
+    if width > 0 {
+        push_merged(&mut out, "…", last_style);
+    }
+    out
+}
 
-        let Some((header, body_rows)) = rows.split_first() else {
-            return;
-        };
+/// Strips leading whitespace from the first segment and trailing whitespace
+/// from the last, dropping segments that become empty.
+fn trim_segments(segments: &mut Vec<StyledSegment>) {
+    while let Some(first) = segments.first_mut() {
+        first.text = first.text.trim_start().to_string();
+        if !first.text.is_empty() {
+            break;
+        }
+        segments.remove(0);
+    }
+    while let Some(last) = segments.last_mut() {
+        last.text = last.text.trim_end().to_string();
+        if !last.text.is_empty() {
+            break;
+        }
+        segments.pop();
+    }
+}
 
-        let line = Self::format_table_row(header, &widths);
-        self.push_text(&line, Style::default().fg(Color::Yellow));
-        self.flush_line(false);
+fn push_merged(line: &mut Vec<StyledSegment>, text: &str, style: Style) {
+    match line.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => line.push(StyledSegment {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
 
-        let mut sep_cells = Vec::with_capacity(col_count);
-        for (idx, width) in widths.iter().enumerate() {
-            let align = table
-                .alignments
-                .get(idx)
-                .copied()
-                .unwrap_or(Alignment::None);
-            let sep = match align {
-                Alignment::Left => format!(":{}", "-".repeat(width.saturating_sub(1))),
-                Alignment::Center => {
-                    if *width <= 1 {
-                        ":".to_string()
-                    } else {
-                        format!(":{}:", "-".repeat(width.saturating_sub(2)))
+/// Greedy word wrap of styled text into lines of at most `width` display
+/// columns. Words wider than a line are broken between characters, and
+/// whitespace at the start of continuation lines is dropped.
+pub(crate) fn wrap_segments(segments: &[StyledSegment], width: usize) -> Vec<Vec<StyledSegment>> {
+    wrap_styled(segments, width, width)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// Word wrap where the first line holds `first_width` columns and the rest
+/// `rest_width`. Each line comes with the byte offset into the concatenated
+/// segment text where it starts.
+fn wrap_styled(
+    segments: &[StyledSegment],
+    first_width: usize,
+    rest_width: usize,
+) -> Vec<(usize, Vec<StyledSegment>)> {
+    let mut lines: Vec<(usize, Vec<StyledSegment>)> = vec![(0, Vec::new())];
+    let rest_width = rest_width.max(1);
+    let mut width = first_width.max(1);
+    let mut used = 0usize;
+    let mut offset = 0usize;
+
+    let break_line = |lines: &mut Vec<(usize, Vec<StyledSegment>)>, at: usize| {
+        if let Some((_, line)) = lines.last_mut() {
+            trim_trailing_whitespace(line);
+        }
+        lines.push((at, Vec::new()));
+        (rest_width, 0)
+    };
+
+    for segment in segments {
+        for token in split_words(&segment.text) {
+            let token_width = token.width();
+            let is_space = token.chars().all(char::is_whitespace);
+            if is_space {
+                if used == 0 && lines.len() > 1 {
+                    if let Some((start, _)) = lines.last_mut() {
+                        *start = offset + token.len();
                     }
+                    offset += token.len();
+                    continue;
                 }
-                Alignment::Right => format!("{}:", "-".repeat(width.saturating_sub(1))),
-                Alignment::None => "-".repeat(*width),
-            };
-            sep_cells.push(sep);
+                if used + token_width > width {
+                    (width, used) = break_line(&mut lines, offset + token.len());
+                    offset += token.len();
+                    continue;
+                }
+            } else if used > 0 && used + token_width > width {
+                (width, used) = break_line(&mut lines, offset);
+            }
+
+            if token_width <= width.saturating_sub(used) {
+                if let Some((_, line)) = lines.last_mut() {
+                    push_merged(line, token, segment.style);
+                }
+                used += token_width;
+                offset += token.len();
+                continue;
+            }
+
+            for (idx, ch) in token.char_indices() {
+                let ch_width = ch.width().unwrap_or(0);
+                if used > 0 && used + ch_width > width {
+                    (width, used) = break_line(&mut lines, offset + idx);
+                }
+                if let Some((_, line)) = lines.last_mut() {
+                    push_merged(line, ch.encode_utf8(&mut [0u8; 4]), segment.style);
+                }
+                used += ch_width;
+            }
+            offset += token.len();
         }
-        let sep_line = Self::format_table_row(&sep_cells, &widths);
-        self.push_text(&sep_line, Style::default().fg(Color::DarkGray));
-        self.flush_line(false);
+    }
+    if lines.len() > 1 && lines.last().is_some_and(|(_, line)| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
 
-        for row in body_rows {
-            let row_line = Self::format_table_row(row, &widths);
-            self.push_text(&row_line, Style::default());
-            self.flush_line(false);
+fn trim_trailing_whitespace(line: &mut Vec<StyledSegment>) {
+    while let Some(last) = line.last_mut() {
+        let trimmed = last.text.trim_end().len();
+        last.text.truncate(trimmed);
+        if !last.text.is_empty() {
+            break;
         }
+        line.pop();
     }
+}
 
-    fn format_table_row(row: &[String], widths: &[usize]) -> String {
-        let mut output = String::from("| ");
-        for (idx, cell) in row.iter().enumerate() {
-            let width = widths[idx];
-            let padded = format!("{cell:<width$}");
-            output.push_str(&padded);
-            output.push_str(" | ");
+/// Splits text into alternating runs of whitespace and non-whitespace.
+fn split_words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0usize;
+    let mut in_space = None;
+    for (idx, ch) in text.char_indices() {
+        let space = ch.is_whitespace();
+        if in_space.is_some_and(|previous| previous != space) {
+            tokens.push(&text[start..idx]);
+            start = idx;
         }
-        output
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
     }
+    tokens
 }
 
 pub(crate) fn render_markdown(
     source: &str,
     syntax_set: &SyntaxSet,
     theme: &Theme,
+    render_options: &RenderOptions,
 ) -> RenderedDocument {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
@@ -680,18 +1933,23 @@ pub(crate) fn render_markdown(
     options.insert(Options::ENABLE_FOOTNOTES);
     options.insert(Options::ENABLE_TASKLISTS);
     options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    options.insert(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
+    options.insert(Options::ENABLE_GFM);
 
     let parser = MdParser::new_ext(source, options);
-    let mut renderer = Renderer::new(syntax_set, theme);
+    let mut renderer = Renderer::new(syntax_set, theme, source, render_options);
 
-    for event in parser {
+    for (event, range) in parser.into_offset_iter() {
+        renderer.event_range = range;
         match event {
             MdEvent::Start(tag) => renderer.handle_start(tag),
             MdEvent::End(tag) => renderer.handle_end(tag),
             MdEvent::Text(text) => renderer.add_text(&text),
             MdEvent::Code(code) => renderer.add_inline_code(&code),
-            MdEvent::Html(html) | MdEvent::InlineHtml(html) => renderer.add_text(&html),
-            MdEvent::FootnoteReference(name) => renderer.add_text(&format!("[^{name}]")),
+            MdEvent::Html(html) => renderer.add_html(&html, true),
+            MdEvent::InlineHtml(html) => renderer.add_html(&html, false),
+            MdEvent::FootnoteReference(name) => renderer.add_footnote_reference(&name),
             MdEvent::SoftBreak => renderer.soft_break(),
             MdEvent::HardBreak => renderer.hard_break(),
             MdEvent::Rule => renderer.add_rule(),
@@ -703,26 +1961,494 @@ pub(crate) fn render_markdown(
     renderer.finish()
 }
 
+/// Anchor slug for footnote `name`, e.g. `fn-note`.
+fn footnote_slug(name: &str) -> String {
+    format!("fn-{}", heading_slug(name))
+}
+
+/// GitHub-compatible heading slug: lowercase, punctuation dropped, spaces
+/// turned into hyphens.
+pub(crate) fn heading_slug(title: &str) -> String {
+    title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            _ if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The shown lines as plain text; bodies of collapsed `<details>` blocks
+/// are left out.
 pub(crate) fn plain_render(doc: &RenderedDocument) -> String {
-    let mut out = String::new();
-    for (idx, line) in doc.lines.iter().enumerate() {
-        out.push_str(&line.plain);
-        if idx + 1 < doc.lines.len() {
-            out.push('\n');
-        }
-    }
-    out
+    let shown: Vec<&str> = doc
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !doc.is_hidden(*idx))
+        .map(|(_, line)| line.plain.as_str())
+        .collect();
+    shown.join("\n")
 }
-fn heading_style(level: u8) -> Style {
-    match level {
-        1 => Style::default()
-            .fg(Color::Yellow)
-            .add_modifier(Modifier::BOLD),
-        2 => Style::default()
-            .fg(Color::LightMagenta)
-            .add_modifier(Modifier::BOLD),
-        _ => Style::default()
-            .fg(Color::LightCyan)
-            .add_modifier(Modifier::BOLD),
+
+fn comment_style(palette: &Palette) -> Style {
+    Style::default()
+        .fg(palette.muted)
+        .add_modifier(Modifier::ITALIC)
+}
+
+/// Counts an opening or closing inline HTML tag.
+fn adjust_depth(depth: &mut usize, closing: bool) {
+    *depth = if closing {
+        depth.saturating_sub(1)
+    } else {
+        depth.saturating_add(1)
+    };
+}
+
+fn heading_style(level: u8, palette: &Palette) -> Style {
+    let [h1, h2, rest] = palette.headings;
+    let color = match level {
+        1 => h1,
+        2 => h2,
+        _ => rest,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntect::highlighting::ThemeSet;
+
+    fn render(source: &str) -> RenderedDocument {
+        render_at(source, None)
+    }
+
+    fn render_at(source: &str, width: Option<usize>) -> RenderedDocument {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme_set = ThemeSet::load_defaults();
+        let theme = theme_set
+            .themes
+            .values()
+            .next()
+            .cloned()
+            .unwrap_or_default();
+        let options = RenderOptions {
+            width,
+            ..RenderOptions::default()
+        };
+        render_markdown(source, &syntax_set, &theme, &options)
+    }
+
+    #[test]
+    fn heading_slug_matches_github_rules() {
+        assert_eq!(heading_slug("Getting Started"), "getting-started");
+        assert_eq!(heading_slug("What's new in v1.2?"), "whats-new-in-v12");
+        assert_eq!(
+            heading_slug("snake_case & kebab-case"),
+            "snake_case--kebab-case"
+        );
+        assert_eq!(heading_slug("Über Café"), "über-café");
+    }
+
+    #[test]
+    fn render_markdown_records_source_lines() {
+        let source = "# Title\n\nfirst line\nsecond line\n\n```rust\nlet a = 1;\nlet b = 2;\n```\n\n- [ ] task\n";
+        let doc = render(source);
+        let spans: Vec<(&str, Option<(usize, usize)>)> = doc
+            .lines
+            .iter()
+            .map(|line| {
+                (
+                    line.plain.as_str(),
+                    line.source.as_ref().map(|s| (s.start_line, s.end_line)),
+                )
+            })
+            .collect();
+
+        assert_eq!(spans[0], ("Title", Some((0, 0))));
+        assert_eq!(spans[1], ("", None));
+        assert_eq!(spans[2], ("first line second line", Some((2, 3))));
+        assert_eq!(spans[4].1, Some((6, 6)));
+        assert_eq!(spans[5].1, Some((7, 7)));
+        assert_eq!(spans[7], ("- [ ] task", Some((10, 10))));
+        assert_eq!(doc.toc[0].source.start_line, 0);
+        assert_eq!(
+            &source[doc.lines[4].source.clone().unwrap().bytes],
+            "let a = 1;"
+        );
+    }
+
+    #[test]
+    fn render_markdown_deduplicates_heading_slugs() {
+        let doc = render("# Setup\n\n## Setup\n\n#### Setup\n\n## Setup 1\n");
+        let slugs: Vec<&str> = doc.anchors.iter().map(|a| a.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["setup", "setup-1", "setup-2", "setup-1-1"]);
+        assert_eq!(doc.toc.len(), 3);
+        assert_eq!(doc.anchor_line("Setup-2"), Some(doc.anchors[2].line));
+    }
+
+    #[test]
+    fn table_header_cells_are_kept() {
+        // pulldown-cmark sends header cells without a `TableRow`; they used
+        // to be dropped when the head ended.
+        let source = "| Key | Value |\n|-----|-------|\n";
+        let doc = render(source);
+        assert_eq!(doc.lines[1].plain, "│ Key │ Value │");
+        let span = doc.lines[1].source.as_ref().unwrap();
+        assert_eq!((span.start_line, span.end_line), (0, 0));
+
+        let doc = render("| Key | Value |\n|-----|-------|\n| a | b |\n");
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert_eq!(plain[1], "│ Key │ Value │");
+        assert_eq!(plain[3], "│ a   │ b     │");
+    }
+
+    #[test]
+    fn tables_render_box_borders_with_styled_cells_and_links() {
+        let source = "| Name | Link |\n|------|-----:|\n| `cfg` | [docs](https://example.com) |\n";
+        let doc = render(source);
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert_eq!(
+            plain[..5],
+            [
+                "┌──────┬──────┐",
+                "│ Name │ Link │",
+                "├──────┼──────┤",
+                "│ cfg  │ docs │",
+                "└──────┴──────┘",
+            ]
+        );
+
+        assert_eq!(doc.links.len(), 1);
+        assert_eq!(doc.links[0].target, "https://example.com");
+        assert_eq!(doc.links[0].line, 3);
+        let code = doc.lines[3]
+            .segments
+            .iter()
+            .find(|segment| segment.text == "cfg")
+            .unwrap();
+        assert_eq!(code.style.fg, Some(Color::LightYellow));
+        assert_eq!(doc.lines[3].source.as_ref().unwrap().start_line, 2);
+    }
+
+    #[test]
+    fn tables_wrap_cells_to_fit_width_and_honor_alignment() {
+        let source =
+            "| Key | Description | N |\n|:---:|---|--:|\n| a | one two three four five six | 7 |\n";
+        let doc = render_at(source, Some(30));
+        assert!(doc.lines.iter().all(|line| line.plain.width() <= 30));
+        let body: Vec<&str> = doc.lines[3..]
+            .iter()
+            .map(|line| line.plain.as_str())
+            .take_while(|line| line.starts_with('│'))
+            .collect();
+        assert!(body.len() > 1);
+        assert!(body[0].starts_with("│  a  │ one two"));
+        assert!(body[0].ends_with("│ 7 │"));
+        let text: Vec<&str> = body
+            .iter()
+            .flat_map(|line| line.split('│').nth(2))
+            .collect();
+        assert_eq!(
+            text.join(" ")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            "one two three four five six"
+        );
+
+        let natural = render(source);
+        assert_eq!(
+            natural.lines[3].plain,
+            "│  a  │ one two three four five six │ 7 │"
+        );
+        assert!(natural.lines[1].plain.starts_with("│ Key │"));
+    }
+
+    #[test]
+    fn tables_in_quotes_and_lists_fit_under_their_prefix() {
+        let source = "> | Key | Description |\n> |---|---|\n> | a | one two three four |\n";
+        let doc = render_at(source, Some(24));
+        let table: Vec<&str> = doc
+            .lines
+            .iter()
+            .map(|line| line.plain.as_str())
+            .filter(|line| !line.is_empty())
+            .collect();
+        assert!(table.len() > 5);
+        assert!(table[0].starts_with("> ┌"));
+        assert!(table.last().is_some_and(|line| line.starts_with("> └")));
+        assert!(table.iter().all(|line| line.starts_with("> ")));
+        assert!(table.iter().all(|line| line.width() <= 24));
+        assert!(table[1..table.len() - 1]
+            .iter()
+            .all(|line| line.ends_with(" │") || line.ends_with('┤')));
+
+        let doc = render("- item\n\n  | a | b |\n  |---|---|\n  | x | y |\n");
+        let top = doc
+            .lines
+            .iter()
+            .find(|line| line.plain.contains('┌'))
+            .unwrap();
+        assert_eq!(top.plain, "  ┌───┬───┐");
+    }
+
+    #[test]
+    fn tables_truncate_cells_when_words_cannot_fit() {
+        let source =
+            "> | Name | Description |\n> |---|---|\n> | alpha | extraordinarily lengthy |\n";
+        let doc = render_at(source, Some(20));
+        let table: Vec<&str> = doc
+            .lines
+            .iter()
+            .map(|line| line.plain.as_str())
+            .filter(|line| !line.is_empty())
+            .collect();
+        // Top border, header, separator, one body row and bottom border.
+        assert_eq!(table.len(), 5);
+        assert!(table.iter().all(|line| line.width() <= 20));
+        assert!(doc.lines.iter().all(|line| !line.wrapped));
+        assert!(table[3].starts_with("> │ alp"));
+        assert!(table[3].contains('…'));
+        assert!(table[3].ends_with(" │"));
+    }
+
+    #[test]
+    fn wrap_segments_breaks_words_and_long_tokens() {
+        let segments = vec![StyledSegment {
+            text: "alpha beta gammadelta".to_string(),
+            style: Style::default(),
+        }];
+        let lines: Vec<String> = wrap_segments(&segments, 6)
+            .iter()
+            .map(|line| line.iter().map(|s| s.text.as_str()).collect())
+            .collect();
+        assert_eq!(lines, vec!["alpha", "beta", "gammad", "elta"]);
+    }
+
+    #[test]
+    fn render_markdown_wraps_with_hanging_indents_at_width() {
+        let source = "# A heading long enough to wrap\n\n- first item with enough words to wrap [link](x.md) here\n\n> quoted text that also needs to wrap around\n";
+        let doc = render_at(source, Some(24));
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert!(plain.iter().all(|line| line.width() <= 24), "{plain:?}");
+
+        assert_eq!(doc.toc[0].line, 0);
+        assert_eq!(doc.toc[0].title, "A heading long enough to wrap");
+        assert!(doc.lines[1].wrapped);
+
+        let item = plain
+            .iter()
+            .position(|line| line.starts_with("- first"))
+            .unwrap();
+        assert!(doc.lines[item + 1].wrapped);
+        assert!(plain[item + 1].starts_with("  ") && !plain[item + 1].starts_with("   "));
+        let link_line = doc.links[0].line;
+        assert!(plain[link_line].contains("link"));
+        assert_eq!(doc.lines[link_line].source.as_ref().unwrap().start_line, 2);
+
+        let quote: Vec<&&str> = plain.iter().filter(|line| line.starts_with("> ")).collect();
+        assert!(quote.len() > 1);
+        assert_eq!(
+            doc.line_for_source_line(4),
+            Some(
+                plain
+                    .iter()
+                    .position(|line| line.starts_with("> quoted"))
+                    .unwrap()
+            )
+        );
+
+        let natural = render(source);
+        assert!(natural.lines.iter().all(|line| !line.wrapped));
+    }
+
+    #[test]
+    fn footnotes_render_in_a_numbered_section_with_anchors() {
+        let source = "Intro[^b] and[^a].\n\n[^a]: Alpha with [docs](https://example.com).\n\nMiddle.\n\n[^b]: Beta.\n";
+        let doc = render(source);
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert_eq!(
+            plain,
+            vec![
+                "Intro[1] and[2].",
+                "",
+                "Middle.",
+                "",
+                "Footnotes",
+                "[1] Beta.",
+                "[2] Alpha with docs.",
+            ]
+        );
+
+        let targets: Vec<&str> = doc.links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, vec!["#fn-b", "#fn-a", "https://example.com"]);
+        assert_eq!(doc.links[0].line, 0);
+        assert_eq!(doc.links[2].line, 6);
+        assert_eq!(doc.anchor_line("fn-b"), Some(5));
+        assert_eq!(doc.anchor_line("fn-a"), Some(6));
+        assert_eq!(doc.lines[6].source.as_ref().unwrap().start_line, 2);
+    }
+
+    #[test]
+    fn footnote_blocks_point_into_the_footnotes_section() {
+        let source = "Intro[^n].\n\n[^n]: Note.\n\n    ## Deep\n\n    > [!NOTE]\n    > Careful.\n\n    <details>\n    <summary>More</summary>\n\n    Hidden.\n\n    </details>\n\n# After\n\nEnd.\n";
+        let doc = render(source);
+        let line_of = |text: &str| {
+            doc.lines
+                .iter()
+                .position(|line| line.plain.contains(text))
+                .unwrap()
+        };
+
+        let toc: Vec<(&str, usize)> = doc
+            .toc
+            .iter()
+            .map(|entry| (entry.title.as_str(), entry.line))
+            .collect();
+        assert_eq!(
+            toc,
+            vec![("After", line_of("After")), ("Deep", line_of("Deep"))]
+        );
+        assert_eq!(doc.anchor_line("deep"), Some(line_of("Deep")));
+        assert_eq!(doc.callouts[0].line, line_of("ℹ Note"));
+        assert!(doc.callouts[0].line > line_of("Footnotes"));
+        assert_eq!(doc.details[0].line, line_of("More"));
+        assert!(doc.is_hidden(line_of("Hidden")));
+        assert!(!doc.is_hidden(line_of("End.")));
+    }
+
+    #[test]
+    fn front_matter_is_parsed_and_hidden_from_the_body() {
+        let doc = render("---\ntitle: Plan\nstatus: draft\n---\n# Body\n\ntext\n");
+        let meta = doc.front_matter.as_ref().unwrap();
+        assert_eq!(meta.format, FrontMatterFormat::Yaml);
+        assert_eq!(meta.title(), Some("Plan"));
+        assert_eq!(meta.status(), Some("draft"));
+        assert_eq!(doc.lines[0].plain, "Body");
+        assert_eq!(doc.toc[0].source.start_line, 4);
+
+        let doc = render("+++\ntitle = \"Spec\"\n+++\ntext\n");
+        assert_eq!(
+            doc.front_matter.as_ref().map(|meta| meta.format),
+            Some(FrontMatterFormat::Toml)
+        );
+        assert_eq!(doc.lines[0].plain, "text");
+        assert!(render("text\n\n---\n\nmore\n").front_matter.is_none());
+    }
+
+    #[test]
+    fn gfm_alerts_render_as_callouts() {
+        let doc = render("> [!IMPORTANT]\n> Ship the **migration** first.\n\n> plain quote\n");
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert_eq!(plain[0], "┃ ‼ Important");
+        assert_eq!(plain[1], "┃ Ship the migration first.");
+        assert!(plain.iter().all(|line| !line.contains("[!IMPORTANT]")));
+        assert!(plain.contains(&"> plain quote"));
+
+        assert_eq!(doc.callouts.len(), 1);
+        let callout = &doc.callouts[0];
+        assert_eq!(callout.kind, CalloutKind::Important);
+        assert_eq!(callout.line, 0);
+        assert_eq!(callout.summary, "Ship the migration first.");
+        assert_eq!(callout.source.start_line, 0);
+    }
+
+    #[test]
+    fn inline_and_block_html_render_without_tags() {
+        let source = "Press <kbd>Ctrl</kbd>+<code>c</code><br>now <!-- note -->ok\n\n<!--\nhidden\n-->\n\n<p>\n  <img src=\"a.png\" alt=\"Logo &amp; mark\">\n</p>\n";
+        let doc = render(source);
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert_eq!(
+            plain,
+            vec![
+                "Press Ctrl+c",
+                "now ok",
+                "",
+                "[image: Logo & mark] (a.png)",
+                ""
+            ]
+        );
+        let kbd = doc.lines[0]
+            .segments
+            .iter()
+            .find(|segment| segment.text == "Ctrl")
+            .unwrap();
+        assert_eq!(kbd.style.bg, Some(Color::DarkGray));
+
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = Theme::default();
+        let options = RenderOptions {
+            show_comments: true,
+            ..RenderOptions::default()
+        };
+        let doc = render_markdown(source, &syntax_set, &theme, &options);
+        let plain: Vec<&str> = doc.lines.iter().map(|line| line.plain.as_str()).collect();
+        assert_eq!(
+            &plain[..5],
+            ["Press Ctrl+c", "now <!-- note -->ok", "", "<!--", "hidden"]
+        );
+    }
+
+    #[test]
+    fn details_blocks_collapse_unless_expanded() {
+        let source = "<details>\n<summary>More <b>info</b></summary>\n\n## Inside\n\nHidden [link](a.md).\n\n</details>\n\nAfter\n";
+        let doc = render(source);
+        let shown: Vec<&str> = doc
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !doc.is_hidden(*idx))
+            .map(|(_, line)| line.plain.as_str())
+            .collect();
+        assert_eq!(shown, vec!["▸ More info", "", "After", ""]);
+        assert_eq!(doc.details.len(), 1);
+        assert!(!doc.details[0].open);
+        assert_eq!(doc.details[0].summary, "More info");
+
+        // The hidden body is still indexed.
+        assert_eq!(doc.toc[0].title, "Inside");
+        assert_eq!(doc.links.len(), 1);
+        let link_line = doc.links[0].line;
+        assert_eq!(doc.lines[link_line].plain, "Hidden link.");
+        assert!(doc.is_hidden(link_line));
+        assert_eq!(doc.shown_line(link_line), 0);
+        assert_eq!(doc.step_shown(0, 2, false), doc.lines.len() - 2);
+        let key = DetailsKey {
+            summary: "More info".to_string(),
+            occurrence: 0,
+        };
+        assert_eq!(doc.collapsed_around(link_line), vec![key.clone()]);
+
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = Theme::default();
+        let options = RenderOptions {
+            expanded_details: HashSet::from([key]),
+            ..RenderOptions::default()
+        };
+        let expanded = render_markdown(source, &syntax_set, &theme, &options);
+        assert_eq!(expanded.lines.len(), doc.lines.len());
+        assert_eq!(expanded.lines[0].plain, "▾ More info");
+        assert_eq!(expanded.links[0].line, link_line);
+        assert!(expanded.details[0].open);
+        assert!(!expanded.is_hidden(link_line));
+    }
+
+    #[test]
+    fn details_keys_count_repeated_summaries() {
+        let source = "<details>\n<summary>Log</summary>\n\nfirst\n\n</details>\n\n<details>\n<summary>Log</summary>\n\nsecond\n\n</details>\n";
+        let doc = render(source);
+        let keys: Vec<_> = doc
+            .details
+            .iter()
+            .map(|details| (details.key.summary.as_str(), details.key.occurrence))
+            .collect();
+        assert_eq!(keys, vec![("Log", 0), ("Log", 1)]);
     }
 }
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use syntect::highlighting::Theme;
+use syntect::parsing::SyntaxSet;
+
+use crate::frontmatter::{FrontMatter, FrontMatterFormat};
+use crate::input::{MetaArgs, OutputFormat};
+use crate::markdown::{render_markdown, RenderOptions};
+use crate::tasks::collect_markdown_files;
+
+#[derive(Debug, Serialize)]
+struct MetaRecord {
+    file: String,
+    format: Option<FrontMatterFormat>,
+    title: Option<String>,
+    status: Option<String>,
+    owners: Vec<String>,
+    tags: Vec<String>,
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl MetaRecord {
+    fn new(file: &Path, front_matter: Option<&FrontMatter>) -> Self {
+        Self {
+            file: file.display().to_string(),
+            format: front_matter.map(|meta| meta.format),
+            title: front_matter
+                .and_then(FrontMatter::title)
+                .map(str::to_string),
+            status: front_matter
+                .and_then(FrontMatter::status)
+                .map(str::to_string),
+            owners: front_matter.map(FrontMatter::owners).unwrap_or_default(),
+            tags: front_matter.map(FrontMatter::tags).unwrap_or_default(),
+            fields: front_matter
+                .map(|meta| meta.fields.iter().cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn matches(&self, args: &MetaArgs) -> bool {
+        let any = |values: &[String], wanted: &str| {
+            values
+                .iter()
+                .any(|value| value.eq_ignore_ascii_case(wanted))
+        };
+        args.status
+            .as_deref()
+            .is_none_or(|wanted| any(self.status.as_slice(), wanted))
+            && args
+                .owner
+                .as_deref()
+                .is_none_or(|wanted| any(&self.owners, wanted))
+            && args
+                .tag
+                .as_deref()
+                .is_none_or(|wanted| any(&self.tags, wanted))
+    }
+}
+
+/// Runs `catmd meta`.
+pub(crate) fn run_meta(args: &MetaArgs, syntax_set: &SyntaxSet, theme: &Theme) -> Result<()> {
+    let mut records = Vec::new();
+    for file in collect_markdown_files(&args.paths)? {
+        let source = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let rendered = render_markdown(&source, syntax_set, theme, &RenderOptions::default());
+        let record = MetaRecord::new(&file, rendered.front_matter.as_ref());
+        if record.matches(args) {
+            records.push(record);
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match args.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in &records {
+                serde_json::to_writer(&mut out, record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Table => write_table(&mut out, &records)?,
+    }
+
+    Ok(())
+}
+
+fn write_table(out: &mut impl Write, records: &[MetaRecord]) -> io::Result<()> {
+    let width = records
+        .iter()
+        .map(|record| record.file.len())
+        .max()
+        .unwrap_or(0);
+    for record in records {
+        let row = format!(
+            "{:<width$}  {:<11}  {}",
+            record.file,
+            record.status.as_deref().unwrap_or("-"),
+            record.title.as_deref().unwrap_or("")
+        );
+        writeln!(out, "{}", row.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn args(status: Option<&str>, owner: Option<&str>, tag: Option<&str>) -> MetaArgs {
+        MetaArgs {
+            paths: vec![PathBuf::from(".")],
+            status: status.map(str::to_string),
+            owner: owner.map(str::to_string),
+            tag: tag.map(str::to_string),
+            format: OutputFormat::Table,
+        }
+    }
+
+    fn record(yaml: &str) -> MetaRecord {
+        let meta = FrontMatter::parse(FrontMatterFormat::Yaml, yaml);
+        MetaRecord::new(Path::new("plan.md"), Some(&meta))
+    }
+
+    #[test]
+    fn meta_record_reads_front_matter() {
+        let record = record("title: Plan\nstatus: Draft\nowners: [ana, bo]\ntags: infra, q3\n");
+        assert_eq!(record.title.as_deref(), Some("Plan"));
+        assert_eq!(record.status.as_deref(), Some("Draft"));
+        assert_eq!(record.owners, vec!["ana", "bo"]);
+        assert_eq!(record.tags, vec!["infra", "q3"]);
+        assert_eq!(record.fields.len(), 4);
+
+        let bare = MetaRecord::new(Path::new("notes.md"), None);
+        assert!(bare.format.is_none() && bare.owners.is_empty() && bare.fields.is_empty());
+    }
+
+    #[test]
+    fn meta_filters_match_any_owner_and_tag() {
+        let record = record("status: Draft\nowners:\n  - ana\n  - bo\ntags: [infra, q3]\n");
+        assert!(record.matches(&args(None, None, None)));
+        assert!(record.matches(&args(Some("draft"), Some("BO"), Some("q3"))));
+        assert!(record.matches(&args(None, Some("ana"), None)));
+        assert!(!record.matches(&args(None, Some("cy"), None)));
+        assert!(!record.matches(&args(Some("done"), None, None)));
+        assert!(!record.matches(&args(None, None, Some("api"))));
+        assert!(!MetaRecord::new(Path::new("notes.md"), None).matches(&args(
+            None,
+            Some("ana"),
+            None
+        )));
+    }
+}
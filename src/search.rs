@@ -0,0 +1,223 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::markdown::RenderedLine;
+
+/// Search modes toggled from the `/` prompt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SearchFlags {
+    pub(crate) regex: bool,
+    pub(crate) whole_word: bool,
+}
+
+/// One occurrence of the query: a byte range of a rendered line's text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SearchMatch {
+    pub(crate) line: usize,
+    pub(crate) range: Range<usize>,
+}
+
+/// A compiled query. Leading `\v` turns on regex syntax for this query,
+/// `\c` / `\C` force case-insensitive / case-sensitive matching; otherwise
+/// matching is smartcase (case-sensitive only if the query has uppercase).
+#[derive(Clone, Debug)]
+pub(crate) struct SearchPattern {
+    regex: Regex,
+    pub(crate) is_regex: bool,
+    pub(crate) case_sensitive: bool,
+}
+
+impl SearchPattern {
+    /// Returns `Ok(None)` for a query without any pattern text.
+    pub(crate) fn compile(query: &str, flags: SearchFlags) -> Result<Option<Self>, regex::Error> {
+        let mut is_regex = flags.regex;
+        let mut case = None;
+        let mut pattern = query;
+        loop {
+            if let Some(rest) = pattern.strip_prefix("\\v") {
+                is_regex = true;
+                pattern = rest;
+            } else if let Some(rest) = pattern.strip_prefix("\\c") {
+                case = Some(false);
+                pattern = rest;
+            } else if let Some(rest) = pattern.strip_prefix("\\C") {
+                case = Some(true);
+                pattern = rest;
+            } else {
+                break;
+            }
+        }
+        if pattern.is_empty() {
+            return Ok(None);
+        }
+
+        let case_sensitive = case.unwrap_or_else(|| pattern.chars().any(char::is_uppercase));
+        let body = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let body = if flags.whole_word {
+            format!(r"\b(?:{body})\b")
+        } else {
+            body
+        };
+        let regex = RegexBuilder::new(&body)
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        Ok(Some(Self {
+            regex,
+            is_regex,
+            case_sensitive,
+        }))
+    }
+
+    /// Non-empty occurrences in `lines`, in document order.
+    pub(crate) fn find_all(&self, lines: &[RenderedLine]) -> Vec<SearchMatch> {
+        lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line, rendered)| {
+                self.regex
+                    .find_iter(&rendered.plain)
+                    .filter(|found| !found.is_empty())
+                    .map(move |found| SearchMatch {
+                        line,
+                        range: found.range(),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Past queries, oldest first, recalled with Up / Down at the `/` prompt.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SearchHistory {
+    entries: Vec<String>,
+    /// Entry shown while browsing; `None` while editing a fresh query.
+    cursor: Option<usize>,
+    /// The query being typed before browsing started.
+    draft: String,
+}
+
+const SEARCH_HISTORY_LIMIT: usize = 100;
+
+impl SearchHistory {
+    /// Records `query` as the newest entry, dropping an older duplicate.
+    pub(crate) fn push(&mut self, query: &str) {
+        self.cursor = None;
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != query);
+        self.entries.push(query.to_string());
+        if self.entries.len() > SEARCH_HISTORY_LIMIT {
+            self.entries.remove(0);
+        }
+    }
+
+    pub(crate) fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Steps to an older entry, remembering `current` as the draft when
+    /// browsing starts.
+    pub(crate) fn older(&mut self, current: &str) -> Option<&str> {
+        let next = match self.cursor {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.cursor = Some(next);
+        self.entries.get(next).map(String::as_str)
+    }
+
+    /// Steps to a newer entry, ending with the draft.
+    pub(crate) fn newer(&mut self) -> Option<&str> {
+        let idx = self.cursor?;
+        if idx.saturating_add(1) >= self.entries.len() {
+            self.cursor = None;
+            return Some(&self.draft);
+        }
+        self.cursor = Some(idx.saturating_add(1));
+        self.entries.get(idx.saturating_add(1)).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(texts: &[&str]) -> Vec<RenderedLine> {
+        texts
+            .iter()
+            .map(|text| RenderedLine {
+                plain: (*text).to_string(),
+                ..RenderedLine::default()
+            })
+            .collect()
+    }
+
+    fn ranges(query: &str, flags: SearchFlags, texts: &[&str]) -> Vec<(usize, Range<usize>)> {
+        SearchPattern::compile(query, flags)
+            .unwrap()
+            .map(|pattern| pattern.find_all(&lines(texts)))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|found| (found.line, found.range))
+            .collect()
+    }
+
+    #[test]
+    fn smartcase_literal_and_unicode_folding() {
+        let plain = SearchFlags::default();
+        let text = ["Straße and STRASSE", "ÉTÉ été"];
+        assert_eq!(ranges("été", plain, &text), vec![(1, 0..5), (1, 6..11)]);
+        assert_eq!(ranges("Été", plain, &text), Vec::new());
+        assert_eq!(ranges("ÉTÉ", plain, &text), vec![(1, 0..5)]);
+        assert_eq!(ranges("\\Cété", plain, &text), vec![(1, 6..11)]);
+        assert_eq!(ranges("\\cÉTÉ", plain, &text), vec![(1, 0..5), (1, 6..11)]);
+        // Literal mode escapes regex syntax.
+        assert_eq!(ranges("a.d", plain, &["and a.d"]), vec![(0, 4..7)]);
+        assert!(SearchPattern::compile("\\v\\c", plain).unwrap().is_none());
+    }
+
+    #[test]
+    fn regex_and_whole_word_modes() {
+        let text = ["cat catalog cat-like", "bobcat 42"];
+        let word = SearchFlags {
+            whole_word: true,
+            ..SearchFlags::default()
+        };
+        assert_eq!(ranges("cat", word, &text), vec![(0, 0..3), (0, 12..15)]);
+        assert_eq!(
+            ranges("\\v\\d+|cat\\w+", SearchFlags::default(), &text),
+            vec![(0, 4..11), (1, 7..9)]
+        );
+        let regex = SearchFlags {
+            regex: true,
+            ..SearchFlags::default()
+        };
+        assert_eq!(ranges("^b\\w+", regex, &text), vec![(1, 0..6)]);
+        assert!(SearchPattern::compile("(", regex).is_err());
+        // Empty matches are skipped instead of highlighting every position.
+        assert_eq!(ranges("x*", regex, &text), Vec::new());
+    }
+
+    #[test]
+    fn history_browses_older_entries_and_returns_to_draft() {
+        let mut history = SearchHistory::default();
+        history.push("alpha");
+        history.push("beta");
+        history.push("alpha");
+        assert_eq!(history.older("dra"), Some("alpha"));
+        assert_eq!(history.older("alpha"), Some("beta"));
+        assert_eq!(history.older("beta"), Some("beta"));
+        assert_eq!(history.newer(), Some("alpha"));
+        assert_eq!(history.newer(), Some("dra"));
+        assert_eq!(history.newer(), None);
+    }
+}
@@ -0,0 +1,287 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use serde::Serialize;
+use syntect::highlighting::Theme;
+use syntect::parsing::SyntaxSet;
+
+use crate::agent::{extract_agent_tasks, AgentTask};
+use crate::input::{OutputFormat, TasksArgs};
+use crate::links::is_markdown_path;
+use crate::markdown::{render_markdown, RenderOptions};
+
+#[derive(Debug, Serialize)]
+struct TaskRecord {
+    file: String,
+    line: Option<usize>,
+    state: &'static str,
+    text: String,
+    owners: Vec<String>,
+    priority: Option<u8>,
+    due: Option<String>,
+    id: Option<String>,
+}
+
+impl TaskRecord {
+    fn new(file: &Path, task: AgentTask) -> Self {
+        Self {
+            file: file.display().to_string(),
+            line: task.source_line().map(|line| line.saturating_add(1)),
+            state: task.state.label(),
+            text: task.text,
+            owners: task.owners,
+            priority: task.priority,
+            due: task.due,
+            id: task.id,
+        }
+    }
+}
+
+fn collect_dir(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?;
+
+    // Symlinked directories are followed; the walker reports loops as
+    // errors, which are skipped along with other unreadable entries.
+    let walker = WalkBuilder::new(dir)
+        .require_git(false)
+        .follow_links(true)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if entry.file_type().is_some_and(|kind| kind.is_file()) && is_markdown_path(entry.path()) {
+            out.push(entry.into_path());
+        }
+    }
+    Ok(())
+}
+
+/// Expands the given paths into markdown files. Files are taken as given;
+/// directories contribute every `.md`/`.markdown`/`.mdx` file beneath them,
+/// skipping hidden and gitignored entries.
+pub(crate) fn collect_markdown_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_dir(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// Collects the tasks `args` asks for, plus whether any unresolved task
+/// remains in the scanned files (regardless of the `--open`/`--done` filter).
+fn collect_tasks(
+    args: &TasksArgs,
+    syntax_set: &SyntaxSet,
+    theme: &Theme,
+) -> Result<(Vec<TaskRecord>, bool)> {
+    let mut records = Vec::new();
+    let mut open_remaining = false;
+
+    for file in collect_markdown_files(&args.paths)? {
+        let source = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        // Tasks inside collapsed `<details>` blocks still count.
+        let options = RenderOptions {
+            expand_details: true,
+            ..RenderOptions::default()
+        };
+        let rendered = render_markdown(&source, syntax_set, theme, &options);
+        for task in extract_agent_tasks(&rendered) {
+            open_remaining |= task.is_open();
+            let keep = if args.open {
+                task.is_open()
+            } else if args.done {
+                !task.is_open()
+            } else {
+                true
+            };
+            if keep {
+                records.push(TaskRecord::new(&file, task));
+            }
+        }
+    }
+    Ok((records, open_remaining))
+}
+
+/// Status 1 while unresolved tasks remain, unless `--exit-zero` is given.
+fn exit_code(args: &TasksArgs, open_remaining: bool) -> ExitCode {
+    if open_remaining && !args.exit_zero {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Runs `catmd tasks` and returns the process exit code.
+pub(crate) fn run_tasks(
+    args: &TasksArgs,
+    syntax_set: &SyntaxSet,
+    theme: &Theme,
+) -> Result<ExitCode> {
+    let (records, open_remaining) = collect_tasks(args, syntax_set, theme)?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match args.format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in &records {
+                serde_json::to_writer(&mut out, record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Table => write_table(&mut out, &records)?,
+    }
+
+    Ok(exit_code(args, open_remaining))
+}
+
+fn write_table(out: &mut impl Write, records: &[TaskRecord]) -> io::Result<()> {
+    let locations: Vec<String> = records
+        .iter()
+        .map(|record| match record.line {
+            Some(line) => format!("{}:{line}", record.file),
+            None => record.file.clone(),
+        })
+        .collect();
+    let width = locations.iter().map(String::len).max().unwrap_or(0);
+
+    for (record, location) in records.iter().zip(&locations) {
+        writeln!(
+            out,
+            "{location:<width$}  {:<11}  {}",
+            record.state, record.text
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use syntect::highlighting::ThemeSet;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("catmd-tasks-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn args(paths: Vec<PathBuf>) -> TasksArgs {
+        TasksArgs {
+            paths,
+            open: false,
+            done: false,
+            format: OutputFormat::Table,
+            exit_zero: false,
+        }
+    }
+
+    fn collect(args: &TasksArgs) -> (Vec<TaskRecord>, bool) {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme = ThemeSet::load_defaults()
+            .themes
+            .into_values()
+            .next()
+            .unwrap_or_default();
+        collect_tasks(args, &syntax_set, &theme).unwrap()
+    }
+
+    #[test]
+    fn collect_tasks_reads_states_lines_and_collapsed_details() {
+        let dir = temp_dir("parse");
+        let file = dir.join("plan.md");
+        fs::write(
+            &file,
+            "# Plan\n\n\
+             - [ ] @agent write docs !p2\n\
+             - [x] @agent ship it\n\n\
+             <details>\n<summary>Later</summary>\n\n\
+             - [~] @agent:reviewer check links due:2026-11-01\n\n\
+             </details>\n",
+        )
+        .unwrap();
+
+        let (records, open_remaining) = collect(&args(vec![file.clone()]));
+        assert!(open_remaining);
+        let summary: Vec<_> = records
+            .iter()
+            .map(|record| (record.line, record.state, record.text.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some(3), "open", "@agent write docs !p2"),
+                (Some(4), "done", "@agent ship it"),
+                (
+                    Some(9),
+                    "in-progress",
+                    "@agent:reviewer check links due:2026-11-01"
+                ),
+            ]
+        );
+        assert_eq!(records[0].priority, Some(2));
+        assert_eq!(records[2].owners, vec!["reviewer"]);
+        assert_eq!(records[2].due.as_deref(), Some("2026-11-01"));
+
+        let mut done_only = args(vec![file]);
+        done_only.done = true;
+        let (records, open_remaining) = collect(&done_only);
+        assert_eq!(records.len(), 1);
+        assert!(open_remaining);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn collect_markdown_files_skips_ignored_entries_and_symlink_loops() {
+        let dir = temp_dir("walk");
+        for file in [
+            "a.md",
+            "sub/b.markdown",
+            "sub/notes.txt",
+            ".hidden/c.md",
+            "build/d.md",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "- [ ] @agent task\n").unwrap();
+        }
+        fs::write(dir.join(".gitignore"), "build/\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop")).unwrap();
+
+        let files = collect_markdown_files(std::slice::from_ref(&dir)).unwrap();
+        assert_eq!(files, vec![dir.join("a.md"), dir.join("sub/b.markdown")]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exit_code_fails_while_open_tasks_remain() {
+        let dir = temp_dir("exit");
+        fs::write(dir.join("open.md"), "- [ ] @agent pending\n").unwrap();
+        fs::write(dir.join("done.md"), "- [x] @agent finished\n").unwrap();
+
+        let mut open = args(vec![dir.join("open.md")]);
+        let (_, open_remaining) = collect(&open);
+        assert_eq!(exit_code(&open, open_remaining), ExitCode::FAILURE);
+        open.exit_zero = true;
+        assert_eq!(exit_code(&open, open_remaining), ExitCode::SUCCESS);
+
+        let done = args(vec![dir.join("done.md")]);
+        let (_, open_remaining) = collect(&done);
+        assert_eq!(exit_code(&done, open_remaining), ExitCode::SUCCESS);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
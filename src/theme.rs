@@ -0,0 +1,251 @@
+use clap::ValueEnum;
+use ratatui::prelude::Color;
+use serde::Deserialize;
+
+/// Colors for everything catmd draws itself; code blocks use the syntect
+/// theme instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Palette {
+    /// h1, h2, and h3 and below.
+    pub(crate) headings: [Color; 3],
+    /// Links, footnote references and `<details>` markers.
+    pub(crate) link: Color,
+    pub(crate) inline_code: Color,
+    /// Code blocks the syntax theme has no tokens for.
+    pub(crate) code_block: Color,
+    /// Image placeholders and hunk markers of settled changes.
+    pub(crate) info: Color,
+    pub(crate) kbd_fg: Color,
+    pub(crate) kbd_bg: Color,
+    /// Note, tip, important, warning and caution callouts.
+    pub(crate) callouts: [Color; 5],
+    /// Borders, list and quote markers, rules, comments and done tasks.
+    pub(crate) muted: Color,
+    /// Status bar and secondary panel text.
+    pub(crate) subtle: Color,
+    /// Selected panel rows, section titles and table headers.
+    pub(crate) highlight: Color,
+    /// Popup borders and the open agent task marker.
+    pub(crate) accent: Color,
+    /// Agent task badges.
+    pub(crate) badge: Color,
+    pub(crate) task_open_bg: Color,
+    pub(crate) task_in_progress_bg: Color,
+    /// Marker of freshly changed lines.
+    pub(crate) changed: Color,
+    pub(crate) changed_bg: Color,
+    pub(crate) changed_dim_bg: Color,
+    /// Search matches; the current one uses `search_current_bg`.
+    pub(crate) search_fg: Color,
+    pub(crate) search_bg: Color,
+    pub(crate) search_current_bg: Color,
+    pub(crate) selection_fg: Color,
+    pub(crate) selection_bg: Color,
+    pub(crate) added: Color,
+    pub(crate) added_bg: Color,
+    pub(crate) removed: Color,
+    pub(crate) removed_bg: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        ThemePreset::Dark.palette()
+    }
+}
+
+/// Built-in themes: a UI palette plus the syntect theme used for code
+/// blocks unless the config file names another one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    Solarized,
+    HighContrast,
+}
+
+impl ThemePreset {
+    pub(crate) const ALL: [Self; 4] =
+        [Self::Dark, Self::Light, Self::Solarized, Self::HighContrast];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::Solarized => "solarized",
+            Self::HighContrast => "high-contrast",
+        }
+    }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Self::Dark => "ANSI colors for dark terminals (default)",
+            Self::Light => "darker tones for light backgrounds",
+            Self::Solarized => "Solarized dark",
+            Self::HighContrast => "bright colors and strong highlights",
+        }
+    }
+
+    pub(crate) fn syntax_theme(self) -> &'static str {
+        match self {
+            Self::Dark => "base16-ocean.dark",
+            Self::Light => "InspiredGitHub",
+            Self::Solarized => "Solarized (dark)",
+            Self::HighContrast => "base16-eighties.dark",
+        }
+    }
+
+    pub(crate) fn palette(self) -> Palette {
+        match self {
+            Self::Dark => Palette {
+                headings: [Color::Yellow, Color::LightMagenta, Color::LightCyan],
+                link: Color::Cyan,
+                inline_code: Color::LightYellow,
+                code_block: Color::LightGreen,
+                info: Color::LightBlue,
+                kbd_fg: Color::White,
+                kbd_bg: Color::DarkGray,
+                callouts: [
+                    Color::LightBlue,
+                    Color::LightGreen,
+                    Color::LightMagenta,
+                    Color::Yellow,
+                    Color::LightRed,
+                ],
+                muted: Color::DarkGray,
+                subtle: Color::Gray,
+                highlight: Color::Yellow,
+                accent: Color::Cyan,
+                badge: Color::Magenta,
+                task_open_bg: Color::Rgb(16, 52, 44),
+                task_in_progress_bg: Color::Rgb(52, 46, 16),
+                changed: Color::LightRed,
+                changed_bg: Color::Rgb(70, 35, 0),
+                changed_dim_bg: Color::Rgb(36, 36, 36),
+                search_fg: Color::Black,
+                search_bg: Color::Yellow,
+                search_current_bg: Color::LightRed,
+                selection_fg: Color::White,
+                selection_bg: Color::Blue,
+                added: Color::LightGreen,
+                added_bg: Color::Rgb(20, 50, 20),
+                removed: Color::LightRed,
+                removed_bg: Color::Rgb(60, 20, 20),
+            },
+            Self::Light => Palette {
+                headings: [
+                    Color::Rgb(0, 95, 175),
+                    Color::Rgb(135, 0, 135),
+                    Color::Rgb(0, 120, 120),
+                ],
+                link: Color::Rgb(0, 95, 215),
+                inline_code: Color::Rgb(175, 0, 0),
+                code_block: Color::Rgb(0, 110, 0),
+                info: Color::Rgb(0, 95, 175),
+                kbd_fg: Color::Black,
+                kbd_bg: Color::Rgb(218, 218, 218),
+                callouts: [
+                    Color::Rgb(0, 95, 175),
+                    Color::Rgb(0, 125, 0),
+                    Color::Rgb(135, 0, 135),
+                    Color::Rgb(175, 95, 0),
+                    Color::Rgb(190, 0, 0),
+                ],
+                muted: Color::Rgb(138, 138, 138),
+                subtle: Color::Rgb(88, 88, 88),
+                highlight: Color::Rgb(175, 95, 0),
+                accent: Color::Rgb(0, 120, 160),
+                badge: Color::Rgb(135, 0, 135),
+                task_open_bg: Color::Rgb(215, 245, 230),
+                task_in_progress_bg: Color::Rgb(250, 240, 200),
+                changed: Color::Rgb(215, 0, 0),
+                changed_bg: Color::Rgb(255, 225, 190),
+                changed_dim_bg: Color::Rgb(235, 235, 235),
+                search_fg: Color::Black,
+                search_bg: Color::Rgb(255, 235, 120),
+                search_current_bg: Color::Rgb(255, 165, 80),
+                selection_fg: Color::Black,
+                selection_bg: Color::Rgb(175, 215, 255),
+                added: Color::Rgb(0, 125, 0),
+                added_bg: Color::Rgb(220, 255, 220),
+                removed: Color::Rgb(175, 0, 0),
+                removed_bg: Color::Rgb(255, 220, 220),
+            },
+            Self::Solarized => {
+                let yellow = Color::Rgb(181, 137, 0);
+                let orange = Color::Rgb(203, 75, 22);
+                let red = Color::Rgb(220, 50, 47);
+                let magenta = Color::Rgb(211, 54, 130);
+                let violet = Color::Rgb(108, 113, 196);
+                let blue = Color::Rgb(38, 139, 210);
+                let cyan = Color::Rgb(42, 161, 152);
+                let green = Color::Rgb(133, 153, 0);
+                Palette {
+                    headings: [yellow, magenta, cyan],
+                    link: blue,
+                    inline_code: orange,
+                    code_block: green,
+                    info: violet,
+                    kbd_fg: Color::Rgb(238, 232, 213),
+                    kbd_bg: Color::Rgb(7, 54, 66),
+                    callouts: [blue, green, violet, yellow, red],
+                    muted: Color::Rgb(88, 110, 117),
+                    subtle: Color::Rgb(131, 148, 150),
+                    highlight: yellow,
+                    accent: cyan,
+                    badge: magenta,
+                    task_open_bg: Color::Rgb(7, 54, 48),
+                    task_in_progress_bg: Color::Rgb(54, 50, 10),
+                    changed: orange,
+                    changed_bg: Color::Rgb(70, 40, 10),
+                    changed_dim_bg: Color::Rgb(7, 54, 66),
+                    search_fg: Color::Rgb(0, 43, 54),
+                    search_bg: yellow,
+                    search_current_bg: orange,
+                    selection_fg: Color::Rgb(253, 246, 227),
+                    selection_bg: blue,
+                    added: green,
+                    added_bg: Color::Rgb(20, 50, 20),
+                    removed: red,
+                    removed_bg: Color::Rgb(70, 20, 20),
+                }
+            }
+            Self::HighContrast => Palette {
+                headings: [Color::LightYellow, Color::LightMagenta, Color::LightCyan],
+                link: Color::LightCyan,
+                inline_code: Color::LightYellow,
+                code_block: Color::LightGreen,
+                info: Color::LightBlue,
+                kbd_fg: Color::Black,
+                kbd_bg: Color::White,
+                callouts: [
+                    Color::LightBlue,
+                    Color::LightGreen,
+                    Color::LightMagenta,
+                    Color::LightYellow,
+                    Color::LightRed,
+                ],
+                muted: Color::Gray,
+                subtle: Color::White,
+                highlight: Color::LightYellow,
+                accent: Color::LightCyan,
+                badge: Color::LightMagenta,
+                task_open_bg: Color::Rgb(0, 90, 60),
+                task_in_progress_bg: Color::Rgb(100, 85, 0),
+                changed: Color::LightRed,
+                changed_bg: Color::Rgb(130, 60, 0),
+                changed_dim_bg: Color::Rgb(60, 60, 60),
+                search_fg: Color::Black,
+                search_bg: Color::LightYellow,
+                search_current_bg: Color::LightMagenta,
+                selection_fg: Color::Black,
+                selection_bg: Color::LightYellow,
+                added: Color::LightGreen,
+                added_bg: Color::Rgb(0, 80, 0),
+                removed: Color::LightRed,
+                removed_bg: Color::Rgb(100, 0, 0),
+            },
+        }
+    }
+}